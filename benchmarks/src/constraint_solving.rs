@@ -0,0 +1,98 @@
+use std::{
+    f64::consts::PI,
+    time::{Duration, Instant},
+};
+
+use cad::{
+    entity::{ConstraintType, GeometricEntity},
+    sketch::Sketch,
+};
+use glm::DVec2;
+
+const MAX_SOLVER_ITERS: usize = 100_000;
+const TOLERANCE: f64 = 1e-8;
+
+/// A regular polygon with `sides` sides of length one, held together by coincident, distance and
+/// angle constraints. The corners start out scattered around their solved positions.
+fn polygon_sketch(sides: usize) -> Sketch {
+    let mut sketch = Sketch::new(format!("{}-gon", sides));
+    let radius = 0.5 / (PI / sides as f64).sin();
+    let corners: Vec<DVec2> = (0..sides)
+        .map(|i| {
+            let angle = 2.0 * PI * i as f64 / sides as f64;
+            // Deterministic so that every run solves the same problem
+            let noise = 0.1 * (i as f64 * 1.7).sin();
+            DVec2::new(angle.cos(), angle.sin()) * (radius + noise)
+        })
+        .collect();
+    let points: Vec<_> = corners
+        .iter()
+        .map(|&pos| sketch.geo_entities.insert(GeometricEntity::Point { pos }))
+        .collect();
+    let lines: Vec<_> = (0..sides)
+        .map(|i| {
+            let start = corners[i];
+            let end = corners[(i + 1) % sides];
+            sketch.geo_entities.insert(GeometricEntity::Line {
+                offset: start,
+                direction: end - start,
+            })
+        })
+        .collect();
+
+    for i in 0..sides {
+        let next = (i + 1) % sides;
+        sketch
+            .add_constraint(points[i], lines[i], ConstraintType::Coincident)
+            .unwrap();
+        sketch
+            .add_constraint(points[next], lines[i], ConstraintType::Coincident)
+            .unwrap();
+        sketch
+            .add_constraint(points[i], points[next], ConstraintType::Distance { x: 1.0 })
+            .unwrap();
+        sketch
+            .add_constraint(
+                lines[i],
+                lines[next],
+                ConstraintType::Angle {
+                    x: 2.0 * PI / sides as f64,
+                },
+            )
+            .unwrap();
+    }
+    sketch
+}
+
+/// Solves a `sides`-gon from the same starting point `iters` times and prints the average number
+/// of solver iterations and the average time spent solving
+pub fn solve_constraints(iters: usize, sides: usize, newton: bool) {
+    let initial = polygon_sketch(sides);
+    let mut total = Duration::ZERO;
+    let mut solver_iterations = 0;
+    let mut error = initial.error();
+    for _ in 0..iters {
+        let _span = tracy_client::span!("Solve");
+        let mut sketch = initial.clone();
+        let start = Instant::now();
+        let result = if newton {
+            sketch.solve_newton(MAX_SOLVER_ITERS, TOLERANCE)
+        } else {
+            sketch.solve(MAX_SOLVER_ITERS, TOLERANCE)
+        };
+        total += start.elapsed();
+        solver_iterations += result.iterations;
+        error = result.error;
+    }
+
+    let runs = iters.max(1);
+    println!(
+        "Solved a {}-gon with {} {} times, {} iterations and {:.3} ms per solve on average, final error {:e}",
+        sides,
+        if newton { "Newton" } else { "SGD" },
+        iters,
+        solver_iterations / runs,
+        (total / runs as u32).as_secs_f64() * 1000.0,
+        error
+    );
+}
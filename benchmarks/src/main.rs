@@ -3,6 +3,19 @@ use clap::{Parser, Subcommand};
 #[derive(Debug, Clone, Copy, Subcommand)]
 enum Benchmark {
     TextRendering,
+    RectRendering {
+        /// Number of rectangles drawn each frame
+        #[arg(short, long, default_value_t = 10_000)]
+        count: usize,
+    },
+    ConstraintSolving {
+        /// Number of sides of the solved polygon
+        #[arg(short, long, default_value_t = 8)]
+        sides: usize,
+        /// Use the Newton solver instead of SGD
+        #[arg(long)]
+        newton: bool,
+    },
 }
 
 #[derive(Parser, Debug, Clone, Copy)]
@@ -13,6 +26,8 @@ struct Args {
     iters: usize,
 }
 
+mod constraint_solving;
+mod rect_rendering;
 mod text_rendering;
 
 fn main() {
@@ -20,6 +35,10 @@ fn main() {
 
     match args.benchmark {
         Benchmark::TextRendering => text_rendering::render_text(args.iters),
+        Benchmark::RectRendering { count } => rect_rendering::render_rects(args.iters, count),
+        Benchmark::ConstraintSolving { sides, newton } => {
+            constraint_solving::solve_constraints(args.iters, sides, newton)
+        }
     }
 
     println!("Hello, world!");
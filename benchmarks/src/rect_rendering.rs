@@ -0,0 +1,134 @@
+use std::{
+    path::PathBuf,
+    str::FromStr as _,
+    time::{Duration, Instant},
+};
+
+use glfw::Context as _;
+use rust_ui::{
+    WindowOptions,
+    geometry::Vector,
+    init_open_gl,
+    render::{
+        graph::GraphRenderer,
+        line::LineRenderer,
+        rect::RectRenderer,
+        renderer::{Anchor, AppState, RenderLayout, Renderer},
+        sprite::{SpriteAtlas, SpriteRenderer},
+        text::TextRenderer,
+        widgets::UiBuilder,
+    },
+    shader::{Shader, ShaderName},
+};
+use taffy::{AvailableSpace, NodeId, Size};
+
+struct RectRendering {
+    pub i: usize,
+    pub count: usize,
+}
+
+impl AppState for RectRendering {
+    type SpriteKey = String;
+
+    fn generate_layout(
+        &mut self,
+        window_size: rust_ui::geometry::Vector<f32>,
+        ui: &UiBuilder<Self>,
+    ) -> Vec<rust_ui::render::renderer::RenderLayout<Self>> {
+        let rects: Vec<NodeId> = (0..self.count)
+            .map(|_| {
+                ui.div(
+                    "w-12 h-12 rounded-4 bg-zinc-700 border-1 border-zinc-500",
+                    &[] as &[NodeId],
+                )
+            })
+            .collect();
+        let root = ui.div("p-4 w-full h-full flex-row flex-wrap gap-2", &rects);
+
+        vec![RenderLayout {
+            tree: ui.tree(),
+            root,
+            desired_size: Size {
+                width: AvailableSpace::Definite(window_size.x),
+                height: AvailableSpace::Definite(window_size.y),
+            },
+            root_pos: Vector::zero(),
+            anchor: Anchor::TopLeft,
+            scissor: true,
+            ..Default::default()
+        }]
+    }
+}
+
+/// Draws `count` rounded rectangles per frame and prints the average time spent rendering a frame
+pub fn render_rects(iters: usize, count: usize) {
+    let (mut glfw, mut window, events) = init_open_gl(
+        1000,
+        800,
+        WindowOptions {
+            vsync: false,
+            ..Default::default()
+        },
+    );
+
+    let rect_shader = Shader::new_from_name(&ShaderName::Rect).unwrap();
+    let text_shader = Shader::new_from_name(&ShaderName::Text).unwrap();
+    let line_shader = Shader::new_from_name(&ShaderName::Line).unwrap();
+
+    let rect_r = RectRenderer::new(rect_shader);
+    let text_r = TextRenderer::new(
+        text_shader,
+        &PathBuf::from_str("assets/fonts/LiberationMono.ttf").unwrap(),
+    )
+    .unwrap();
+    let line_r = LineRenderer::new(line_shader);
+    let sprite_r = SpriteRenderer::new(Shader::empty(), SpriteAtlas::empty());
+    let graph_r = GraphRenderer::new(
+        Shader::empty(),
+        Vector::new(window.get_size().0, window.get_size().1),
+    );
+
+    let mut state = Renderer::new(
+        rect_r,
+        text_r,
+        line_r,
+        sprite_r,
+        graph_r,
+        RectRendering { i: 0, count },
+    );
+    let mut total = Duration::ZERO;
+    while !window.should_close() && state.app_state.i < iters {
+        let _span = tracy_client::span!("Loop iteration");
+        glfw.poll_events();
+        state.pre_update();
+        for (_, _) in glfw::flush_messages(&events) {}
+        state.update();
+
+        let projection = state.projection();
+        rect_shader.use_shader();
+        rect_shader.set_uniform("projection", &projection);
+
+        unsafe {
+            gl::ClearColor(0.2, 0.2, 0.2, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+        }
+        let start = Instant::now();
+        state.render();
+        // Wait for the GPU so the frame time includes the draws and not just their submission
+        unsafe { gl::Finish() };
+        total += start.elapsed();
+        window.swap_buffers();
+
+        state.app_state.i += 1;
+    }
+    let frames = state.app_state.i.max(1) as u32;
+    println!(
+        "Rendered {} rects over {} frames, {:.3} ms per frame on average",
+        count,
+        frames,
+        (total / frames).as_secs_f64() * 1000.0
+    );
+    glfw::make_context_current(None);
+    // Segfaults due to bug in glfw with wayland
+    std::mem::forget(window);
+}
@@ -2,6 +2,7 @@ use std::{cell::RefCell, path::PathBuf, str::FromStr as _};
 
 use glfw::Context as _;
 use rust_ui::{
+    WindowOptions,
     geometry::Vector,
     init_open_gl,
     render::{
@@ -65,12 +66,20 @@ impl AppState for TextRendering {
             root_pos: Vector::zero(),
             anchor: Anchor::TopLeft,
             scissor: true,
+            z: 0,
         }]
     }
 }
 
 pub fn render_text(iters: usize) {
-    let (mut glfw, mut window, events) = init_open_gl(1000, 800, true, false);
+    let (mut glfw, mut window, events) = init_open_gl(
+        1000,
+        800,
+        WindowOptions {
+            vsync: false,
+            ..Default::default()
+        },
+    );
 
     let rect_shader = Shader::new_from_name(&ShaderName::Rect).unwrap();
     let text_shader = Shader::new_from_name(&ShaderName::Text).unwrap();
@@ -1,9 +1,15 @@
 use core::f32;
-use std::{cell::RefCell, f64::consts::PI, time::Instant};
+use std::{
+    cell::RefCell,
+    f64::consts::PI,
+    path::{Path, PathBuf},
+    time::Instant,
+};
 
 use cad::{
     Plane, Scene, SketchInfo,
     entity::GeometricEntity,
+    sketch::{snap_to_angle, snap_to_grid},
     topology::{Edge, Face, TopoEntity, TopoId},
 };
 use glfw::{Action, Key, Modifiers, Scancode, WindowEvent};
@@ -13,9 +19,10 @@ use rust_ui::{
     input::glfw_key_to_key_input,
     perf_overlay::PerformanceOverlay,
     render::{
-        Color,
+        Color, FrameCounter,
         line::LineRenderer,
         renderer::{AppState, RenderLayout, visual_log},
+        widgets::UiBuilder,
     },
 };
 use tracing::{debug, error, info};
@@ -32,6 +39,9 @@ use crate::{
     },
 };
 
+/// Where the scene is saved if it wasn't loaded from a file
+const SCENE_PATH: &str = "scene.json";
+
 #[derive(Debug, Clone, Copy, Default)]
 pub struct SketchModeData {
     pub sketch_id: u16,
@@ -48,12 +58,56 @@ pub struct LineModeData {
     pub points: Vec<glm::DVec2>,
 }
 
+impl LineModeData {
+    /// The last point placed by clicking. The final point follows the mouse.
+    pub fn last_placed(&self) -> Option<glm::DVec2> {
+        self.points.len().checked_sub(2).map(|i| self.points[i])
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct CircleModeData {
     pub center: Option<glm::DVec2>,
     pub boundary: Option<glm::DVec2>,
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct SnapSettings {
+    /// Distance between grid lines in sketch units. Positions aren't snapped to a grid when `None`
+    pub grid_spacing: Option<f64>,
+    /// The spacing [Self::grid_spacing] is set to when grid snapping is toggled on
+    pub preferred_grid_spacing: f64,
+    /// Lines snap to multiples of this angle, in degrees, while [Self::angle_active] is set
+    pub angle_increment: f64,
+    /// Set while shift is held
+    pub angle_active: bool,
+}
+
+impl Default for SnapSettings {
+    fn default() -> Self {
+        Self {
+            grid_spacing: None,
+            preferred_grid_spacing: 0.1,
+            angle_increment: 45.0,
+            angle_active: false,
+        }
+    }
+}
+
+impl SnapSettings {
+    /// Snaps a position in sketch space. Angles are measured from `origin`, the previous point of
+    /// a line being drawn, and take precedence over the grid.
+    pub fn apply(&self, origin: Option<glm::DVec2>, pos: glm::DVec2) -> glm::DVec2 {
+        match (origin, self.grid_spacing) {
+            (Some(origin), _) if self.angle_active => {
+                snap_to_angle(origin, pos, self.angle_increment)
+            }
+            (_, Some(spacing)) => snap_to_grid(pos, spacing),
+            (_, None) => pos,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct AppMutableState {
     pub scene: Scene,
@@ -61,6 +115,9 @@ pub(crate) struct AppMutableState {
     pub point_mode_data: PointModeData,
     pub line_mode_data: LineModeData,
     pub circle_mode_data: CircleModeData,
+    /// The picked entity and the id of the sketch it belongs to
+    pub selected: Option<(TopoId, u16)>,
+    pub snap: SnapSettings,
 }
 
 /// Returns the TopoIds of edges belonging to the loop that contains the given point,
@@ -221,6 +278,17 @@ impl App {
                                 si.plane.y.cast(),
                                 face_edges.as_deref(),
                             );
+                            if let Some((entity, sketch_id)) = self.mutable_state.borrow().selected
+                                && sketch_id == si.id
+                            {
+                                self.sketch_renderer.draw_selection(
+                                    &si.sketch,
+                                    entity,
+                                    data,
+                                    si.plane.x.cast(),
+                                    si.plane.y.cast(),
+                                );
+                            }
                         }
                         let active_sketch =
                             { self.mutable_state.borrow().sketch_mode_data.sketch_id };
@@ -310,7 +378,11 @@ impl App {
                             },
                             Action::Repeat => {}
                         },
-                        AppMode::Sketch => {}
+                        AppMode::Sketch => {
+                            if action == Action::Press && button == glfw::MouseButton::Button1 {
+                                self.select_hovered_entity();
+                            }
+                        }
                         AppMode::Point | AppMode::Line | AppMode::Circle => {}
                     }
                     if self.dragging_boundary.is_none() {
@@ -341,6 +413,23 @@ impl App {
         }
     }
 
+    /// Selects the entity under the mouse in the sketch being edited, or clears the selection if
+    /// there is nothing there
+    fn select_hovered_entity(&mut self) {
+        let Some(area) = self.area_manager.area_map.values().find(|area| {
+            matches!(area.area_type, AreaType::Viewport) && area.bbox.contains(self.mouse_pos)
+        }) else {
+            return;
+        };
+        let mouse_in_area = self.mouse_pos - area.bbox.x0;
+        let mut state = self.mutable_state.borrow_mut();
+        let active_sketch = state.sketch_mode_data.sketch_id;
+        state.selected = self
+            .sketch_picker
+            .hovered(mouse_in_area.into(), area.bbox.height())
+            .filter(|(_, sketch_id)| *sketch_id == active_sketch);
+    }
+
     pub fn toggle_visibility(&self, sketch_id: u16) {
         for s in self.mutable_state.borrow_mut().scene.sketches.iter_mut() {
             if s.id == sketch_id {
@@ -348,10 +437,10 @@ impl App {
             }
         }
     }
-}
 
-impl Default for App {
-    fn default() -> Self {
+    /// The viewport counts its draw calls into `frame_counter`, which should be shared with the
+    /// [rust_ui::render::renderer::Renderer] so they show up in its frame stats
+    pub fn new(frame_counter: FrameCounter) -> Self {
         let original_size = Vector::new(1000.0, 800.0);
 
         // Try to load saved layout first
@@ -458,6 +547,21 @@ impl Default for App {
             ],
             solids: vec![],
         };
+        // Prefer the document saved last time over the example scene
+        let scene = if Path::new(SCENE_PATH).exists() {
+            match Scene::load(Path::new(SCENE_PATH)) {
+                Ok(scene) => {
+                    info!("Loaded scene from {}", SCENE_PATH);
+                    scene
+                }
+                Err(e) => {
+                    error!("Failed to load scene: {}", e);
+                    scene
+                }
+            }
+        } else {
+            scene
+        };
 
         Self {
             perf_overlay: PerformanceOverlay::default(),
@@ -469,14 +573,20 @@ impl Default for App {
             debug_picker: true,
             settings: Settings {},
             settings_open: false,
-            sketch_renderer: SketchRenderer::new(),
-            sketch_picker: SketchPicker::new(original_size.x as i32, original_size.y as i32),
+            sketch_renderer: SketchRenderer::new(frame_counter.clone()),
+            sketch_picker: SketchPicker::new(
+                original_size.x as i32,
+                original_size.y as i32,
+                frame_counter,
+            ),
             mutable_state: RefCell::new(AppMutableState {
                 scene,
                 sketch_mode_data: SketchModeData::default(),
                 point_mode_data: PointModeData::default(),
                 line_mode_data: LineModeData::default(),
                 circle_mode_data: CircleModeData::default(),
+                selected: None,
+                snap: SnapSettings::default(),
             }),
             config: default_config(),
             mode_stack: ModeStack::with_base(AppMode::Base),
@@ -487,7 +597,11 @@ impl Default for App {
 impl AppState for App {
     type SpriteKey = String;
 
-    fn generate_layout(&mut self, window_size: Vector<f32>) -> Vec<RenderLayout<Self>> {
+    fn generate_layout(
+        &mut self,
+        window_size: Vector<f32>,
+        ui: &UiBuilder<Self>,
+    ) -> Vec<RenderLayout<Self>> {
         {
             let loops: Vec<Face> = self.mutable_state.borrow().scene.sketches[0]
                 .sketch
@@ -499,7 +613,7 @@ impl AppState for App {
         let mut out = vec![];
         out.extend(self.base_layer(window_size));
         if self.perf_overlay.visible {
-            out.push(self.perf_overlay.generate_layout(window_size));
+            out.push(self.perf_overlay.generate_layout(window_size, ui));
         }
         if self.settings_open {
             out.push(self.settings.generate_layout(window_size));
@@ -511,6 +625,10 @@ impl AppState for App {
         #[allow(clippy::single_match)]
         let mut state = self.mutable_state.borrow_mut();
 
+        if matches!(key, Key::LeftShift | Key::RightShift) {
+            state.snap.angle_active = action != Action::Release;
+        }
+
         if action == Action::Release {
             match glfw_key_to_key_input(key, modifiers) {
                 Some(key_input) => {
@@ -580,6 +698,39 @@ impl AppState for App {
                             AppBindableMessage::ActivatePointMode => {
                                 self.mode_stack.push(AppMode::Point);
                             }
+                            AppBindableMessage::SaveScene => {
+                                let scene = &mut state.scene;
+                                let path = scene
+                                    .path
+                                    .get_or_insert_with(|| PathBuf::from(SCENE_PATH))
+                                    .clone();
+                                match scene.save() {
+                                    Ok(()) => info!("Scene saved to {}", path.display()),
+                                    Err(e) => error!("Failed to save scene: {}", e),
+                                }
+                            }
+                            AppBindableMessage::Undo | AppBindableMessage::Redo => {
+                                let sid = state.sketch_mode_data.sketch_id;
+                                if let Some(sketch) =
+                                    state.scene.sketches.iter_mut().find(|s| s.id == sid)
+                                {
+                                    let changed = match action {
+                                        AppBindableMessage::Undo => sketch.sketch.undo(),
+                                        _ => sketch.sketch.redo(),
+                                    };
+                                    if !changed {
+                                        debug!("Nothing to {:?}", action);
+                                    }
+                                }
+                                // The selected entity might not exist anymore
+                                state.selected = None;
+                            }
+                            AppBindableMessage::ToggleGridSnap => {
+                                state.snap.grid_spacing = match state.snap.grid_spacing {
+                                    Some(_) => None,
+                                    None => Some(state.snap.preferred_grid_spacing),
+                                };
+                            }
                             AppBindableMessage::Confirm => {
                                 debug!("Confirm!");
                                 match self.mode_stack.outermost().unwrap() {
@@ -593,6 +744,7 @@ impl AppState for App {
                                             .iter_mut()
                                             .find(|s| s.id == sid)
                                             .unwrap();
+                                        sketch.sketch.checkpoint();
                                         sketch.sketch.insert_capped_lines(&points);
                                         self.mode_stack.pop_until(&AppMode::Sketch);
                                     }
@@ -12,22 +12,24 @@ use clap::Parser;
 use glfw::Context as _;
 use glm::{DVec2, DVec3, Vec2};
 use rust_ui::{
+    WindowOptions,
     geometry::Vector,
     init_open_gl,
     render::{
-        NORD0, NORD1, NORD2, NORD3, NORD4, NORD5, NORD6, NORD7, NORD8, NORD9, NORD10, NORD11,
-        NORD12, NORD13, NORD14, NORD15,
+        FrameCounter, NORD0, NORD1, NORD2, NORD3, NORD4, NORD5, NORD6, NORD7, NORD8, NORD9, NORD10,
+        NORD11, NORD12, NORD13, NORD14, NORD15,
         line::LineRenderer,
         rect::RectRenderer,
         renderer::Renderer,
-        sprite::{SpriteAtlas, SpriteRenderer},
+        sprite::{SpriteAtlas, SpriteRenderer, TextureFilter},
         text::TextRenderer,
     },
+    set_vsync,
     shader::{Shader, ShaderName},
     style::TAILWIND_COLORS,
 };
 use sysinfo::{ProcessesToUpdate, System};
-use tracing::error;
+use tracing::{error, info};
 use tracing_subscriber::EnvFilter;
 
 use crate::app::App;
@@ -55,7 +57,16 @@ fn main() {
 
     let args = Args::parse();
 
-    let (mut glfw, mut window, events) = init_open_gl(1000, 800, false, false);
+    let mut vsync = false;
+    let (mut glfw, mut window, events) = init_open_gl(
+        1000,
+        800,
+        WindowOptions {
+            resizable: false,
+            vsync,
+            ..Default::default()
+        },
+    );
 
     let rect_shader = Shader::new_from_name(&ShaderName::Rect).unwrap();
     let text_shader = Shader::new_from_name(&ShaderName::Text).unwrap();
@@ -65,7 +76,9 @@ fn main() {
         &PathBuf::from_str("assets/atlas/icons.png").unwrap(),
         &PathBuf::from_str("assets/atlas/icons.csv").unwrap(),
     )
-    .unwrap();
+    .unwrap()
+    // Keeps the icons smooth when the UI is scaled past their size in the atlas
+    .with_filter(TextureFilter::Linear);
 
     let rect_r = RectRenderer::new(rect_shader);
     let text_r = TextRenderer::new(
@@ -76,7 +89,9 @@ fn main() {
     let line_r = LineRenderer::new(line_shader);
     let sprite_r = SpriteRenderer::new(sprite_shader, sprite_atlas);
 
-    let app_state = App::default();
+    // Shared with the renderers of the viewport, which are created along with the app state
+    let frame_counter = FrameCounter::default();
+    let app_state = App::new(frame_counter.clone());
     if let Some(path) = args.sketch {
         let mut state = app_state.mutable_state.borrow_mut();
         state.scene.sketches.clear();
@@ -94,7 +109,8 @@ fn main() {
         }
     }
 
-    let mut state = Renderer::new(rect_r, text_r, line_r, sprite_r, app_state);
+    let mut state = Renderer::new(rect_r, text_r, line_r, sprite_r, app_state)
+        .with_frame_counter(frame_counter);
 
     // Set up projection matrix for 2D rendering
     let projection = glm::ortho(0.0, state.width as f32, state.height as f32, 0.0, -1.0, 1.0);
@@ -116,10 +132,11 @@ fn main() {
     sys.refresh_processes(ProcessesToUpdate::Some(&[pid]), false);
     let mut ram_usage = sys.process(pid).unwrap().memory();
 
-    let debug_renderer = LineRenderer::new(line_shader);
+    let debug_renderer = LineRenderer::new(line_shader).with_frame_counter(state.frame_counter());
 
     // Defer the direct handling of some of these events until we know they weren't handled by UI
     let mut window_events = vec![];
+    let mut take_screenshot = false;
 
     {
         let mut colors = (*TAILWIND_COLORS).write().unwrap();
@@ -149,6 +166,8 @@ fn main() {
 
         glfw.poll_events();
 
+        // Everything drawn last frame, read before the counts are reset
+        let frame_stats = state.frame_stats();
         state.pre_update();
         for (_, event) in glfw::flush_messages(&events) {
             match event {
@@ -182,6 +201,17 @@ fn main() {
                             }
                             _ => {}
                         },
+                        glfw::Key::F10 => {
+                            if action == glfw::Action::Release {
+                                vsync = !vsync;
+                                set_vsync(&mut glfw, vsync);
+                            }
+                        }
+                        glfw::Key::F11 => {
+                            if action == glfw::Action::Release {
+                                take_screenshot = true;
+                            }
+                        }
                         _ => {}
                     }
                 }
@@ -190,7 +220,10 @@ fn main() {
         }
 
         state.update();
-        state.app_state.perf_overlay.update(avg_sleep_ms, ram_usage);
+        state
+            .app_state
+            .perf_overlay
+            .update(avg_sleep_ms, ram_usage, frame_stats);
 
         state
             .app_state
@@ -223,12 +256,27 @@ fn main() {
                     Vector::new(state.width as f32, state.height as f32),
                 )
             }
+            if take_screenshot {
+                take_screenshot = false;
+                let path = format!(
+                    "screenshot-{}.png",
+                    std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs()
+                );
+                match state.capture_frame().save(&path) {
+                    Ok(()) => info!("Saved screenshot to {path}"),
+                    Err(e) => error!("Failed to save screenshot: {e}"),
+                }
+            }
 
             window.swap_buffers();
         }
 
         let frame_time = frame_start.elapsed();
-        let sleep_duration = if frame_time < FRAME_TIME {
+        // With vsync on, swapping buffers already paces the loop
+        let sleep_duration = if !vsync && frame_time < FRAME_TIME {
             let sleep_time = FRAME_TIME - frame_time;
             std::thread::sleep(sleep_time);
             sleep_time
@@ -31,7 +31,11 @@ pub enum AppBindableMessage {
     SplitAreaHorizontally,
     SplitAreaVertically,
     CollapseBoundary,
+    SaveScene,
     ActivatePointMode,
+    ToggleGridSnap,
+    Undo,
+    Redo,
     Confirm,
 }
 
@@ -82,13 +86,31 @@ pub fn default_config() -> Config<AppMode, AppBindableMessage, AppMouseAction> {
             KeyInput::from_str("d").unwrap(),
             AppBindableMessage::CollapseBoundary,
         ),
+        Keybind::new(
+            KeyInput::from_str("Ctrl+s").unwrap(),
+            AppBindableMessage::SaveScene,
+        ),
     ];
     let base_bindings = Keybinds::new(base_keybinds);
 
-    let sketch_keybinds = vec![Keybind::new(
-        KeyInput::from_str("p").unwrap(),
-        AppBindableMessage::ActivatePointMode,
-    )];
+    let sketch_keybinds = vec![
+        Keybind::new(
+            KeyInput::from_str("p").unwrap(),
+            AppBindableMessage::ActivatePointMode,
+        ),
+        Keybind::new(
+            KeyInput::from_str("g").unwrap(),
+            AppBindableMessage::ToggleGridSnap,
+        ),
+        Keybind::new(
+            KeyInput::from_str("Ctrl+z").unwrap(),
+            AppBindableMessage::Undo,
+        ),
+        Keybind::new(
+            KeyInput::from_str("Ctrl+Shift+z").unwrap(),
+            AppBindableMessage::Redo,
+        ),
+    ];
     let sketch_bindings = Keybinds::new(sketch_keybinds);
     let line_keybinds = vec![Keybind::new(
         KeyInput::from_str("Enter").unwrap(),
@@ -1,3 +1,5 @@
+use std::time::Instant;
+
 use cad::{
     SketchInfo,
     entity::{Circle, GeoId, Point},
@@ -8,7 +10,9 @@ use modes::ModeStack;
 use rust_ui::{
     geometry::Vector,
     render::{
-        COLOR_FACE_HOVER, COLOR_SUCCESS, Color, circle::CircleRenderer, line::LineRenderer,
+        COLOR_FACE_HOVER, COLOR_PRIMARY, COLOR_SUCCESS, Color, FrameCounter,
+        circle::CircleRenderer,
+        line::{LineCap, LineJoin, LineRenderer},
         point::PointRenderer,
     },
     shader::{Shader, ShaderName},
@@ -23,27 +27,107 @@ use crate::{
 
 pub const PENDING_COLOR: Color = COLOR_SUCCESS;
 pub const FACE_HOVER_COLOR: Color = COLOR_FACE_HOVER;
+pub const SELECTION_COLOR: Color = COLOR_PRIMARY;
+/// Distance in pixels between a selected entity and its selection outline
+const SELECTION_PADDING: f32 = 8.0;
+/// How fast the dashes of the selection outline move, in pixels per second
+const SELECTION_SPEED: f32 = 16.0;
+
+/// Projects `points` in the sketch plane spanned by `x_axis` and `y_axis` to the screen and draws
+/// them as one polyline with round joins, so connected lines meet without notches. Parts of the
+/// polyline behind the camera are left out.
+#[allow(clippy::too_many_arguments)]
+fn draw_sketch_polyline(
+    line_r: &LineRenderer,
+    state: &ViewportData,
+    points: &[Vector<f32>],
+    x_axis: glm::Vec3,
+    y_axis: glm::Vec3,
+    color: Color,
+    thickness: f32,
+) {
+    let screen_points: Vec<Option<Vector<f32>>> = points
+        .iter()
+        .map(|p| state.world_to_screen(p.x * x_axis + p.y * y_axis))
+        .collect();
+    for run in screen_points.split(|p| p.is_none()) {
+        let run: Vec<Vector<f32>> = run.iter().flatten().copied().collect();
+        line_r.draw_polyline_styled(
+            &run,
+            color,
+            thickness,
+            LineCap::Round,
+            LineJoin::Round,
+            state.size,
+        );
+    }
+}
+
+/// Joins the capped lines `segments`, given as `(start, end)`, into chains of points wherever
+/// they share end points. A closed loop ends with the point it started at.
+fn chain_segments(segments: &[(GeoId, GeoId)]) -> Vec<Vec<GeoId>> {
+    let mut used = vec![false; segments.len()];
+    let mut chains = vec![];
+    for i in 0..segments.len() {
+        if used[i] {
+            continue;
+        }
+        used[i] = true;
+        let mut chain = vec![segments[i].0, segments[i].1];
+        // Grow the chain from its end, then from its start
+        for _ in 0..2 {
+            while chain.first() != chain.last() {
+                let last = *chain.last().unwrap();
+                let next = segments
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| !used[*j])
+                    .find_map(|(j, (start, end))| {
+                        if *start == last {
+                            Some((j, *end))
+                        } else if *end == last {
+                            Some((j, *start))
+                        } else {
+                            None
+                        }
+                    });
+                let Some((j, next)) = next else {
+                    break;
+                };
+                used[j] = true;
+                chain.push(next);
+            }
+            chain.reverse();
+        }
+        chains.push(chain);
+    }
+    chains
+}
 
 pub struct SketchRenderer {
     line_r: LineRenderer,
     point_r: PointRenderer,
     circle_r: CircleRenderer,
+    /// Drives the animation of the selection outline
+    created: Instant,
 }
 
 impl SketchRenderer {
-    pub fn new() -> Self {
+    /// Draw calls are counted into `frame_counter`, usually the one behind
+    /// [rust_ui::render::renderer::Renderer::frame_stats]
+    pub fn new(frame_counter: FrameCounter) -> Self {
         let line_shader = Shader::new_from_name(&ShaderName::Line).unwrap();
         Self {
-            line_r: LineRenderer::new(line_shader.clone()),
-            point_r: PointRenderer::new(line_shader.clone()),
-            circle_r: CircleRenderer::new(line_shader),
+            line_r: LineRenderer::new(line_shader.clone())
+                .with_frame_counter(frame_counter.clone()),
+            point_r: PointRenderer::new(line_shader.clone())
+                .with_frame_counter(frame_counter.clone()),
+            circle_r: CircleRenderer::new(line_shader).with_frame_counter(frame_counter),
+            created: Instant::now(),
         }
     }
 
     pub fn draw_axes(&mut self, state: &ViewportData) {
-        let projection = state.projection();
-        let model = state.model();
-        let view = state.view();
         let axes = &[
             (glm::vec3(1.0, 0.0, 0.0), Color::new(1.0, 0.0, 0.0, 1.0)),
             (glm::vec3(0.0, 1.0, 0.0), Color::new(0.0, 1.0, 0.0, 1.0)),
@@ -52,16 +136,18 @@ impl SketchRenderer {
             (glm::vec3(0.0, -1.0, 0.0), Color::new(0.0, 0.2, 0.0, 1.0)),
             (glm::vec3(0.0, 0.0, -1.0), Color::new(0.0, 0.0, 0.2, 1.0)),
         ];
+        let origin = state.world_to_screen(glm::vec3(0.0, 0.0, 0.0));
         for (ax, color) in axes {
-            self.line_r.draw_3d(
-                glm::vec3(0.0, 0.0, 0.0),
-                *ax,
-                *color,
-                2.0,
-                &projection,
-                &model,
-                &view,
-            );
+            if let (Some(origin), Some(end)) = (origin, state.world_to_screen(*ax)) {
+                self.line_r.draw_polyline_styled(
+                    &[origin, end],
+                    *color,
+                    2.0,
+                    LineCap::Butt,
+                    LineJoin::default(),
+                    state.size,
+                );
+            }
         }
     }
 
@@ -83,6 +169,8 @@ impl SketchRenderer {
         let projection = state.projection();
         let model = state.model();
         let view = state.view();
+        let mut lines = vec![];
+        let mut hovered_lines = vec![];
         for (id, eid) in sketch.topo_entities.iter() {
             match eid {
                 cad::topology::TopoEntity::Point { id: pid } => {
@@ -119,32 +207,96 @@ impl SketchRenderer {
                         y_axis,
                     );
                 }
-                cad::topology::TopoEntity::Edge { edge } => match edge {
-                    cad::topology::Edge::CappedLine {
-                        start,
-                        end,
-                        line: _,
-                    } => {
-                        let start: Point = sketch.geo_entities[*start].try_into().unwrap();
-                        let end: Point = sketch.geo_entities[*end].try_into().unwrap();
-                        let s = Vector::new(start.pos.x as f32, start.pos.y as f32);
-                        let e = Vector::new(end.pos.x as f32, end.pos.y as f32);
-
-                        let s_3d = s.x * x_axis + s.y * y_axis;
-                        let e_3d = e.x * x_axis + e.y * y_axis;
-                        let color = if face_edges.map_or(false, |edges| edges.contains(id)) {
-                            FACE_HOVER_COLOR
-                        } else {
-                            Color::new(1.0, 1.0, 1.0, 1.0)
-                        };
-                        self.line_r
-                            .draw_3d(s_3d, e_3d, color, 2.0, &projection, &model, &view);
+                cad::topology::TopoEntity::Edge {
+                    edge: cad::topology::Edge::CappedLine { start, end, .. },
+                } => {
+                    if face_edges.map_or(false, |edges| edges.contains(id)) {
+                        hovered_lines.push((*start, *end));
+                    } else {
+                        lines.push((*start, *end));
                     }
-                    _ => {}
-                },
+                }
                 _ => {}
             }
         }
+
+        // Connected lines are drawn as polylines so that their corners are joined
+        for (segments, color) in [
+            (lines, Color::new(1.0, 1.0, 1.0, 1.0)),
+            (hovered_lines, FACE_HOVER_COLOR),
+        ] {
+            for chain in chain_segments(&segments) {
+                let points: Vec<Vector<f32>> = chain
+                    .into_iter()
+                    .map(|id| {
+                        let point: Point = sketch.geo_entities[id].try_into().unwrap();
+                        Vector::new(point.pos.x as f32, point.pos.y as f32)
+                    })
+                    .collect();
+                draw_sketch_polyline(&self.line_r, state, &points, x_axis, y_axis, color, 2.0);
+            }
+        }
+    }
+
+    /// Draws a dashed outline with moving dashes around the entity `id` in `sketch`. The outline
+    /// is recomputed from the current geometry every frame so it follows the entity as the
+    /// solver moves it.
+    pub fn draw_selection(
+        &mut self,
+        sketch: &Sketch,
+        id: TopoId,
+        state: &ViewportData,
+        x_axis: glm::Vec3,
+        y_axis: glm::Vec3,
+    ) {
+        let points: Vec<Vector<f32>> = match sketch.topo_entities.get(&id) {
+            Some(cad::topology::TopoEntity::Point { id: pid }) => {
+                let point: Point = sketch.geo_entities[*pid].try_into().unwrap();
+                vec![Vector::new(point.pos.x as f32, point.pos.y as f32)]
+            }
+            Some(cad::topology::TopoEntity::Circle { id: cid }) => {
+                let circle: Circle = sketch.geo_entities[*cid].try_into().unwrap();
+                let center = Vector::new(circle.pos.x as f32, circle.pos.y as f32);
+                // The circle is sampled since it might be viewed at an angle
+                (0..32)
+                    .map(|i| {
+                        let angle = i as f32 / 32.0 * std::f32::consts::TAU;
+                        center + Vector::new(angle.cos(), angle.sin()) * circle.radius as f32
+                    })
+                    .collect()
+            }
+            Some(cad::topology::TopoEntity::Edge {
+                edge: cad::topology::Edge::CappedLine { start, end, .. },
+            }) => {
+                let start: Point = sketch.geo_entities[*start].try_into().unwrap();
+                let end: Point = sketch.geo_entities[*end].try_into().unwrap();
+                vec![
+                    Vector::new(start.pos.x as f32, start.pos.y as f32),
+                    Vector::new(end.pos.x as f32, end.pos.y as f32),
+                ]
+            }
+            _ => vec![],
+        };
+
+        let screen_points: Vec<Vector<f32>> = points
+            .iter()
+            .filter_map(|p| state.world_to_screen(p.x * x_axis + p.y * y_axis))
+            .collect();
+        let Some(first) = screen_points.first() else {
+            return;
+        };
+        let (mut x0, mut x1) = (*first, *first);
+        for p in &screen_points {
+            x0 = Vector::new(x0.x.min(p.x), x0.y.min(p.y));
+            x1 = Vector::new(x1.x.max(p.x), x1.y.max(p.y));
+        }
+        x0 -= Vector::new(SELECTION_PADDING, SELECTION_PADDING);
+        x1 += Vector::new(SELECTION_PADDING, SELECTION_PADDING);
+
+        let corners = [x0, Vector::new(x1.x, x0.y), x1, Vector::new(x0.x, x1.y)];
+        let phase = -self.created.elapsed().as_secs_f32() * SELECTION_SPEED;
+        self.line_r
+            .draw_dashed_loop(&corners, SELECTION_COLOR, 1.0, 4.0, 4.0, phase, state.size);
     }
 
     pub fn draw_pending(
@@ -169,21 +321,21 @@ impl SketchRenderer {
                 }
             }
             Some(AppMode::Line) => {
-                for w in app_state.line_mode_data.points.windows(2) {
-                    let start = w[0];
-                    let end = w[1];
-                    let start_3d = (start.x as f32) * x_axis + (start.y as f32) * y_axis;
-                    let end_3d = (end.x as f32) * x_axis + (end.y as f32) * y_axis;
-                    self.line_r.draw_3d(
-                        start_3d,
-                        end_3d,
-                        PENDING_COLOR,
-                        2.0,
-                        &projection,
-                        &model,
-                        &view,
-                    );
-                }
+                let points: Vec<Vector<f32>> = app_state
+                    .line_mode_data
+                    .points
+                    .iter()
+                    .map(|p| Vector::new(p.x as f32, p.y as f32))
+                    .collect();
+                draw_sketch_polyline(
+                    &self.line_r,
+                    vp_state,
+                    &points,
+                    x_axis,
+                    y_axis,
+                    PENDING_COLOR,
+                    2.0,
+                );
 
                 for p in &app_state.line_mode_data.points {
                     let p_3d = (p.x as f32) * x_axis + (p.y as f32) * y_axis;
@@ -234,12 +386,15 @@ pub struct SketchPicker {
 }
 
 impl SketchPicker {
-    pub fn new(window_width: i32, window_height: i32) -> Self {
+    /// Draw calls are counted into `frame_counter`, see [SketchRenderer::new]
+    pub fn new(window_width: i32, window_height: i32, frame_counter: FrameCounter) -> Self {
         let line_shader = Shader::new_from_name(&ShaderName::Pick).unwrap();
         Self {
-            line_r: LineRenderer::new(line_shader.clone()),
-            point_r: PointRenderer::new(line_shader.clone()),
-            circle_r: CircleRenderer::new(line_shader),
+            line_r: LineRenderer::new(line_shader.clone())
+                .with_frame_counter(frame_counter.clone()),
+            point_r: PointRenderer::new(line_shader.clone())
+                .with_frame_counter(frame_counter.clone()),
+            circle_r: CircleRenderer::new(line_shader).with_frame_counter(frame_counter),
             picker: EntityPicker::new(window_width, window_height),
             window_width,
             window_height,
@@ -303,28 +458,25 @@ impl SketchPicker {
                     );
                 }
                 cad::topology::TopoEntity::Edge { edge } => match edge {
-                    cad::topology::Edge::CappedLine { start, end, line } => {
+                    cad::topology::Edge::CappedLine { start, end, .. } => {
                         let start: Point = si.sketch.geo_entities[*start].try_into().unwrap();
                         let end: Point = si.sketch.geo_entities[*end].try_into().unwrap();
                         let s = Vector::new(start.pos.x as f32, start.pos.y as f32);
                         let e = Vector::new(end.pos.x as f32, end.pos.y as f32);
-                        let projection = state.projection();
-                        let model = state.model();
-                        let view = state.view();
 
-                        let s_3d = s.x * x_axis + s.y * y_axis;
-                        let e_3d = e.x * x_axis + e.y * y_axis;
                         self.line_r.shader.use_shader();
                         self.line_r.shader.set_uniform("entityId", &(*id as u32));
                         self.line_r.shader.set_uniform("sketchId", &(si.id as u32));
-                        self.line_r.draw_3d(
-                            s_3d,
-                            e_3d,
+                        // Drawn like the lines of SketchRenderer::draw so the picked area
+                        // matches what is visible
+                        draw_sketch_polyline(
+                            &self.line_r,
+                            state,
+                            &[s, e],
+                            x_axis,
+                            y_axis,
                             Color::new(1.0, 1.0, 1.0, 1.0),
                             8.0,
-                            &projection,
-                            &model,
-                            &view,
                         );
                     }
                     _ => {}
@@ -217,14 +217,15 @@ impl Area {
                     ..Default::default()
                 },
                 NodeContext {
-                    bg_color: match self.area_type {
+                    background: match self.area_type {
                         AreaType::Red => NORD11,
                         AreaType::Green => NORD14,
                         AreaType::Blue => NORD9,
                         AreaType::Viewport => Color::new(0.0, 0.0, 0.0, 0.0),
                         AreaType::SceneExplorer => NORD1,
                         AreaType::Modes => NORD1,
-                    },
+                    }
+                    .into(),
                     on_mouse_exit: Some(Arc::new(move |state: &mut Renderer<App>| {
                         // Might not exist if we exit on the same frame an area is deleted
                         if let Some(area) = state.app_state.area_manager.area_map.get_mut(&id) {
@@ -266,6 +267,7 @@ impl Area {
             root_pos: self.bbox.x0,
             anchor: Anchor::TopLeft,
             scissor: true,
+            z: 0,
         }
     }
 
@@ -279,7 +281,7 @@ impl Area {
                     ..Default::default()
                 },
                 NodeContext {
-                    bg_color: Color::default(),
+                    background: Color::default().into(),
                     ..Default::default()
                 },
             )
@@ -309,7 +311,7 @@ impl Area {
                         font_size: 18,
                         color: Color::default(),
                     },
-                    bg_color: COLOR_BLACK,
+                    background: COLOR_BLACK.into(),
                     ..Default::default()
                 },
             )
@@ -333,7 +335,7 @@ impl Area {
                         font_size: 18,
                         color: COLOR_LIGHT,
                     },
-                    bg_color: COLOR_BLACK,
+                    background: COLOR_BLACK.into(),
                     bg_color_hover: NORD3,
                     on_left_mouse_down: Some(Arc::new(move |state: &mut Renderer<App>| {
                         let area = &mut state.app_state.area_manager.area_map[id];
@@ -362,7 +364,7 @@ impl Area {
                     ..Default::default()
                 },
                 NodeContext {
-                    bg_color: COLOR_BLACK,
+                    background: COLOR_BLACK.into(),
                     ..Default::default()
                 },
             )
@@ -392,7 +394,7 @@ impl Area {
                                 font_size: 18,
                                 color: COLOR_LIGHT,
                             },
-                            bg_color: COLOR_BLACK,
+                            background: COLOR_BLACK.into(),
                             bg_color_hover: NORD3,
                             on_left_mouse_up: Some(Arc::new(move |state| {
                                 state.app_state.area_manager.area_map[id].area_type = kind;
@@ -427,6 +429,7 @@ impl Area {
             root_pos: self.bbox.x0,
             anchor: Anchor::TopLeft,
             scissor: true,
+            z: 0,
         }
     }
 
@@ -472,8 +475,9 @@ impl Area {
                             .iter_mut()
                             .find(|s| s.id == state.sketch_mode_data.sketch_id)
                         {
-                            state.point_mode_data.pending =
-                                data.screen_to_sketch_coords(mouse_in_viewport, &sketch_info.plane);
+                            state.point_mode_data.pending = data
+                                .screen_to_sketch_coords(mouse_in_viewport, &sketch_info.plane)
+                                .map(|p| state.snap.apply(None, p));
                         }
                     }
                     if mode_stack.is_active(&AppMode::Line) {
@@ -486,6 +490,9 @@ impl Area {
                             && let Some(sketch_coords) =
                                 data.screen_to_sketch_coords(mouse_in_viewport, &sketch_info.plane)
                         {
+                            let sketch_coords = state
+                                .snap
+                                .apply(state.line_mode_data.last_placed(), sketch_coords);
                             if let Some(last) = state.line_mode_data.points.last_mut() {
                                 *last = sketch_coords;
                             } else {
@@ -501,12 +508,13 @@ impl Area {
                             .iter_mut()
                             .find(|s| s.id == state.sketch_mode_data.sketch_id)
                         {
+                            let sketch_coords = data
+                                .screen_to_sketch_coords(mouse_in_viewport, &sketch_info.plane)
+                                .map(|p| state.snap.apply(None, p));
                             if state.circle_mode_data.boundary.is_none() {
-                                state.circle_mode_data.center = data
-                                    .screen_to_sketch_coords(mouse_in_viewport, &sketch_info.plane);
+                                state.circle_mode_data.center = sketch_coords;
                             } else {
-                                state.circle_mode_data.boundary = data
-                                    .screen_to_sketch_coords(mouse_in_viewport, &sketch_info.plane);
+                                state.circle_mode_data.boundary = sketch_coords;
                             }
                         }
                     }
@@ -551,8 +559,14 @@ impl Area {
                                 if let Some(sketch_coords) = viewport_data
                                     .screen_to_sketch_coords(mouse_in_viewport, &sketch_info.plane)
                                 {
+                                    let origin = match mode_stack.outermost() {
+                                        Some(AppMode::Line) => state.line_mode_data.last_placed(),
+                                        _ => None,
+                                    };
+                                    let sketch_coords = state.snap.apply(origin, sketch_coords);
                                     match mode_stack.outermost().unwrap() {
                                         AppMode::Point => {
+                                            sketch_info.sketch.checkpoint();
                                             sketch_info.sketch.insert_point(sketch_coords);
                                         }
                                         AppMode::Line => {
@@ -563,6 +577,7 @@ impl Area {
                                                 state.circle_mode_data.boundary =
                                                     Some(sketch_coords);
                                             } else {
+                                                sketch_info.sketch.checkpoint();
                                                 sketch_info.sketch.insert_circle(
                                                     state.circle_mode_data.center.unwrap(),
                                                     (state.circle_mode_data.center.unwrap()
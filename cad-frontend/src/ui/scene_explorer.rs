@@ -56,18 +56,18 @@ impl SceneExplorer {
                     font_size: 14,
                     color: s_color,
                 }),
-                b.sprite("w-24 h-24 translate-y-2", if sketch.visible { "Visible" } else { "Invisible" }, Listeners {
+                b.tooltip(b.sprite("w-24 h-24 translate-y-2", if sketch.visible { "Visible" } else { "Invisible" }, Listeners {
                     on_left_mouse_up: Some(Arc::new(move |state| {
                         state.app_state.toggle_visibility(sketch_id);
                     })),
                     ..Default::default()
-                }),
-                b.sprite("w-24 h-24 translate-y-3", "EditSketch", Listeners {
+                }), if sketch.visible { "Hide sketch" } else { "Show sketch" }),
+                b.tooltip(b.sprite("w-24 h-24 translate-y-3", "EditSketch", Listeners {
                     on_left_mouse_up: Some(Arc::new(move |state| {
                         state.app_state.edit_sketch(sketch_id);
                     })),
                     ..Default::default()
-                })
+                }), "Edit sketch"),
             ]);
             sketch_rows.push(id);
         }
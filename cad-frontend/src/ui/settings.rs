@@ -30,7 +30,7 @@ impl Settings {
                     ..Default::default()
                 },
                 NodeContext {
-                    bg_color: Color::new(0.0, 0.0, 0.0, 0.1),
+                    background: Color::new(0.0, 0.0, 0.0, 0.1).into(),
                     ..Default::default()
                 },
             )
@@ -57,7 +57,7 @@ impl Settings {
                     ..Default::default()
                 },
                 NodeContext {
-                    bg_color: COLOR_BLACK,
+                    background: COLOR_BLACK.into(),
                     border: Border {
                         thickness: 2.0,
                         radius: BorderRadius::all(12.0),
@@ -103,7 +103,7 @@ impl Settings {
                         font_size: 18,
                         color: COLOR_LIGHT,
                     },
-                    bg_color: COLOR_PRIMARY,
+                    background: COLOR_PRIMARY.into(),
                     bg_color_hover: COLOR_SECONDARY,
                     border: Border {
                         radius: BorderRadius::all(8.0),
@@ -135,7 +135,7 @@ impl Settings {
                         font_size: 18,
                         color: COLOR_LIGHT,
                     },
-                    bg_color: COLOR_SUCCESS,
+                    background: COLOR_SUCCESS.into(),
                     bg_color_hover: COLOR_SECONDARY,
                     border: Border {
                         radius: BorderRadius::all(8.0),
@@ -164,6 +164,8 @@ impl Settings {
             root_pos: Vector::zero(),
             anchor: Anchor::TopLeft,
             scissor: false,
+            // Above the areas, wherever it ends up in the list of layers
+            z: 1,
         }
     }
 }
@@ -195,6 +195,24 @@ impl ViewportData {
         }
     }
 
+    /// Projects a point in world space to pixel coordinates within the viewport, the inverse of
+    /// [Self::screen_to_ray]. Returns `None` for points behind the camera.
+    pub fn world_to_screen(&self, point: glm::Vec3) -> Option<Vector<f32>> {
+        let clip = self.projection()
+            * self.view()
+            * self.model()
+            * glm::vec4(point.x, point.y, point.z, 1.0);
+        if clip.w <= 0.0 {
+            return None;
+        }
+        let ndc_x = clip.x / clip.w;
+        let ndc_y = clip.y / clip.w;
+        Some(Vector::new(
+            (ndc_x + 1.0) / 2.0 * self.size.x,
+            (1.0 - ndc_y) / 2.0 * self.size.y,
+        ))
+    }
+
     fn ray_plane_intersection(
         ray_origin: &glm::Vec3,
         ray_direction: &glm::Vec3,
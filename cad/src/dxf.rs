@@ -0,0 +1,93 @@
+//! A minimal reader for the entities section of ASCII DXF files
+
+use std::error::Error;
+
+use nalgebra::Vector2;
+use tracing::warn;
+
+/// The subset of DXF entities that can be turned into sketch geometry. Angles are in degrees,
+/// measured counterclockwise from the x-axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DxfEntity {
+    Line {
+        start: Vector2<f64>,
+        end: Vector2<f64>,
+    },
+    Circle {
+        center: Vector2<f64>,
+        radius: f64,
+    },
+    /// Runs counterclockwise from `start_angle` to `end_angle`
+    Arc {
+        center: Vector2<f64>,
+        radius: f64,
+        start_angle: f64,
+        end_angle: f64,
+    },
+}
+
+/// Reads the LINE, CIRCLE and ARC entities of a DXF file. Other entity types are skipped with a
+/// warning.
+pub fn parse_entities(source: &str) -> Result<Vec<DxfEntity>, Box<dyn Error>> {
+    let lines: Vec<&str> = source.lines().map(|l| l.trim()).collect();
+    let mut pairs = vec![];
+    for (i, pair) in lines.chunks(2).enumerate() {
+        if let [code, value] = pair {
+            let code: i32 = code.parse().map_err(|_| {
+                format!("Couldn't parse group code on line {}: {}", 2 * i + 1, code)
+            })?;
+            pairs.push((code, *value));
+        }
+    }
+
+    let mut entities = vec![];
+    let mut in_entities = false;
+    let mut i = 0;
+    while i < pairs.len() {
+        let (code, value) = pairs[i];
+        i += 1;
+        if code != 0 {
+            continue;
+        }
+        match value {
+            "SECTION" => in_entities = pairs.get(i) == Some(&(2, "ENTITIES")),
+            "ENDSEC" => in_entities = false,
+            kind if in_entities => {
+                // An entity's properties last until the next entity starts
+                let start = i;
+                while i < pairs.len() && pairs[i].0 != 0 {
+                    i += 1;
+                }
+                let properties = &pairs[start..i];
+                let number = |code: i32| -> Result<f64, Box<dyn Error>> {
+                    let (_, value) = properties
+                        .iter()
+                        .find(|(c, _)| *c == code)
+                        .ok_or(format!("{} is missing group code {}", kind, code))?;
+                    value
+                        .parse()
+                        .map_err(|_| format!("Couldn't parse {} in {}", value, kind).into())
+                };
+                match kind {
+                    "LINE" => entities.push(DxfEntity::Line {
+                        start: Vector2::new(number(10)?, number(20)?),
+                        end: Vector2::new(number(11)?, number(21)?),
+                    }),
+                    "CIRCLE" => entities.push(DxfEntity::Circle {
+                        center: Vector2::new(number(10)?, number(20)?),
+                        radius: number(40)?,
+                    }),
+                    "ARC" => entities.push(DxfEntity::Arc {
+                        center: Vector2::new(number(10)?, number(20)?),
+                        radius: number(40)?,
+                        start_angle: number(50)?,
+                        end_angle: number(51)?,
+                    }),
+                    _ => warn!("Skipping unsupported DXF entity {}", kind),
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(entities)
+}
@@ -53,6 +53,15 @@ pub fn vector_angle(a: Vector2<f64>) -> f64 {
 }
 
 impl GeometricEntity {
+    /// Name of the kind of entity for messages shown to the user
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GeometricEntity::Point { .. } => "point",
+            GeometricEntity::Line { .. } => "line",
+            GeometricEntity::Circle { .. } => "circle",
+        }
+    }
+
     pub fn distance_to_position(&self, target: &Vector2<f64>) -> f64 {
         match self {
             GeometricEntity::Point { pos } => (pos - target).norm(),
@@ -65,6 +74,38 @@ impl GeometricEntity {
         }
     }
 
+    /// Number of values that describe the entity, i.e. how many [GeometricEntity::parameter_mut]
+    /// accepts
+    pub fn parameter_count(&self) -> usize {
+        match self {
+            GeometricEntity::Point { .. } => 2,
+            GeometricEntity::Line { .. } => 4,
+            GeometricEntity::Circle { .. } => 3,
+        }
+    }
+
+    /// The `i`:th value describing the entity. Positions and offsets come first (x then y),
+    /// followed by the direction of a line or the radius of a circle.
+    pub fn parameter_mut(&mut self, i: usize) -> &mut f64 {
+        match self {
+            GeometricEntity::Point { pos } => &mut pos[i],
+            GeometricEntity::Line { offset, direction } => {
+                if i < 2 {
+                    &mut offset[i]
+                } else {
+                    &mut direction[i - 2]
+                }
+            }
+            GeometricEntity::Circle { pos, radius } => {
+                if i < 2 {
+                    &mut pos[i]
+                } else {
+                    radius
+                }
+            }
+        }
+    }
+
     pub fn circle_from_three_coords(
         p1: &Vector2<f64>,
         p2: &Vector2<f64>,
@@ -96,15 +137,36 @@ pub fn project(a: &Vector2<f64>, b: &Vector2<f64>) -> Vector2<f64> {
     a.dot(b) / b.dot(b) * b
 }
 
+/// Mirrors the position `p` across the line through `offset` along `direction`
+pub fn reflect(p: &Vector2<f64>, offset: &Vector2<f64>, direction: &Vector2<f64>) -> Vector2<f64> {
+    let foot = offset + project(&(p - offset), direction);
+    2.0 * foot - p
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, Copy)]
 pub enum ConstraintType {
-    Angle { x: f64 },
+    Angle {
+        x: f64,
+    },
     Coincident,
-    Colinear,            // Should be paired with a parallel constraint for line-line
-    Distance { x: f64 }, // Should be paired with a parallel constraint for line-line
+    /// Should be paired with a parallel constraint for line-line
+    Colinear,
+    /// Should be paired with a parallel constraint for line-line
+    Distance {
+        x: f64,
+    },
+    /// The lines are equally long, measured by the length of their directions. Capped lines are
+    /// measured between their end points, see [crate::sketch::Sketch::add_constraint].
+    EqualLength,
     Horizontal,
     Parallel,
     Perpendicular,
+    /// The entities are mirror images of each other across the line through `offset` along
+    /// `direction`
+    Symmetric {
+        offset: Vector2<f64>,
+        direction: Vector2<f64>,
+    },
     Tangent,
     Vertical,
 }
@@ -129,6 +191,7 @@ impl BiConstraint {
                     | ConstraintType::Distance { .. }
                     | ConstraintType::Vertical
                     | ConstraintType::Horizontal
+                    | ConstraintType::Symmetric { .. }
             ),
             (GeometricEntity::Point { .. }, GeometricEntity::Line { .. }) => matches!(
                 c,
@@ -148,6 +211,8 @@ impl BiConstraint {
                     | ConstraintType::Colinear
                     | ConstraintType::Distance { .. }
                     | ConstraintType::Angle { .. }
+                    | ConstraintType::EqualLength
+                    | ConstraintType::Symmetric { .. }
             ),
             (GeometricEntity::Circle { .. }, GeometricEntity::Line { .. }) => matches!(
                 c,
@@ -162,6 +227,7 @@ impl BiConstraint {
                     | ConstraintType::Tangent
                     | ConstraintType::Vertical
                     | ConstraintType::Horizontal
+                    | ConstraintType::Symmetric { .. }
             ),
             (_, _) => BiConstraint::possible(e2, e1, c),
         }
@@ -206,6 +272,9 @@ impl BiConstraint {
             ConstraintType::Horizontal => (p1.pos.y - p2.pos.y).powi(2),
             ConstraintType::Vertical => (p1.pos.x - p2.pos.x).powi(2),
             ConstraintType::Distance { x } => ((p1.pos - p2.pos).norm() - x).powi(2),
+            ConstraintType::Symmetric { offset, direction } => {
+                (reflect(&p1.pos, &offset, &direction) - p2.pos).norm_squared()
+            }
             _ => 0.0,
         }
     }
@@ -267,6 +336,17 @@ impl BiConstraint {
                 ((ortho_1 - ortho_2).norm() - x).powi(2)
             }
             ConstraintType::Angle { x } => (l1.direction.angle(&l2.direction) - x).powi(2),
+            ConstraintType::EqualLength => (l1.direction.norm() - l2.direction.norm()).powi(2),
+            ConstraintType::Symmetric { offset, direction } => {
+                let mirrored_offset = reflect(&l1.offset, &offset, &direction);
+                let mirrored_direction = reflect(&l1.direction, &Vector2::zeros(), &direction);
+                // Both lines have to point the same way and pass through the same points
+                let sin_angle = mirrored_direction.perp(&l2.direction)
+                    / (mirrored_direction.norm() * l2.direction.norm());
+                let diff = l2.offset - mirrored_offset;
+                let ortho = diff - project(&diff, &mirrored_direction);
+                sin_angle.powi(2) + ortho.norm_squared()
+            }
             _ => 0.0,
         }
     }
@@ -300,6 +380,10 @@ impl BiConstraint {
             ConstraintType::Vertical => (c1.pos.x - c2.pos.x).powi(2),
             ConstraintType::Tangent => ((c1.pos - c2.pos).norm() - (c1.radius + c2.radius)).powi(2),
             ConstraintType::Distance { x } => ((c1.pos - c2.pos).norm() - x).powi(2),
+            ConstraintType::Symmetric { offset, direction } => {
+                (reflect(&c1.pos, &offset, &direction) - c2.pos).norm_squared()
+                    + (c1.radius - c2.radius).powi(2)
+            }
             _ => 0.0,
         }
     }
@@ -826,4 +910,54 @@ mod tests {
             &ConstraintType::Angle { x: 0.0 }
         ));
     }
+
+    #[test]
+    fn equal_length_and_symmetric_errors() {
+        let line = |x: f64, y: f64, dx: f64, dy: f64| GeometricEntity::Line {
+            offset: Vector2::new(x, y),
+            direction: Vector2::new(dx, dy),
+        };
+        let y_axis = ConstraintType::Symmetric {
+            offset: Vector2::zeros(),
+            direction: Vector2::y(),
+        };
+
+        assert!(BiConstraint::possible(
+            &line(0.0, 0.0, 1.0, 0.0),
+            &line(0.0, 0.0, 0.0, 1.0),
+            &ConstraintType::EqualLength
+        ));
+        let equal = BiConstraint::error(
+            &line(0.0, 0.0, 3.0, 4.0),
+            &line(1.0, 1.0, 0.0, 5.0),
+            &ConstraintType::EqualLength,
+        );
+        assert!(equal.abs() < 1e-12);
+        let unequal = BiConstraint::error(
+            &line(0.0, 0.0, 3.0, 4.0),
+            &line(1.0, 1.0, 0.0, 4.0),
+            &ConstraintType::EqualLength,
+        );
+        assert!((unequal - 1.0).abs() < 1e-12);
+
+        let point = |x: f64, y: f64| GeometricEntity::Point {
+            pos: Vector2::new(x, y),
+        };
+        assert!(BiConstraint::error(&point(1.0, 2.0), &point(-1.0, 2.0), &y_axis).abs() < 1e-12);
+        assert!(BiConstraint::error(&point(1.0, 2.0), &point(1.0, 2.0), &y_axis) > 1.0);
+
+        // A line leaning right mirrors into a line leaning left
+        let mirrored = BiConstraint::error(
+            &line(1.0, 0.0, 1.0, 1.0),
+            &line(-2.0, 1.0, -1.0, 1.0),
+            &y_axis,
+        );
+        assert!(mirrored.abs() < 1e-12);
+        let not_mirrored = BiConstraint::error(
+            &line(1.0, 0.0, 1.0, 1.0),
+            &line(-2.0, 1.0, 1.0, 1.0),
+            &y_axis,
+        );
+        assert!(not_mirrored > 0.1);
+    }
 }
@@ -4,10 +4,14 @@
     clippy::uninlined_format_args
 )]
 
-use std::{error::Error, path::PathBuf};
+use std::{
+    error::Error,
+    fs::File,
+    path::{Path, PathBuf},
+};
 
-use curvo::prelude::{NurbsCurve3D, NurbsSurface3D};
-use nalgebra::{Point3, Vector2};
+use curvo::prelude::{NurbsCurve2D, NurbsSurface3D, TrimmedSurface};
+use nalgebra::{Point2, Point3, Point4, Vector2};
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -15,6 +19,7 @@ use crate::{
     topology::{Face, Solid},
 };
 
+pub mod dxf;
 pub mod entity;
 pub mod registry;
 pub mod sketch;
@@ -51,6 +56,32 @@ impl SketchInfo {
     pub fn sketch_space_to_scene_space(&self, v: Vector2<f64>) -> Point3<f64> {
         Point3::from(self.plane.x * v.x + self.plane.y * v.y)
     }
+
+    /// The corners of `face` in sketch space, in the order they're visited when walking around it.
+    /// Only faces made of capped lines have well defined corners for now.
+    fn face_corners(&self, face: &Face) -> Result<Vec<Vector2<f64>>, Box<dyn Error>> {
+        for topo_id in &face.ids {
+            match self.sketch.topo_entities[*topo_id] {
+                topology::TopoEntity::Edge {
+                    edge: topology::Edge::CappedLine { .. },
+                } => {}
+                topology::TopoEntity::Edge {
+                    edge: topology::Edge::ArcThreePoint { .. },
+                } => return Err("Arc edges aren't supported (yet)".into()),
+                _ => return Err("A face shouldn't contain ids for non-edge entities".into()),
+            }
+        }
+        self.sketch
+            .face_vertices(face)?
+            .into_iter()
+            .map(|id| {
+                let p: entity::Point = self.sketch.geo_entities[id]
+                    .try_into()
+                    .map_err(|e| format!("{:?} is not a point", e))?;
+                Ok(p.pos)
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -61,6 +92,26 @@ pub struct Scene {
 }
 
 impl Scene {
+    /// Writes the scene as JSON to [Self::path]
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = self
+            .path
+            .as_ref()
+            .ok_or("The scene doesn't have a path to save to")?;
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    /// Reads a scene written by [Self::save]. The scene remembers `path` so that it is saved back
+    /// to the same file.
+    pub fn load(path: &Path) -> Result<Scene, Box<dyn Error>> {
+        let contents = std::fs::read_to_string(path)?;
+        let mut scene: Scene = serde_json::from_str(&contents)?;
+        scene.path = Some(path.to_path_buf());
+        Ok(scene)
+    }
+
     pub fn add_sketch(&mut self, plane: Plane) {
         let name = format!("Sketch {}", self.sketches.len() + 1);
         self.sketches.push(SketchInfo {
@@ -72,49 +123,215 @@ impl Scene {
         });
     }
 
-    pub fn extrude(&mut self, sketch_id: u16, face: Face, distance: f64) -> Solid {
-        todo!()
+    fn sketch(&self, sketch_id: u16) -> Result<&SketchInfo, Box<dyn Error>> {
+        self.sketches
+            .iter()
+            .find(|si| si.id == sketch_id)
+            .ok_or(format!("Sketch of id {} not found", sketch_id).into())
     }
 
-    // TODO: This is wrong. I want a NurbsSurface, not a Curve
-    pub fn face_to_curve(
-        &self,
+    /// Sweeps `face` of the sketch `sketch_id` along the normal of the sketch's plane and adds the
+    /// result to the scene. Negative distances extrude in the opposite direction. Only faces made
+    /// of capped lines can be extruded for now.
+    pub fn extrude(
+        &mut self,
         sketch_id: u16,
         face: Face,
-    ) -> Result<NurbsCurve3D<f64>, Box<dyn Error>> {
-        // Only handle capped lines for now. Arcs will be approximated by many points
-        // Get the corners in 3D space
-        // Construct curve
-        let si = self
-            .sketches
-            .iter()
-            .find(|si| si.id == sketch_id)
-            .ok_or(format!("Sketch of id {} not found", sketch_id))?;
-        let mut points: Vec<entity::Point> = vec![];
-        for (i, topo_id) in face.ids.iter().enumerate() {
-            match si.sketch.topo_entities[*topo_id] {
-                topology::TopoEntity::Edge { edge } => match edge {
-                    topology::Edge::CappedLine { start, end, .. } => {
-                        points.push(si.sketch.geo_entities[start].try_into().unwrap());
-                        if i == face.ids.len() - 1 {
-                            points.push(si.sketch.geo_entities[end].try_into().unwrap());
-                        }
-                    }
-                    topology::Edge::ArcThreePoint { .. } => {
-                        todo!("Arc edges cant be rasterized (yet)")
-                    }
-                },
-                _ => return Err("A face shouldn't contain ids for non-edge entities".into()),
-            }
+        distance: f64,
+    ) -> Result<Solid, Box<dyn Error>> {
+        if distance == 0.0 {
+            return Err("Can't extrude a face by zero".into());
         }
-
-        let points_3d: Vec<Point3<f64>> = points
+        let si = self.sketch(sketch_id)?;
+        let mut corners = si.face_corners(&face)?;
+        // The prism wants the base wound counterclockwise around the extrusion direction
+        let signed_area: f64 = (0..corners.len())
+            .map(|i| corners[i].perp(&corners[(i + 1) % corners.len()]))
+            .sum();
+        if (signed_area < 0.0) != (distance < 0.0) {
+            corners.reverse();
+        }
+        let base: Vec<Point3<f64>> = corners
             .into_iter()
-            .map(|p| si.sketch_space_to_scene_space(p.pos))
+            .map(|p| si.sketch_space_to_scene_space(p))
+            .collect();
+
+        let solid = Solid::prism((sketch_id, face), &base, si.plane.normal() * distance);
+        self.solids.push(solid.clone());
+        Ok(solid)
+    }
+
+    /// Builds a flat bilinear surface in the plane of the sketch spanning the bounding rectangle
+    /// of `face`, trimmed by the face's edge loop. The trim lives in the surface's parameter space,
+    /// where `u` follows the plane's x-axis and `v` its y-axis.
+    pub fn face_to_surface(
+        &self,
+        sketch_id: u16,
+        face: &Face,
+    ) -> Result<TrimmedSurface<f64>, Box<dyn Error>> {
+        let si = self.sketch(sketch_id)?;
+        let corners = si.face_corners(face)?;
+        let (min, max) = corners.iter().fold(
+            (
+                Vector2::repeat(f64::INFINITY),
+                Vector2::repeat(f64::NEG_INFINITY),
+            ),
+            |(min, max), p| (min.inf(p), max.sup(p)),
+        );
+        if min.x >= max.x || min.y >= max.y {
+            return Err("The face doesn't cover any area".into());
+        }
+
+        let control_point = |x: f64, y: f64| {
+            let p = si.sketch_space_to_scene_space(Vector2::new(x, y));
+            Point4::new(p.x, p.y, p.z, 1.0)
+        };
+        // Indexed as [u][v]
+        let control_points = vec![
+            vec![control_point(min.x, min.y), control_point(min.x, max.y)],
+            vec![control_point(max.x, min.y), control_point(max.x, max.y)],
+        ];
+        let knots = vec![0.0, 0.0, 1.0, 1.0];
+        let surface = NurbsSurface3D::new(1, 1, knots.clone(), knots, control_points);
+
+        let size = max - min;
+        let trim: Vec<Point2<f64>> = corners
+            .iter()
+            .chain(corners.first())
+            .map(|p| Point2::from((p - min).component_div(&size)))
             .collect();
-        //NurbsSurface3D::new(1, 1, u_knots, v_knots, control_points);
-        //let curve = NurbsCurve3D::bezier(&points_3d);
-        //Ok(curve)
-        todo!()
+        let exterior = NurbsCurve2D::polyline(&trim, true);
+        Ok(TrimmedSurface::new(surface, Some(exterior.into()), vec![]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Vector2, Vector3};
+
+    use super::*;
+
+    #[test]
+    fn extrude_rectangle_into_box() {
+        let mut scene = Scene {
+            path: None,
+            sketches: vec![],
+            solids: vec![],
+        };
+        scene.add_sketch(Plane {
+            x: Vector3::x(),
+            y: Vector3::y(),
+        });
+        let sketch = &mut scene.sketches[0].sketch;
+        sketch.insert_capped_lines(&[
+            Vector2::new(0.0, 0.0),
+            Vector2::new(2.0, 0.0),
+            Vector2::new(2.0, 1.0),
+            Vector2::new(0.0, 1.0),
+            Vector2::new(0.0, 0.0),
+        ]);
+        let face = sketch.loops[0].clone();
+
+        let solid = scene.extrude(0, face, 3.0).unwrap();
+        assert_eq!(solid.vertices.len(), 8);
+        assert_eq!(solid.polygons.len(), 6);
+        assert_eq!(solid.triangles().len(), 12);
+        assert_eq!(scene.solids.len(), 1);
+
+        // Every normal should point away from the center of the box
+        let center = Vector3::new(1.0, 0.5, 1.5);
+        for polygon in &solid.polygons {
+            let on_face = solid.vertices[polygon[0]].coords - center;
+            assert!(solid.normal(polygon).dot(&on_face) > 0.0);
+        }
+    }
+
+    #[test]
+    fn scene_round_trips_through_file() {
+        let mut scene = Scene {
+            path: None,
+            sketches: vec![],
+            solids: vec![],
+        };
+        assert!(
+            scene.save().is_err(),
+            "A scene without a path can't be saved"
+        );
+
+        scene.add_sketch(Plane {
+            x: Vector3::x(),
+            y: Vector3::y(),
+        });
+        scene.sketches[0]
+            .sketch
+            .insert_point(Vector2::new(1.0, 2.0));
+        // The process id keeps concurrent test runs from sharing the file
+        let path =
+            std::env::temp_dir().join(format!("cad_scene_round_trip_{}.json", std::process::id()));
+        scene.path = Some(path.clone());
+        scene.save().unwrap();
+
+        let loaded = Scene::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.path, Some(path));
+        assert_eq!(loaded.sketches.len(), 1);
+        assert_eq!(loaded.sketches[0].name, "Sketch 1");
+        assert_eq!(loaded.sketches[0].sketch.topo_entities.len(), 1);
+    }
+
+    #[test]
+    fn face_to_planar_surface() {
+        let mut scene = Scene {
+            path: None,
+            sketches: vec![],
+            solids: vec![],
+        };
+        scene.add_sketch(Plane {
+            x: Vector3::x(),
+            y: Vector3::z(),
+        });
+        let sketch = &mut scene.sketches[0].sketch;
+        sketch.insert_capped_lines(&[
+            Vector2::new(1.0, 1.0),
+            Vector2::new(3.0, 1.0),
+            Vector2::new(3.0, 2.0),
+            Vector2::new(1.0, 2.0),
+            Vector2::new(1.0, 1.0),
+        ]);
+        let face = sketch.loops[0].clone();
+
+        let surface = scene.face_to_surface(0, &face).unwrap();
+        let center = surface.surface().point_at(0.5, 0.5);
+        assert!((center - Point3::new(2.0, 0.0, 1.5)).norm() < 1e-10);
+        assert!(scene.face_to_surface(1, &face).is_err());
+
+        let sketch = &mut scene.sketches[0].sketch;
+        sketch.insert_capped_lines(&[
+            Vector2::new(5.0, 1.0),
+            Vector2::new(7.0, 1.0),
+            Vector2::new(6.0, 2.0),
+            Vector2::new(5.0, 1.0),
+        ]);
+        let triangle = sketch
+            .loops
+            .iter()
+            .find(|l| l.ids.len() == 3)
+            .unwrap()
+            .clone();
+        let surface = scene.face_to_surface(0, &triangle).unwrap();
+        let corner = surface.surface().point_at(1.0, 1.0);
+        assert!((corner - Point3::new(7.0, 0.0, 2.0)).norm() < 1e-10);
+
+        // The trim walks the triangle's corners in parameter space
+        let trim = surface.exterior().unwrap();
+        assert!(trim.is_closed(None));
+        let corners = trim.spans()[0].dehomogenized_control_points();
+        for expected in [
+            Point2::new(0.0, 0.0),
+            Point2::new(1.0, 0.0),
+            Point2::new(0.5, 1.0),
+        ] {
+            assert!(corners.iter().any(|p| (p - expected).norm() < 1e-10));
+        }
     }
 }
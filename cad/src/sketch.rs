@@ -1,20 +1,105 @@
+use std::collections::{HashMap, VecDeque};
 use std::error::Error;
+use std::f64::consts::PI;
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
 
-use nalgebra::Vector2;
+use nalgebra::{DMatrix, DVector, Vector2};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use tracing::error;
 
-use crate::entity::{self, BiConstraint, Circle, GeoId, GeometricEntity, Point};
+use crate::dxf::{DxfEntity, parse_entities};
+use crate::entity::{
+    self, BiConstraint, Circle, ConstraintType, GeoId, GeometricEntity, Point, reflect,
+};
 use crate::registry::Registry;
 use crate::topology::{
     ArcThreePoint, CappedLine, Edge, Face, ParametrizedIntersection, TopoEntity, TopoId,
 };
 
 const EQ_TOL: f64 = 1e-10;
+/// Number of edits that can be undone. The oldest snapshots are dropped past this.
+const MAX_UNDO: usize = 100;
+
+/// An edge walked in one direction, as `(edge, from, to)`
+type HalfEdge = (TopoId, GeoId, GeoId);
+
+/// The end points of every capped line, as `(start, end)`, keyed by the line it was built on
+type LineEndpoints = HashMap<GeoId, (GeoId, GeoId)>;
+
+/// What one side of a constraint is evaluated on
+#[derive(Debug, Clone, Copy)]
+enum Operand {
+    Entity(GeoId),
+    /// The line from the start to the end point of a capped line
+    Span(GeoId, GeoId),
+}
+
+/// Signed area between the arc from `start` through `middle` to `end` and its chord. Positive when
+/// the arc bulges to the right of the chord, which is when it adds to a counterclockwise loop.
+fn arc_segment_area(start: Vector2<f64>, middle: Vector2<f64>, end: Vector2<f64>) -> f64 {
+    // The angle at the middle point subtends the chord, and is half of what's left of a full turn
+    // after the arc's sweep
+    let inscribed = (start - middle).angle(&(end - middle));
+    if inscribed.sin() < EQ_TOL {
+        return 0.0;
+    }
+    let sweep = 2.0 * PI - 2.0 * inscribed;
+    let radius = (end - start).norm() / (2.0 * inscribed.sin());
+    let area = radius.powi(2) / 2.0 * (sweep - sweep.sin());
+    if (end - start).perp(&(middle - start)) < 0.0 {
+        area
+    } else {
+        -area
+    }
+}
+
+/// Rounds `pos` to the nearest intersection of a square grid through the origin with `spacing`
+/// between its lines. Non-positive spacings leave the position as is.
+pub fn snap_to_grid(pos: Vector2<f64>, spacing: f64) -> Vector2<f64> {
+    if spacing <= 0.0 {
+        return pos;
+    }
+    pos.map(|x| (x / spacing).round() * spacing)
+}
+
+/// Rotates `pos` around `origin` to the nearest direction which is a multiple of
+/// `increment_degrees` from the x-axis, keeping its distance to `origin`. An increment of 90
+/// degrees gives horizontal and vertical lines, 45 degrees also allows diagonals.
+pub fn snap_to_angle(
+    origin: Vector2<f64>,
+    pos: Vector2<f64>,
+    increment_degrees: f64,
+) -> Vector2<f64> {
+    let delta = pos - origin;
+    let length = delta.norm();
+    if increment_degrees <= 0.0 || length < EQ_TOL {
+        return pos;
+    }
+    let increment = increment_degrees.to_radians();
+    let angle = (delta.y.atan2(delta.x) / increment).round() * increment;
+    origin + Vector2::new(angle.cos(), angle.sin()) * length
+}
+
+/// How far a solver got before stopping
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SolveResult {
+    /// Number of steps taken
+    pub iterations: usize,
+    /// [Sketch::error] after the last step
+    pub error: f64,
+}
+
+/// Everything about a sketch that can be edited, as saved by [Sketch::checkpoint]
+#[derive(Debug, Clone)]
+struct SketchSnapshot {
+    geo_entities: Registry<GeoId, GeometricEntity>,
+    topo_entities: Registry<TopoId, TopoEntity>,
+    bi_constraints: Vec<BiConstraint>,
+    loops: Vec<Face>,
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Sketch {
@@ -24,6 +109,10 @@ pub struct Sketch {
     pub bi_constraints: Vec<BiConstraint>,
     pub loops: Vec<Face>,
     step_size: f64,
+    #[serde(skip)]
+    undo_stack: VecDeque<SketchSnapshot>,
+    #[serde(skip)]
+    redo_stack: Vec<SketchSnapshot>,
 }
 
 impl Sketch {
@@ -35,7 +124,57 @@ impl Sketch {
             bi_constraints: Vec::new(),
             loops: Vec::new(),
             step_size: 1e-2,
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+        }
+    }
+
+    fn snapshot(&self) -> SketchSnapshot {
+        SketchSnapshot {
+            geo_entities: self.geo_entities.clone(),
+            topo_entities: self.topo_entities.clone(),
+            bi_constraints: self.bi_constraints.clone(),
+            loops: self.loops.clone(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: SketchSnapshot) {
+        self.geo_entities = snapshot.geo_entities;
+        self.topo_entities = snapshot.topo_entities;
+        self.bi_constraints = snapshot.bi_constraints;
+        self.loops = snapshot.loops;
+    }
+
+    /// Saves the current state of the sketch so that it can be returned to with [Self::undo].
+    /// Should be called right before every edit. Anything undone before is no longer redoable.
+    pub fn checkpoint(&mut self) {
+        if self.undo_stack.len() == MAX_UNDO {
+            self.undo_stack.pop_front();
         }
+        self.undo_stack.push_back(self.snapshot());
+        self.redo_stack.clear();
+    }
+
+    /// Returns to the state of the last [Self::checkpoint]. Returns false if there is nothing to
+    /// undo.
+    pub fn undo(&mut self) -> bool {
+        let Some(snapshot) = self.undo_stack.pop_back() else {
+            return false;
+        };
+        self.redo_stack.push(self.snapshot());
+        self.restore(snapshot);
+        true
+    }
+
+    /// Reapplies the last edit reverted by [Self::undo]. Returns false if there is nothing to
+    /// redo.
+    pub fn redo(&mut self) -> bool {
+        let Some(snapshot) = self.redo_stack.pop() else {
+            return false;
+        };
+        self.undo_stack.push_back(self.snapshot());
+        self.restore(snapshot);
+        true
     }
 
     pub fn from_path(path: &Path) -> Result<Self, Box<dyn Error>> {
@@ -44,28 +183,274 @@ impl Sketch {
         serde_json::from_str(&contents).map_err(|e| Box::from(e))
     }
 
+    /// Reads the lines, circles and arcs of a DXF file. Other entity types are skipped. End points
+    /// closer than 1e-5 are merged so that closed profiles form loops.
+    pub fn from_dxf(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut contents = String::new();
+        File::open(path)?.read_to_string(&mut contents)?;
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Self::from_dxf_source(name, &contents)
+    }
+
+    fn from_dxf_source(name: String, source: &str) -> Result<Self, Box<dyn Error>> {
+        let mut sketch = Self::new(name);
+        for entity in parse_entities(source)? {
+            match entity {
+                DxfEntity::Line { start, end } => {
+                    let start = sketch.query_or_insert_point(&start, 1e-5);
+                    let end = sketch.query_or_insert_point(&end, 1e-5);
+                    sketch.insert_capped_line_between(start, end);
+                }
+                DxfEntity::Circle { center, radius } => sketch.insert_circle(center, radius),
+                DxfEntity::Arc {
+                    center,
+                    radius,
+                    start_angle,
+                    end_angle,
+                } => {
+                    let start_angle = start_angle.to_radians();
+                    let mut end_angle = end_angle.to_radians();
+                    if end_angle <= start_angle {
+                        end_angle += 2.0 * PI;
+                    }
+                    let at = |angle: f64| center + radius * Vector2::new(angle.cos(), angle.sin());
+                    let start = sketch.query_or_insert_point(&at(start_angle), 1e-5);
+                    let end = sketch.query_or_insert_point(&at(end_angle), 1e-5);
+                    let middle = sketch.geo_entities.insert(GeometricEntity::Point {
+                        pos: at((start_angle + end_angle) / 2.0),
+                    });
+                    let circle = sketch.geo_entities.insert(GeometricEntity::Circle {
+                        pos: center,
+                        radius,
+                    });
+                    sketch.topo_entities.insert(
+                        ArcThreePoint {
+                            start,
+                            middle,
+                            end,
+                            circle,
+                        }
+                        .into(),
+                    );
+                }
+            }
+        }
+        sketch.loops = sketch.find_loops();
+        Ok(sketch)
+    }
+
     pub fn error(&self) -> f64 {
-        let mut sum = 0.0;
-        for BiConstraint { e1, e2, c } in &self.bi_constraints {
-            sum += BiConstraint::error(&self.geo_entities[*e1], &self.geo_entities[*e2], c);
+        self.error_with(&self.line_endpoints())
+    }
+
+    fn error_with(&self, endpoints: &LineEndpoints) -> f64 {
+        self.bi_constraints
+            .iter()
+            .map(|constraint| self.constraint_error(constraint, endpoints))
+            .sum()
+    }
+
+    fn constraint_error(&self, constraint: &BiConstraint, endpoints: &LineEndpoints) -> f64 {
+        let [o1, o2] = self.operands(constraint, endpoints);
+        BiConstraint::error(&self.resolve(o1), &self.resolve(o2), &constraint.c)
+    }
+
+    /// What each side of `constraint` is evaluated on. [ConstraintType::EqualLength] is measured
+    /// between the end points of the capped lines, since the solver can scale the direction of a
+    /// line freely.
+    fn operands(
+        &self,
+        BiConstraint { e1, e2, c }: &BiConstraint,
+        endpoints: &LineEndpoints,
+    ) -> [Operand; 2] {
+        [*e1, *e2].map(|id| match (c, endpoints.get(&id)) {
+            (ConstraintType::EqualLength, Some((start, end))) => Operand::Span(*start, *end),
+            _ => Operand::Entity(id),
+        })
+    }
+
+    fn resolve(&self, operand: Operand) -> GeometricEntity {
+        match operand {
+            Operand::Entity(id) => self.geo_entities[id],
+            Operand::Span(start, end) => {
+                let offset = self.get_point_pos(start);
+                GeometricEntity::Line {
+                    offset,
+                    direction: self.get_point_pos(end) - offset,
+                }
+            }
+        }
+    }
+
+    /// Stores `entity` back into whatever `operand` was resolved from
+    fn write_back(&mut self, operand: Operand, entity: GeometricEntity) {
+        match (operand, entity) {
+            (Operand::Entity(id), _) => self.geo_entities[id] = entity,
+            (Operand::Span(start, end), GeometricEntity::Line { offset, direction }) => {
+                self.geo_entities[start] = GeometricEntity::Point { pos: offset };
+                self.geo_entities[end] = GeometricEntity::Point {
+                    pos: offset + direction,
+                };
+            }
+            (Operand::Span(..), _) => unreachable!("Spans always resolve to lines"),
         }
-        sum
+    }
+
+    fn line_endpoints(&self) -> LineEndpoints {
+        self.topo_entities
+            .values()
+            .filter_map(|e| match *e {
+                TopoEntity::Edge {
+                    edge: Edge::CappedLine { start, end, line },
+                } => Some((line, (start, end))),
+                _ => None,
+            })
+            .collect()
     }
 
     pub fn sgd_step(&mut self) {
+        self.sgd_step_with(&self.line_endpoints());
+    }
+
+    fn sgd_step_with(&mut self, endpoints: &LineEndpoints) {
         let mut rng = rand::rng();
-        for BiConstraint { e1, e2, c } in &self.bi_constraints {
-            let [fe1, fe2] = self.geo_entities.get_disjoint_mut([e1, e2]);
-            let fe1 = fe1.unwrap();
-            let fe2 = fe2.unwrap();
+        for i in 0..self.bi_constraints.len() {
+            let constraint = self.bi_constraints[i];
+            let mut operands = self.operands(&constraint, endpoints);
             if rng.random_bool(0.5) {
-                BiConstraint::apply_grad_error(fe1, fe2, c, self.step_size);
-            } else {
-                BiConstraint::apply_grad_error(fe2, fe1, c, self.step_size);
+                operands.reverse();
             }
+            let [moved, other] = operands;
+            let mut entity = self.resolve(moved);
+            BiConstraint::apply_grad_error(
+                &mut entity,
+                &self.resolve(other),
+                &constraint.c,
+                self.step_size,
+            );
+            self.write_back(moved, entity);
         }
     }
 
+    /// Runs [Sketch::sgd_step] until the error is below `tol` or `max_iters` steps have been taken
+    pub fn solve(&mut self, max_iters: usize, tol: f64) -> SolveResult {
+        let endpoints = self.line_endpoints();
+        let mut error = self.error_with(&endpoints);
+        let mut iterations = 0;
+        while error >= tol && iterations < max_iters {
+            self.sgd_step_with(&endpoints);
+            error = self.error_with(&endpoints);
+            iterations += 1;
+        }
+        SolveResult { iterations, error }
+    }
+
+    /// Solves the constraints with damped Gauss-Newton (Levenberg-Marquardt) steps until the
+    /// error is below `tol` or `max_iters` steps have been taken. Stops early if no step reduces
+    /// the error any further.
+    ///
+    /// The residual of each constraint is the square root of its error, and the Jacobian is found
+    /// with central differences over the parameters of every constrained entity.
+    pub fn solve_newton(&mut self, max_iters: usize, tol: f64) -> SolveResult {
+        let endpoints = self.line_endpoints();
+        let mut parameters: Vec<(GeoId, usize)> = vec![];
+        for constraint in &self.bi_constraints {
+            let ids = self
+                .operands(constraint, &endpoints)
+                .into_iter()
+                .flat_map(|operand| match operand {
+                    Operand::Entity(id) => vec![id],
+                    Operand::Span(start, end) => vec![start, end],
+                });
+            for id in ids {
+                if !parameters.iter().any(|(other, _)| *other == id) {
+                    let count = self.geo_entities[id].parameter_count();
+                    parameters.extend((0..count).map(|i| (id, i)));
+                }
+            }
+        }
+
+        let h = 1e-7;
+        let mut damping = 1e-3;
+        let mut residuals = self.residuals(&endpoints);
+        for i in 0..max_iters {
+            let error = self.error_with(&endpoints);
+            if error < tol {
+                return SolveResult {
+                    iterations: i,
+                    error,
+                };
+            }
+
+            let mut jacobian = DMatrix::<f64>::zeros(residuals.len(), parameters.len());
+            for (j, (id, k)) in parameters.iter().enumerate() {
+                let original = *self.geo_entities[*id].parameter_mut(*k);
+                *self.geo_entities[*id].parameter_mut(*k) = original + h / 2.0;
+                let forward = self.residuals(&endpoints);
+                *self.geo_entities[*id].parameter_mut(*k) = original - h / 2.0;
+                let backward = self.residuals(&endpoints);
+                *self.geo_entities[*id].parameter_mut(*k) = original;
+                jacobian.set_column(j, &((forward - backward) / h));
+            }
+
+            let gradient = jacobian.transpose() * &residuals;
+            let approximate_hessian = jacobian.transpose() * &jacobian;
+            let original: Vec<f64> = parameters
+                .iter()
+                .map(|(id, k)| *self.geo_entities[*id].parameter_mut(*k))
+                .collect();
+            let residual_error = residuals.norm_squared();
+
+            // Stiffen the step until it reduces the error. Constraints whose residual isn't
+            // smooth at the solution can otherwise bounce back and forth across it.
+            let mut improved = false;
+            for _ in 0..16 {
+                let system = &approximate_hessian
+                    + DMatrix::identity(parameters.len(), parameters.len()) * damping;
+                let Some(step) = system.cholesky().map(|c| c.solve(&-&gradient)) else {
+                    damping *= 10.0;
+                    continue;
+                };
+                for ((id, k), (x, dx)) in parameters.iter().zip(original.iter().zip(step.iter())) {
+                    *self.geo_entities[*id].parameter_mut(*k) = x + dx;
+                }
+                let candidate = self.residuals(&endpoints);
+                if candidate.norm_squared() < residual_error {
+                    residuals = candidate;
+                    damping = (damping / 3.0).max(1e-12);
+                    improved = true;
+                    break;
+                }
+                damping *= 2.0;
+            }
+            if !improved {
+                for ((id, k), x) in parameters.iter().zip(original) {
+                    *self.geo_entities[*id].parameter_mut(*k) = x;
+                }
+                return SolveResult {
+                    iterations: i,
+                    error: self.error_with(&endpoints),
+                };
+            }
+        }
+        SolveResult {
+            iterations: max_iters,
+            error: self.error_with(&endpoints),
+        }
+    }
+
+    fn residuals(&self, endpoints: &LineEndpoints) -> DVector<f64> {
+        DVector::from_iterator(
+            self.bi_constraints.len(),
+            self.bi_constraints
+                .iter()
+                .map(|constraint| self.constraint_error(constraint, endpoints).max(0.0).sqrt()),
+        )
+    }
+
     fn query_point(&self, query_pos: &Vector2<f64>, radius: f64) -> Option<GeoId> {
         let mut closest_id = None;
         let mut closest_dist = f64::INFINITY;
@@ -153,6 +538,209 @@ impl Sketch {
         out
     }
 
+    /// Connects two existing points with a capped line, without checking for intersections
+    fn insert_capped_line_between(&mut self, start: GeoId, end: GeoId) -> TopoId {
+        let start_pos = self.get_point_pos(start);
+        let line = self.geo_entities.insert(GeometricEntity::Line {
+            offset: start_pos,
+            direction: self.get_point_pos(end) - start_pos,
+        });
+        self.topo_entities
+            .insert(CappedLine { start, end, line }.into())
+    }
+
+    /// Removes the part of the capped line `line_id` around `pick_point` that lies between the
+    /// nearest lines crossing it. The crossing lines are split so that the remaining parts of
+    /// `line_id` end on a shared point. The whole line is removed if nothing crosses it.
+    pub fn trim(
+        &mut self,
+        line_id: TopoId,
+        pick_point: Vector2<f64>,
+    ) -> Result<(), Box<dyn Error>> {
+        let line: CappedLine = self.topo_entities[line_id].try_into()?;
+        let (p, v) = line.parametrize(&self.geo_entities);
+        let t_pick = (pick_point - p).dot(&v) / v.norm_squared();
+
+        // The crossings are sorted along the line
+        let mut before = None;
+        let mut after = None;
+        for (other_id, _, point) in self.intersecting_capped_lines(line) {
+            let t = (point.pos - p).dot(&v) / v.norm_squared();
+            if t < t_pick {
+                before = Some((other_id, point));
+            } else if after.is_none() {
+                after = Some((other_id, point));
+            }
+        }
+        let before = before.map(|(other_id, point)| self.split_capped_line(other_id, point).0.end);
+        let after = after.map(|(other_id, point)| self.split_capped_line(other_id, point).0.end);
+
+        self.topo_entities.remove(&line_id);
+        self.remove_geo_entity(line.line);
+        if let Some(before) = before {
+            self.insert_capped_line_between(line.start, before);
+        }
+        if let Some(after) = after {
+            self.insert_capped_line_between(after, line.end);
+        }
+        for point in [line.start, line.end] {
+            if !self.is_referenced(point) {
+                self.remove_geo_entity(point);
+            }
+        }
+        self.loops = self.find_loops();
+        Ok(())
+    }
+
+    /// Rounds the corner where the capped lines `line_a` and `line_b` meet with an arc of `radius`
+    /// tangent to both. The lines are shortened to end where the arc starts and the id of the arc
+    /// is returned.
+    pub fn fillet(
+        &mut self,
+        line_a: TopoId,
+        line_b: TopoId,
+        radius: f64,
+    ) -> Result<TopoId, Box<dyn Error>> {
+        let a: CappedLine = self.topo_entities[line_a].try_into()?;
+        let b: CappedLine = self.topo_entities[line_b].try_into()?;
+        let corner = [a.start, a.end]
+            .into_iter()
+            .find(|p| *p == b.start || *p == b.end)
+            .ok_or("The lines don't share a vertex")?;
+        let far_a = if a.start == corner { a.end } else { a.start };
+        let far_b = if b.start == corner { b.end } else { b.start };
+
+        let corner_pos = self.get_point_pos(corner);
+        let to_a = self.get_point_pos(far_a) - corner_pos;
+        let to_b = self.get_point_pos(far_b) - corner_pos;
+        let half_angle = to_a.angle(&to_b) / 2.0;
+        if half_angle.sin() < EQ_TOL || half_angle.cos() < EQ_TOL {
+            return Err("Lines that are parallel don't form a corner".into());
+        }
+        // How far from the corner the arc touches each line
+        let setback = radius / half_angle.tan();
+        if radius <= 0.0 || setback > to_a.norm() || setback > to_b.norm() {
+            return Err(format!("A radius of {} doesn't fit in the corner", radius).into());
+        }
+
+        let bisector = (to_a.normalize() + to_b.normalize()).normalize();
+        let center = corner_pos + bisector * (radius / half_angle.sin());
+        let tangent_a = self.geo_entities.insert(GeometricEntity::Point {
+            pos: corner_pos + to_a.normalize() * setback,
+        });
+        let tangent_b = self.geo_entities.insert(GeometricEntity::Point {
+            pos: corner_pos + to_b.normalize() * setback,
+        });
+        let middle = self.geo_entities.insert(GeometricEntity::Point {
+            pos: center - bisector * radius,
+        });
+        let circle = self.geo_entities.insert(GeometricEntity::Circle {
+            pos: center,
+            radius,
+        });
+
+        for (id, line, tangent) in [(line_a, a, tangent_a), (line_b, b, tangent_b)] {
+            let shortened = if line.start == corner {
+                CappedLine {
+                    start: tangent,
+                    ..line
+                }
+            } else {
+                CappedLine {
+                    end: tangent,
+                    ..line
+                }
+            };
+            let start_pos = self.get_point_pos(shortened.start);
+            self.geo_entities[line.line] = GeometricEntity::Line {
+                offset: start_pos,
+                direction: self.get_point_pos(shortened.end) - start_pos,
+            };
+            self.topo_entities[id] = shortened.into();
+        }
+        if !self.is_referenced(corner) {
+            self.remove_geo_entity(corner);
+        }
+
+        let arc = self.topo_entities.insert(
+            ArcThreePoint {
+                start: tangent_a,
+                middle,
+                end: tangent_b,
+                circle,
+            }
+            .into(),
+        );
+        self.loops = self.find_loops();
+        Ok(arc)
+    }
+
+    /// Whether any topological entity is built from the geometric entity `id`
+    fn is_referenced(&self, id: GeoId) -> bool {
+        self.topo_entities.values().any(|e| match *e {
+            TopoEntity::Point { id: other }
+            | TopoEntity::Line { id: other }
+            | TopoEntity::Circle { id: other } => other == id,
+            TopoEntity::Edge {
+                edge: Edge::CappedLine { start, end, line },
+            } => [start, end, line].contains(&id),
+            TopoEntity::Edge {
+                edge:
+                    Edge::ArcThreePoint {
+                        start,
+                        middle,
+                        end,
+                        circle,
+                    },
+            } => [start, middle, end, circle].contains(&id),
+        })
+    }
+
+    /// Constrains `e1` and `e2` to each other. Fails if either entity doesn't exist or if the
+    /// constraint doesn't apply to their kinds of entities, see [BiConstraint::possible].
+    pub fn add_constraint(
+        &mut self,
+        e1: GeoId,
+        e2: GeoId,
+        c: ConstraintType,
+    ) -> Result<(), Box<dyn Error>> {
+        if e1 == e2 {
+            return Err(format!("{e1:?} can't be constrained to itself").into());
+        }
+        let entity1 = self
+            .geo_entities
+            .get(&e1)
+            .ok_or_else(|| format!("{e1:?} doesn't exist"))?;
+        let entity2 = self
+            .geo_entities
+            .get(&e2)
+            .ok_or_else(|| format!("{e2:?} doesn't exist"))?;
+        if !BiConstraint::possible(entity1, entity2, &c) {
+            return Err(format!(
+                "{c:?} can't constrain a {} to a {}",
+                entity1.kind(),
+                entity2.kind()
+            )
+            .into());
+        }
+        if let ConstraintType::EqualLength = c {
+            let endpoints = self.line_endpoints();
+            for id in [e1, e2] {
+                if !endpoints.contains_key(&id) {
+                    return Err(format!("{id:?} isn't the line of a capped line").into());
+                }
+            }
+        }
+        self.bi_constraints.push(BiConstraint::new(e1, e2, c));
+        Ok(())
+    }
+
+    /// Removes a geometric entity along with every constraint on it
+    fn remove_geo_entity(&mut self, id: GeoId) {
+        self.geo_entities.remove(&id);
+        self.bi_constraints.retain(|c| c.e1 != id && c.e2 != id);
+    }
+
     pub fn insert_circle(&mut self, center: Vector2<f64>, radius: f64) {
         // TODO: Check for intersections with existing entities
         // If intersecting:
@@ -166,6 +754,129 @@ impl Sketch {
             .insert(TopoEntity::Circle { id: circle_id });
     }
 
+    /// Inserts copies of `entity_ids` mirrored across the line through `axis_start` and `axis_end`
+    /// and returns the ids of the copies. Mirrored points that land on existing points are merged
+    /// with them, so edges lying on the axis aren't copied at all. Every copied entity is tied to
+    /// its original with a [ConstraintType::Symmetric] constraint.
+    pub fn mirror(
+        &mut self,
+        entity_ids: &[TopoId],
+        axis_start: Vector2<f64>,
+        axis_end: Vector2<f64>,
+    ) -> Vec<TopoId> {
+        let axis = (axis_start, axis_end - axis_start);
+        // Entities referenced by several edges, such as shared corners, are only mirrored once
+        let mut copies: HashMap<GeoId, GeoId> = HashMap::new();
+        let mut out = vec![];
+        for topo_id in entity_ids {
+            let entity = self.topo_entities[*topo_id];
+            let copy = match entity {
+                TopoEntity::Point { id } => {
+                    let copy = self.mirror_geo(id, axis, &mut copies);
+                    (copy != id).then_some(TopoEntity::Point { id: copy })
+                }
+                TopoEntity::Line { id } => Some(TopoEntity::Line {
+                    id: self.mirror_geo(id, axis, &mut copies),
+                }),
+                TopoEntity::Circle { id } => Some(TopoEntity::Circle {
+                    id: self.mirror_geo(id, axis, &mut copies),
+                }),
+                TopoEntity::Edge {
+                    edge: Edge::CappedLine { start, end, line },
+                } => {
+                    let mirrored_start = self.mirror_geo(start, axis, &mut copies);
+                    let mirrored_end = self.mirror_geo(end, axis, &mut copies);
+                    if [mirrored_start, mirrored_end] == [start, end] {
+                        None
+                    } else {
+                        Some(
+                            CappedLine {
+                                start: mirrored_start,
+                                end: mirrored_end,
+                                line: self.mirror_geo(line, axis, &mut copies),
+                            }
+                            .into(),
+                        )
+                    }
+                }
+                TopoEntity::Edge {
+                    edge:
+                        Edge::ArcThreePoint {
+                            start,
+                            middle,
+                            end,
+                            circle,
+                        },
+                } => {
+                    let mirrored_start = self.mirror_geo(start, axis, &mut copies);
+                    let mirrored_middle = self.mirror_geo(middle, axis, &mut copies);
+                    let mirrored_end = self.mirror_geo(end, axis, &mut copies);
+                    if [mirrored_start, mirrored_middle, mirrored_end] == [start, middle, end] {
+                        None
+                    } else {
+                        Some(
+                            ArcThreePoint {
+                                start: mirrored_start,
+                                middle: mirrored_middle,
+                                end: mirrored_end,
+                                circle: self.mirror_geo(circle, axis, &mut copies),
+                            }
+                            .into(),
+                        )
+                    }
+                }
+            };
+            if let Some(copy) = copy {
+                out.push(self.topo_entities.insert(copy));
+            }
+        }
+        self.loops = self.find_loops();
+        out
+    }
+
+    /// Mirrors a single geometric entity across `axis`, given as *(offset, direction)*
+    fn mirror_geo(
+        &mut self,
+        id: GeoId,
+        axis: (Vector2<f64>, Vector2<f64>),
+        copies: &mut HashMap<GeoId, GeoId>,
+    ) -> GeoId {
+        if let Some(copy) = copies.get(&id) {
+            return *copy;
+        }
+        let (axis_offset, axis_direction) = axis;
+        let entity = self.geo_entities[id];
+        let copy = match entity {
+            GeometricEntity::Point { pos } => {
+                self.query_or_insert_point(&reflect(&pos, &axis_offset, &axis_direction), 1e-5)
+            }
+            GeometricEntity::Line { offset, direction } => {
+                self.geo_entities.insert(GeometricEntity::Line {
+                    offset: reflect(&offset, &axis_offset, &axis_direction),
+                    direction: reflect(&direction, &Vector2::zeros(), &axis_direction),
+                })
+            }
+            GeometricEntity::Circle { pos, radius } => {
+                self.geo_entities.insert(GeometricEntity::Circle {
+                    pos: reflect(&pos, &axis_offset, &axis_direction),
+                    radius,
+                })
+            }
+        };
+        if copy != id {
+            self.bi_constraints.push(BiConstraint::new(
+                id,
+                copy,
+                ConstraintType::Symmetric {
+                    offset: axis_offset,
+                    direction: axis_direction,
+                },
+            ));
+        }
+        copies.insert(id, copy);
+        copy
+    }
+
     #[allow(unused)]
     fn dump(&self, name: &str) {
         let mut file = std::fs::File::create(format!(
@@ -208,7 +919,16 @@ impl Sketch {
         for id in &l.ids {
             match self.topo_entities.get(id) {
                 Some(x) => match *x {
-                    TopoEntity::Circle { id: _ } => todo!(),
+                    TopoEntity::Circle { id } => {
+                        let circle: Circle =
+                            (*self.geo_entities.get(&id).unwrap()).try_into().unwrap();
+                        // A ray starting inside the circle crosses it exactly once. From outside
+                        // it either misses, touches or crosses twice, none of which changes the
+                        // parity.
+                        if (point - circle.pos).norm_squared() < circle.radius.powi(2) {
+                            intersections += 1;
+                        }
+                    }
                     TopoEntity::Edge { edge } => match edge {
                         Edge::CappedLine { start, end, line } => {
                             if self.intersects_capped_line(
@@ -323,27 +1043,25 @@ impl Sketch {
     ///
     /// The algorithm:
     /// 1. Builds an adjacency map from vertices to connected edges
-    /// 2. Sorts edges at each vertex by angle (counterclockwise)
+    /// 2. Sorts edges at each vertex by the angle they leave it at (counterclockwise)
     /// 3. Traces faces by following the "next counterclockwise" edge at each vertex
     /// 4. Filters out the outer infinite face (identified by clockwise winding / negative area)
     ///
-    /// Currently only supports CappedLine edges (arcs are skipped).
+    /// Both capped lines and arcs are followed. Arcs leave their end points along the tangent of
+    /// the arc rather than along the chord.
     pub fn find_loops(&self) -> Vec<Face> {
-        use crate::topology::CappedLine;
-        use std::collections::{HashMap, HashSet};
+        use std::collections::HashSet;
 
         // Step 1: Build adjacency map (vertex -> list of (edge_id, other_vertex))
         let mut adjacency: HashMap<GeoId, Vec<(TopoId, GeoId)>> = HashMap::new();
 
         for (topo_id, edge) in self.topo_entities.iter_edges() {
-            // Skip arcs for now, only handle CappedLines
-            let capped_line: CappedLine = match (*edge).try_into() {
-                Ok(line) => line,
-                Err(_) => continue,
+            let (Ok(start), Ok(end)) = (edge.start_point(), edge.end_point()) else {
+                continue;
             };
-
-            let start = capped_line.start;
-            let end = capped_line.end;
+            if start == end {
+                continue;
+            }
 
             adjacency.entry(start).or_default().push((*topo_id, end));
             adjacency.entry(end).or_default().push((*topo_id, start));
@@ -351,61 +1069,47 @@ impl Sketch {
 
         // Step 2: Sort edges at each vertex by angle (counterclockwise)
         for (vertex, edges) in adjacency.iter_mut() {
-            let vertex_pos = self.get_point_pos(*vertex);
-            edges.sort_by(|(_, other_a), (_, other_b)| {
-                let pos_a = self.get_point_pos(*other_a);
-                let pos_b = self.get_point_pos(*other_b);
-                let angle_a = (pos_a - vertex_pos).y.atan2((pos_a - vertex_pos).x);
-                let angle_b = (pos_b - vertex_pos).y.atan2((pos_b - vertex_pos).x);
+            edges.sort_by(|(edge_a, _), (edge_b, _)| {
+                let angle_a = self.departure_angle(*edge_a, *vertex);
+                let angle_b = self.departure_angle(*edge_b, *vertex);
                 angle_a.partial_cmp(&angle_b).unwrap()
             });
         }
 
-        // Step 3: Build lookup structures
-        // half_edge_to_topo: (from, to) -> TopoId of the edge
-        // next_half_edge: (from, to) -> (next_from, next_to) for CCW face traversal
-        let mut half_edge_to_topo: HashMap<(GeoId, GeoId), TopoId> = HashMap::new();
-        let mut next_half_edge: HashMap<(GeoId, GeoId), (GeoId, GeoId)> = HashMap::new();
+        // Step 3: Build the next-half-edge lookup for CCW face traversal. Half-edges are stored as
+        // (edge, from, to) since a line and an arc may connect the same two vertices.
+        let mut next_half_edge: HashMap<HalfEdge, HalfEdge> = HashMap::new();
 
-        // Populate half_edge_to_topo
-        for (topo_id, edge) in self.topo_entities.iter_edges() {
-            let capped_line: CappedLine = match (*edge).try_into() {
-                Ok(line) => line,
-                Err(_) => continue,
-            };
-            half_edge_to_topo.insert((capped_line.start, capped_line.end), *topo_id);
-            half_edge_to_topo.insert((capped_line.end, capped_line.start), *topo_id);
-        }
-
-        // Build next-half-edge lookup
         for (vertex, edges) in &adjacency {
             let n = edges.len();
             for i in 0..n {
-                let (_edge_id, other) = edges[i];
+                let (edge_id, other) = edges[i];
                 // The half-edge coming INTO this vertex is (other -> vertex)
                 // The next half-edge leaving this vertex in CCW order is the PREVIOUS one in the sorted list
                 // (because we want to turn left/counterclockwise)
                 let prev_idx = (i + n - 1) % n;
-                let (_next_edge_id, next_other) = edges[prev_idx];
+                let (next_edge_id, next_other) = edges[prev_idx];
 
-                next_half_edge.insert((other, *vertex), (*vertex, next_other));
+                next_half_edge.insert(
+                    (edge_id, other, *vertex),
+                    (next_edge_id, *vertex, next_other),
+                );
             }
         }
 
         // Step 4: Trace all faces
-        let mut used_half_edges: HashSet<(GeoId, GeoId)> = HashSet::new();
+        let mut used_half_edges: HashSet<HalfEdge> = HashSet::new();
         let mut found_loops: Vec<(Vec<TopoId>, f64)> = Vec::new(); // (edge_ids, signed_area)
 
         for (vertex, edges) in &adjacency {
-            for (_edge_id, other) in edges {
-                let start_half_edge = (*vertex, *other);
+            for (edge_id, other) in edges {
+                let start_half_edge = (*edge_id, *vertex, *other);
 
                 if used_half_edges.contains(&start_half_edge) {
                     continue;
                 }
 
-                let mut face_edges: Vec<TopoId> = Vec::new();
-                let mut face_vertices: Vec<GeoId> = Vec::new();
+                let mut face_half_edges: Vec<HalfEdge> = Vec::new();
                 let mut current = start_half_edge;
 
                 loop {
@@ -414,14 +1118,7 @@ impl Sketch {
                         break;
                     }
                     used_half_edges.insert(current);
-
-                    let (from, _to) = current;
-                    face_vertices.push(from);
-
-                    // Record the edge for this half-edge
-                    if let Some(topo_id) = half_edge_to_topo.get(&current) {
-                        face_edges.push(*topo_id);
-                    }
+                    face_half_edges.push(current);
 
                     // Find the next half-edge
                     if let Some(&next) = next_half_edge.get(&current) {
@@ -436,9 +1133,13 @@ impl Sketch {
                     }
                 }
 
-                if face_edges.len() >= 3 && current == start_half_edge {
+                // Two edges are enough to close a loop if one of them is an arc
+                let closes = face_half_edges.len() >= 3
+                    || (face_half_edges.len() == 2 && face_half_edges[0].0 != face_half_edges[1].0);
+                if closes && current == start_half_edge {
                     // Compute signed area using shoelace formula
-                    let signed_area = self.compute_signed_area(&face_vertices);
+                    let signed_area = self.compute_signed_area(&face_half_edges);
+                    let face_edges = face_half_edges.into_iter().map(|(id, _, _)| id).collect();
                     found_loops.push((face_edges, signed_area));
                 }
             }
@@ -461,23 +1162,143 @@ impl Sketch {
         }
     }
 
-    /// Computes the signed area of a polygon using the shoelace formula.
-    /// Positive area = counterclockwise winding, negative = clockwise.
-    fn compute_signed_area(&self, vertices: &[GeoId]) -> f64 {
-        let n = vertices.len();
-        if n < 3 {
-            return 0.0;
-        }
+    /// The angle of the direction `edge` leaves `vertex` in
+    fn departure_angle(&self, edge: TopoId, vertex: GeoId) -> f64 {
+        let vertex_pos = self.get_point_pos(vertex);
+        let direction = match self.topo_entities[edge] {
+            TopoEntity::Edge {
+                edge: Edge::CappedLine { start, end, .. },
+            } => {
+                let other = if start == vertex { end } else { start };
+                self.get_point_pos(other) - vertex_pos
+            }
+            TopoEntity::Edge {
+                edge: Edge::ArcThreePoint { middle, circle, .. },
+            } => {
+                let circle: Circle = (*self.geo_entities.get(&circle).unwrap())
+                    .try_into()
+                    .unwrap();
+                let radius = vertex_pos - circle.pos;
+                let tangent = Vector2::new(-radius.y, radius.x);
+                // The tangent points whichever way gets to the middle of the arc
+                if tangent.dot(&(self.get_point_pos(middle) - vertex_pos)) < 0.0 {
+                    -tangent
+                } else {
+                    tangent
+                }
+            }
+            _ => Vector2::zeros(),
+        };
+        direction.y.atan2(direction.x)
+    }
 
+    /// Computes the signed area of a loop using the shoelace formula, with the circular segment
+    /// between each arc and its chord added on top.
+    /// Positive area = counterclockwise winding, negative = clockwise.
+    pub(crate) fn compute_signed_area(&self, half_edges: &[HalfEdge]) -> f64 {
         let mut area = 0.0;
-        for i in 0..n {
-            let p1 = self.get_point_pos(vertices[i]);
-            let p2 = self.get_point_pos(vertices[(i + 1) % n]);
+        for (edge, from, to) in half_edges {
+            let p1 = self.get_point_pos(*from);
+            let p2 = self.get_point_pos(*to);
             area += p1.x * p2.y - p2.x * p1.y;
+            if let TopoEntity::Edge {
+                edge: Edge::ArcThreePoint { middle, .. },
+            } = self.topo_entities[*edge]
+            {
+                area += 2.0 * arc_segment_area(p1, self.get_point_pos(middle), p2);
+            }
         }
         area / 2.0
     }
 
+    /// Returns the corner points of `face` in the order they're visited when walking around it.
+    /// The edges of a face don't all have to point in the walking direction.
+    pub fn face_vertices(&self, face: &Face) -> Result<Vec<GeoId>, Box<dyn Error>> {
+        let mut endpoints = vec![];
+        for id in &face.ids {
+            let edge: Edge = self.topo_entities[*id].try_into()?;
+            endpoints.push((edge.start_point()?, edge.end_point()?));
+        }
+        let (first_start, first_end) = *endpoints.first().ok_or("A face can't be empty")?;
+        // The first edge is walked backwards, from its end, if its start point is shared with the
+        // second edge
+        let mut current = match endpoints.get(1) {
+            Some((start, end)) if first_start == *start || first_start == *end => first_end,
+            _ => first_start,
+        };
+        let mut vertices = vec![];
+        for (start, end) in endpoints {
+            if start == current {
+                vertices.push(start);
+                current = end;
+            } else if end == current {
+                vertices.push(end);
+                current = start;
+            } else {
+                return Err(
+                    format!("The edges of {:?} don't connect at {:?}", face.ids, current).into(),
+                );
+            }
+        }
+        if current != vertices[0] {
+            return Err(format!("{:?} is not closed", face.ids).into());
+        }
+        Ok(vertices)
+    }
+
+    /// Returns the corners of `l` moved `distance` away from the loop along the normals of its
+    /// edges. Positive distances grow the loop and negative distances shrink it. Each corner is
+    /// mitered, so its neighbouring edges stay parallel to the original ones.
+    ///
+    /// Only loops of capped lines are supported. Offsetting further than the loop's features can
+    /// take makes edges cross each other, and the returned polygon is then self-intersecting.
+    pub fn offset_loop(
+        &self,
+        l: &Face,
+        distance: f64,
+    ) -> Result<Vec<Vector2<f64>>, Box<dyn Error>> {
+        for id in &l.ids {
+            match self.topo_entities.get(id) {
+                Some(TopoEntity::Edge {
+                    edge: Edge::CappedLine { .. },
+                }) => {}
+                Some(TopoEntity::Edge {
+                    edge: Edge::ArcThreePoint { .. },
+                }) => return Err("Loops with arcs can't be offset (yet)".into()),
+                _ => return Err(format!("{:?} is not an edge", id).into()),
+            }
+        }
+        let corners: Vec<Vector2<f64>> = self
+            .face_vertices(l)?
+            .into_iter()
+            .map(|id| self.get_point_pos(id))
+            .collect();
+        let n = corners.len();
+        let signed_area: f64 = (0..n).map(|i| corners[i].perp(&corners[(i + 1) % n])).sum();
+        // The right hand side of each edge is outside of a counterclockwise loop
+        let outward = if signed_area > 0.0 { 1.0 } else { -1.0 };
+        let normal = |from: Vector2<f64>, to: Vector2<f64>| {
+            let dir = (to - from).normalize();
+            Vector2::new(dir.y, -dir.x) * outward
+        };
+
+        (0..n)
+            .map(|i| {
+                let prev = corners[(i + n - 1) % n];
+                let corner = corners[i];
+                let next = corners[(i + 1) % n];
+                let n1 = normal(prev, corner);
+                let n2 = normal(corner, next);
+                // The offset edges meet where the corner has moved `distance` along both normals
+                let denom = 1.0 + n1.dot(&n2);
+                if denom < EQ_TOL {
+                    return Err(format!("The loop folds back on itself at {:?}", corner).into());
+                }
+                Ok(corner + (n1 + n2) * (distance / denom))
+            })
+            .collect()
+    }
+
     pub fn loops(&self) -> impl Iterator<Item = &Face> {
         self.loops.iter()
     }
@@ -579,6 +1400,87 @@ mod tests {
         }
     }
 
+    #[test]
+    fn invalid_constraints_are_rejected() {
+        let mut sketch = Sketch::new("Invalid constraints".to_string());
+        let p1 = sketch.geo_entities.insert(GeometricEntity::Point {
+            pos: Vector2::new(0.0, 0.0),
+        });
+        let p2 = sketch.geo_entities.insert(GeometricEntity::Point {
+            pos: Vector2::new(1.0, 1.0),
+        });
+
+        let err = sketch
+            .add_constraint(p1, p2, ConstraintType::Tangent)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Tangent can't constrain a point to a point"
+        );
+        assert!(
+            sketch
+                .add_constraint(p1, p1, ConstraintType::Coincident)
+                .is_err()
+        );
+        assert!(
+            sketch
+                .add_constraint(p1, GeoId(999), ConstraintType::Coincident)
+                .is_err()
+        );
+        assert!(sketch.bi_constraints.is_empty());
+
+        sketch
+            .add_constraint(p1, p2, ConstraintType::Horizontal)
+            .unwrap();
+        assert_eq!(sketch.bi_constraints.len(), 1);
+    }
+
+    #[test]
+    fn undo_and_redo_edits() {
+        let mut sketch = Sketch::new("Undo and redo".to_string());
+        sketch.checkpoint();
+        sketch.insert_point(Vector2::new(0.0, 0.0));
+        sketch.checkpoint();
+        sketch.insert_circle(Vector2::new(1.0, 1.0), 0.5);
+        let entities = sketch.geo_entities.len();
+
+        assert!(sketch.undo());
+        assert_eq!(sketch.topo_entities.len(), 1);
+        assert!(sketch.undo());
+        assert_eq!(sketch.topo_entities.len(), 0);
+        assert!(!sketch.undo(), "There should be nothing left to undo");
+
+        assert!(sketch.redo());
+        assert!(sketch.redo());
+        assert_eq!(sketch.geo_entities.len(), entities);
+        assert!(!sketch.redo());
+
+        // A new edit discards what could have been redone
+        sketch.undo();
+        sketch.checkpoint();
+        assert!(!sketch.redo());
+    }
+
+    #[test]
+    fn snapping_to_grid_and_angles() {
+        let snapped = snap_to_grid(Vector2::new(0.26, -0.74), 0.5);
+        assert!((snapped - Vector2::new(0.5, -0.5)).norm() < EQ_TOL);
+        assert_eq!(
+            snap_to_grid(Vector2::new(0.26, 0.1), 0.0),
+            Vector2::new(0.26, 0.1)
+        );
+
+        let origin = Vector2::new(1.0, 1.0);
+        let horizontal = snap_to_angle(origin, Vector2::new(3.0, 1.2), 90.0);
+        assert!((horizontal.y - 1.0).abs() < EQ_TOL);
+        assert!(
+            ((horizontal - origin).norm() - Vector2::<f64>::new(2.0, 0.2).norm()).abs() < EQ_TOL
+        );
+
+        let diagonal = snap_to_angle(origin, Vector2::new(2.0, 2.2), 45.0);
+        assert!(((diagonal.x - origin.x) - (diagonal.y - origin.y)).abs() < EQ_TOL);
+    }
+
     #[test]
     fn basic_error_setup() {
         let mut sketch = Sketch::new("Basic Error Setup".to_string());
@@ -681,6 +1583,108 @@ mod tests {
         );
     }
 
+    #[test]
+    fn pythagorean_triplet_newton() {
+        let mut sketch = Sketch::new("Pythagorean Triplet Newton".to_string());
+        let e1 = sketch.geo_entities.insert(GeometricEntity::Point {
+            pos: Vector2::new(0.0, 0.0),
+        });
+        let e2 = sketch.geo_entities.insert(GeometricEntity::Point {
+            pos: Vector2::new(1.0, 0.1),
+        });
+        let e3 = sketch.geo_entities.insert(GeometricEntity::Point {
+            pos: Vector2::new(0.1, 1.0),
+        });
+        sketch.bi_constraints.extend([
+            BiConstraint::new(e1, e2, ConstraintType::Horizontal),
+            BiConstraint::new(e1, e3, ConstraintType::Vertical),
+            BiConstraint::new(e1, e2, ConstraintType::Distance { x: 3.0 }),
+            BiConstraint::new(e1, e3, ConstraintType::Distance { x: 4.0 }),
+        ]);
+
+        let result = sketch.solve_newton(100, 1e-12);
+
+        assert!(
+            result.iterations < 100,
+            "Took {} iterations",
+            result.iterations
+        );
+        assert!(result.error < 1e-12);
+        let top_corner: Point = sketch.geo_entities[e3].try_into().unwrap();
+        let right_corner: Point = sketch.geo_entities[e2].try_into().unwrap();
+        assert!(((top_corner.pos - right_corner.pos).norm() - 5.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn equal_length_capped_lines() {
+        for newton in [false, true] {
+            let mut sketch = Sketch::new("Equal Length".to_string());
+            let short =
+                sketch.insert_capped_lines(&[Vector2::new(0.0, 0.0), Vector2::new(1.0, 0.0)]);
+            let long =
+                sketch.insert_capped_lines(&[Vector2::new(0.0, 1.0), Vector2::new(0.0, 3.0)]);
+            let short: CappedLine = sketch.topo_entities[short[0]].try_into().unwrap();
+            let long: CappedLine = sketch.topo_entities[long[0]].try_into().unwrap();
+            // Stretching the direction of a line doesn't change how long its capped line is
+            if let GeometricEntity::Line { direction, .. } = &mut sketch.geo_entities[short.line] {
+                *direction *= 2.0;
+            }
+            sketch
+                .add_constraint(short.line, long.line, ConstraintType::EqualLength)
+                .unwrap();
+            assert!((sketch.error() - 1.0).abs() < 1e-12);
+
+            let result = if newton {
+                sketch.solve_newton(100, 1e-12)
+            } else {
+                sketch.solve(10_000, 1e-12)
+            };
+
+            assert!(result.error < 1e-12);
+            let length = |line: CappedLine| {
+                (sketch.get_point_pos(line.end) - sketch.get_point_pos(line.start)).norm()
+            };
+            assert!((length(short) - length(long)).abs() < 1e-5);
+        }
+
+        let mut sketch = Sketch::new("Uncapped Equal Length".to_string());
+        let l1 = sketch.geo_entities.insert(GeometricEntity::Line {
+            offset: Vector2::new(0.0, 0.0),
+            direction: Vector2::new(1.0, 0.0),
+        });
+        let l2 = sketch.geo_entities.insert(GeometricEntity::Line {
+            offset: Vector2::new(0.0, 1.0),
+            direction: Vector2::new(2.0, 0.0),
+        });
+        assert!(
+            sketch
+                .add_constraint(l1, l2, ConstraintType::EqualLength)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn solve_stops_once_converged() {
+        let mut sketch = Sketch::new("Solve Stops Once Converged".to_string());
+        let e1 = sketch.geo_entities.insert(GeometricEntity::Point {
+            pos: Vector2::new(0.0, 0.0),
+        });
+        let e2 = sketch.geo_entities.insert(GeometricEntity::Point {
+            pos: Vector2::new(1.0, 0.5),
+        });
+        sketch
+            .bi_constraints
+            .push(BiConstraint::new(e1, e2, ConstraintType::Horizontal));
+
+        let result = sketch.solve(20000, 1e-8);
+        assert!(result.iterations < 20000);
+        assert!(result.error < 1e-8);
+        assert_eq!(result.error, sketch.error());
+
+        let again = sketch.solve(20000, 1e-8);
+        assert_eq!(again.iterations, 0, "A solved sketch shouldn't be stepped");
+    }
+
     #[test]
     fn point_line_coincident() {
         let mut sketch = Sketch::new("Point Line Coincident".to_string());
@@ -893,6 +1897,44 @@ mod tests {
         assert!(sketch.is_inside(&l, Vector2::new(0.5, 0.5)));
     }
 
+    #[test]
+    fn point_is_inside_circle() {
+        let mut sketch = Sketch::new("Inside Circle".to_string());
+        sketch.insert_circle(Vector2::new(1.0, 1.0), 2.0);
+        let l = Face {
+            ids: sketch.topo_entities.keys().cloned().collect(),
+        };
+        assert_eq!(l.ids.len(), 1);
+        assert!(sketch.is_inside(&l, Vector2::new(1.5, 0.5)));
+        assert!(!sketch.is_inside(&l, Vector2::new(-2.0, 1.0)));
+        assert!(!sketch.is_inside(&l, Vector2::new(4.0, 1.0)));
+    }
+
+    #[test]
+    fn offset_square_loop() {
+        let mut sketch = Sketch::new("Offset Square Loop".to_string());
+        sketch.insert_capped_lines(&[
+            Vector2::new(0.0, 0.0),
+            Vector2::new(0.0, 1.0),
+            Vector2::new(1.0, 1.0),
+            Vector2::new(1.0, 0.0),
+            Vector2::new(0.0, 0.0),
+        ]);
+        let l = sketch.loops().next().unwrap().clone();
+
+        let grown = sketch.offset_loop(&l, 0.5).unwrap();
+        assert_eq!(grown.len(), 4);
+        for p in grown {
+            assert!((p.x + 0.5).abs() < 1e-9 || (p.x - 1.5).abs() < 1e-9);
+            assert!((p.y + 0.5).abs() < 1e-9 || (p.y - 1.5).abs() < 1e-9);
+        }
+        let shrunk = sketch.offset_loop(&l, -0.25).unwrap();
+        for p in shrunk {
+            assert!((p.x - 0.25).abs() < 1e-9 || (p.x - 0.75).abs() < 1e-9);
+            assert!((p.y - 0.25).abs() < 1e-9 || (p.y - 0.75).abs() < 1e-9);
+        }
+    }
+
     #[test]
     fn capped_lines_should_intersect() {
         // Capped lines have to be manually constructed since the method splits any existing lines
@@ -1283,4 +2325,191 @@ mod tests {
         let loops = sketch.find_loops();
         assert_eq!(loops.len(), 0, "Should find no loops for open geometry");
     }
+
+    #[test]
+    fn find_loops_stadium() {
+        // Two horizontal lines joined by a semicircle at each end
+        let mut sketch = Sketch::new("Find Loops Stadium".to_string());
+
+        let mut point = |x: f64, y: f64| {
+            sketch.geo_entities.insert(GeometricEntity::Point {
+                pos: Vector2::new(x, y),
+            })
+        };
+        let bottom_left = point(0.0, 0.0);
+        let bottom_right = point(2.0, 0.0);
+        let top_right = point(2.0, 2.0);
+        let top_left = point(0.0, 2.0);
+        let right_middle = point(3.0, 1.0);
+        let left_middle = point(-1.0, 1.0);
+        let right_circle = sketch.geo_entities.insert(GeometricEntity::Circle {
+            pos: Vector2::new(2.0, 1.0),
+            radius: 1.0,
+        });
+        let left_circle = sketch.geo_entities.insert(GeometricEntity::Circle {
+            pos: Vector2::new(0.0, 1.0),
+            radius: 1.0,
+        });
+        let dummy = GeoId::default();
+
+        sketch.topo_entities.insert(
+            crate::topology::Edge::CappedLine {
+                start: bottom_left,
+                end: bottom_right,
+                line: dummy,
+            }
+            .into(),
+        );
+        sketch.topo_entities.insert(
+            crate::topology::Edge::ArcThreePoint {
+                start: bottom_right,
+                middle: right_middle,
+                end: top_right,
+                circle: right_circle,
+            }
+            .into(),
+        );
+        sketch.topo_entities.insert(
+            crate::topology::Edge::CappedLine {
+                start: top_left,
+                end: top_right,
+                line: dummy,
+            }
+            .into(),
+        );
+        sketch.topo_entities.insert(
+            crate::topology::Edge::ArcThreePoint {
+                start: bottom_left,
+                middle: left_middle,
+                end: top_left,
+                circle: left_circle,
+            }
+            .into(),
+        );
+
+        let loops = sketch.find_loops();
+        assert_eq!(loops.len(), 1, "Should find exactly one loop (stadium)");
+        assert_eq!(loops[0].ids.len(), 4, "Stadium should have 4 edges");
+
+        let segment = arc_segment_area(
+            Vector2::new(2.0, 0.0),
+            Vector2::new(3.0, 1.0),
+            Vector2::new(2.0, 2.0),
+        );
+        assert!((segment - PI / 2.0).abs() < 1e-10);
+    }
+
+    #[test]
+    fn mirror_l_into_t() {
+        let mut sketch = Sketch::new("Mirror L Into T".to_string());
+        let original = sketch.insert_capped_lines(&[
+            Vector2::new(0.0, 0.0),
+            Vector2::new(0.0, 2.0),
+            Vector2::new(1.0, 2.0),
+        ]);
+
+        let copies = sketch.mirror(&original, Vector2::new(0.0, 0.0), Vector2::new(0.0, 1.0));
+
+        // The stem lies on the axis so only the arm is copied
+        assert_eq!(copies.len(), 1);
+        assert_eq!(sketch.topo_entities.iter_edges().count(), 3);
+        let arm: CappedLine = sketch.topo_entities[copies[0]].try_into().unwrap();
+        let original_arm: CappedLine = sketch.topo_entities[original[1]].try_into().unwrap();
+        assert_eq!(
+            arm.start, original_arm.start,
+            "The arms should share a corner"
+        );
+        assert_eq!(sketch.get_point_pos(arm.end), Vector2::new(-1.0, 2.0));
+        assert!(sketch.error() < 1e-12);
+    }
+
+    #[test]
+    fn from_dxf_source() {
+        let source = "0\nSECTION\n2\nHEADER\n0\nENDSEC\n0\nSECTION\n2\nENTITIES\n\
+            0\nLINE\n8\n0\n10\n0.0\n20\n0.0\n11\n2.0\n21\n0.0\n\
+            0\nARC\n10\n2.0\n20\n1.0\n40\n1.0\n50\n270.0\n51\n90.0\n\
+            0\nLINE\n10\n2.0\n20\n2.0\n11\n0.0\n21\n2.0\n\
+            0\nLINE\n10\n0.0\n20\n2.0\n11\n0.0\n21\n0.0\n\
+            0\nTEXT\n1\nSkipped\n\
+            0\nCIRCLE\n10\n5.0\n20\n5.0\n40\n0.5\n\
+            0\nENDSEC\n0\nEOF\n";
+
+        let sketch = Sketch::from_dxf_source("From DXF Source".to_string(), source).unwrap();
+
+        assert_eq!(sketch.topo_entities.iter_edges().count(), 4);
+        assert_eq!(
+            sketch.loops.len(),
+            1,
+            "The lines and the arc should close a loop"
+        );
+        let circles = sketch
+            .topo_entities
+            .values()
+            .filter(|e| matches!(e, TopoEntity::Circle { .. }))
+            .count();
+        assert_eq!(circles, 1);
+    }
+
+    #[test]
+    fn trim_crossing_line() {
+        let mut sketch = Sketch::new("Trim Crossing Line".to_string());
+        let mut point = |x: f64, y: f64| {
+            sketch.geo_entities.insert(GeometricEntity::Point {
+                pos: Vector2::new(x, y),
+            })
+        };
+        let left = point(-1.0, 0.0);
+        let right = point(1.0, 0.0);
+        let bottom = point(0.0, -1.0);
+        let top = point(0.0, 1.0);
+        // Lines inserted directly aren't split where they cross
+        sketch.insert_capped_line_between(left, right);
+        let vertical = sketch.insert_capped_line_between(bottom, top);
+
+        sketch.trim(vertical, Vector2::new(0.0, 0.5)).unwrap();
+
+        assert_eq!(
+            sketch.topo_entities.iter_edges().count(),
+            3,
+            "Both halves of the horizontal line and the bottom of the vertical line should remain"
+        );
+        assert!(sketch.geo_entities.get(&top).is_none());
+        for (_, edge) in sketch.topo_entities.iter_edges() {
+            let line: CappedLine = (*edge).try_into().unwrap();
+            let end = sketch.get_point_pos(line.end);
+            let start = sketch.get_point_pos(line.start);
+            assert!(start.y <= 0.0 && end.y <= 0.0);
+        }
+    }
+
+    #[test]
+    fn fillet_right_angle() {
+        let mut sketch = Sketch::new("Fillet Right Angle".to_string());
+        let mut point = |x: f64, y: f64| {
+            sketch.geo_entities.insert(GeometricEntity::Point {
+                pos: Vector2::new(x, y),
+            })
+        };
+        let right = point(2.0, 0.0);
+        let corner = point(0.0, 0.0);
+        let top = point(0.0, 2.0);
+        let far = point(5.0, 5.0);
+        let bottom = sketch.insert_capped_line_between(right, corner);
+        let left = sketch.insert_capped_line_between(corner, top);
+        let unrelated = sketch.insert_capped_line_between(far, right);
+
+        assert!(sketch.fillet(bottom, left, 3.0).is_err());
+        assert!(sketch.fillet(left, unrelated, 1.0).is_err());
+
+        let arc_id = sketch.fillet(bottom, left, 1.0).unwrap();
+        let arc: ArcThreePoint = sketch.topo_entities[arc_id].try_into().unwrap();
+        let circle: Circle = sketch.geo_entities[arc.circle].try_into().unwrap();
+        assert!((circle.pos - Vector2::new(1.0, 1.0)).norm() < 1e-10);
+        for p in [arc.start, arc.middle, arc.end] {
+            assert!(((sketch.get_point_pos(p) - circle.pos).norm() - 1.0).abs() < 1e-10);
+        }
+        let bottom: CappedLine = sketch.topo_entities[bottom].try_into().unwrap();
+        assert_eq!(bottom.end, arc.start);
+        assert!(sketch.geo_entities.get(&corner).is_none());
+    }
 }
@@ -1,7 +1,7 @@
 use std::{error::Error, f64::consts::PI};
 
 use enum_variant_type::EnumVariantType;
-use nalgebra::{Rotation2, Vector2};
+use nalgebra::{Point3, Rotation2, Vector2, Vector3};
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -306,7 +306,199 @@ pub struct ParametrizedIntersection {
     pub s: f64,
 }
 
+/// A closed polyhedron in scene space. Each polygon is a list of indices into `vertices`, wound
+/// counterclockwise when seen from outside the solid.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Solid {
+    /// The sketch faces the solid was built from, keyed by sketch id
     faces: Vec<(u16, Face)>,
+    #[serde(default)]
+    pub vertices: Vec<Point3<f64>>,
+    #[serde(default)]
+    pub polygons: Vec<Vec<usize>>,
+}
+
+impl Solid {
+    /// Sweeps the polygon `base` along `offset`. `base` must be wound counterclockwise around
+    /// `offset`. The result has a cap at each end and one quadrilateral side per edge of `base`.
+    pub fn prism(source: (u16, Face), base: &[Point3<f64>], offset: Vector3<f64>) -> Self {
+        let n = base.len();
+        let mut vertices = base.to_vec();
+        vertices.extend(base.iter().map(|p| p + offset));
+
+        // The bottom cap faces away from `offset` so it is wound the other way around
+        let mut polygons = vec![(0..n).rev().collect::<Vec<_>>(), (n..2 * n).collect()];
+        for i in 0..n {
+            let j = (i + 1) % n;
+            polygons.push(vec![i, j, n + j, n + i]);
+        }
+
+        Self {
+            faces: vec![source],
+            vertices,
+            polygons,
+        }
+    }
+
+    /// The sketch faces the solid was built from, as `(sketch_id, face)`
+    pub fn faces(&self) -> &[(u16, Face)] {
+        &self.faces
+    }
+
+    /// Outward unit normal of a polygon using Newell's method
+    pub fn normal(&self, polygon: &[usize]) -> Vector3<f64> {
+        let mut normal = Vector3::zeros();
+        for (i, a) in polygon.iter().enumerate() {
+            let a = self.vertices[*a];
+            let b = self.vertices[polygon[(i + 1) % polygon.len()]];
+            normal += Vector3::new(
+                (a.y - b.y) * (a.z + b.z),
+                (a.z - b.z) * (a.x + b.x),
+                (a.x - b.x) * (a.y + b.y),
+            );
+        }
+        normal.normalize()
+    }
+
+    /// Encodes the solid as a binary STL file. Every triangle carries its outward normal, which is
+    /// the normal of the polygon it was cut from.
+    pub fn to_stl(&self) -> Vec<u8> {
+        let triangles = self.triangles();
+
+        let mut out = Vec::with_capacity(84 + triangles.len() * 50);
+        let mut header = [0u8; 80];
+        let title = b"Exported from a cad Solid";
+        header[..title.len()].copy_from_slice(title);
+        out.extend_from_slice(&header);
+        out.extend_from_slice(&(triangles.len() as u32).to_le_bytes());
+        for corners in triangles {
+            let normal = self.normal(&corners);
+            let points = corners.map(|i| self.vertices[i].coords);
+            for v in std::iter::once(normal).chain(points) {
+                for c in v.iter() {
+                    out.extend_from_slice(&(*c as f32).to_le_bytes());
+                }
+            }
+            // Attribute byte count, unused by most readers
+            out.extend_from_slice(&0u16.to_le_bytes());
+        }
+        out
+    }
+
+    /// Splits every polygon into triangles wound the same way as the polygon
+    pub fn triangles(&self) -> Vec<[usize; 3]> {
+        self.polygons
+            .iter()
+            .flat_map(|p| self.triangulate(p))
+            .collect()
+    }
+
+    /// Ear clipping of a single planar polygon, which also handles concave polygons
+    fn triangulate(&self, polygon: &[usize]) -> Vec<[usize; 3]> {
+        let normal = self.normal(polygon);
+        let turns_left = |a: usize, b: usize, c: usize| {
+            let (a, b, c) = (self.vertices[a], self.vertices[b], self.vertices[c]);
+            (b - a).cross(&(c - b)).dot(&normal)
+        };
+
+        let mut remaining = polygon.to_vec();
+        let mut out = Vec::with_capacity(polygon.len().saturating_sub(2));
+        while remaining.len() > 3 {
+            let n = remaining.len();
+            let ear = (0..n).find(|&i| {
+                let (a, b, c) = (
+                    remaining[(i + n - 1) % n],
+                    remaining[i],
+                    remaining[(i + 1) % n],
+                );
+                turns_left(a, b, c) > 0.0
+                    && remaining.iter().all(|&p| {
+                        p == a
+                            || p == b
+                            || p == c
+                            || turns_left(a, b, p) < 0.0
+                            || turns_left(b, c, p) < 0.0
+                            || turns_left(c, a, p) < 0.0
+                    })
+            });
+            // Only degenerate polygons have no ear, fan out what is left of them
+            let Some(i) = ear else {
+                break;
+            };
+            out.push([
+                remaining[(i + n - 1) % n],
+                remaining[i],
+                remaining[(i + 1) % n],
+            ]);
+            remaining.remove(i);
+        }
+        out.extend(
+            (1..remaining.len().saturating_sub(1))
+                .map(|i| [remaining[0], remaining[i], remaining[i + 1]]),
+        );
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_box_to_stl() {
+        let base = [
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(1.0, 1.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+        ];
+        let solid = Solid::prism((0, Face { ids: vec![] }), &base, Vector3::z());
+
+        let stl = solid.to_stl();
+        let count = u32::from_le_bytes(stl[80..84].try_into().unwrap()) as usize;
+        assert_eq!(count, 12);
+        assert_eq!(stl.len(), 84 + count * 50);
+
+        let float = |at: usize| f32::from_le_bytes(stl[at..at + 4].try_into().unwrap());
+        for i in 0..count {
+            let at = 84 + i * 50;
+            let normal = Vector3::new(float(at), float(at + 4), float(at + 8));
+            assert!((normal.norm() - 1.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn solid_without_geometry_deserializes() {
+        let solid: Solid = serde_json::from_str(r#"{"faces":[[0,{"ids":[1,2,3]}]]}"#).unwrap();
+        assert_eq!(solid.faces().len(), 1);
+        assert!(solid.vertices.is_empty());
+        assert!(solid.polygons.is_empty());
+    }
+
+    #[test]
+    fn concave_caps_cover_their_area() {
+        // An L with an area of 3. A fan from the first corner would reach outside of it.
+        let base = [
+            Point3::new(2.0, 1.0, 0.0),
+            Point3::new(1.0, 1.0, 0.0),
+            Point3::new(1.0, 2.0, 0.0),
+            Point3::new(0.0, 2.0, 0.0),
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(2.0, 0.0, 0.0),
+        ];
+        let solid = Solid::prism((0, Face { ids: vec![] }), &base, Vector3::z());
+
+        let cap = solid.triangulate(&solid.polygons[1]);
+        assert_eq!(cap.len(), 4);
+        let area: f64 = cap
+            .iter()
+            .map(|[a, b, c]| {
+                let (a, b, c) = (solid.vertices[*a], solid.vertices[*b], solid.vertices[*c]);
+                // Positive as long as every triangle keeps the winding of the cap
+                (b - a).cross(&(c - a)).z / 2.0
+            })
+            .inspect(|area| assert!(*area > 0.0))
+            .sum();
+        assert!((area - 3.0).abs() < 1e-12);
+    }
 }
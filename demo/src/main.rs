@@ -10,6 +10,7 @@ use std::{
 
 use glfw::{Action, Context as _, Key, Modifiers, Scancode};
 use rust_ui::{
+    WindowOptions,
     geometry::Vector,
     init_open_gl,
     render::{
@@ -118,7 +119,7 @@ impl PerfStats {
                     ..Default::default()
                 },
                 NodeContext {
-                    bg_color: Color::new(0.0, 0.0, 0.0, 0.5),
+                    background: Color::new(0.0, 0.0, 0.0, 0.5).into(),
                     ..Default::default()
                 },
             )
@@ -154,7 +155,7 @@ impl PerfStats {
                     ..Default::default()
                 },
                 NodeContext {
-                    bg_color: self.header_bg,
+                    background: self.header_bg.into(),
                     border: Border {
                         radius: BorderRadius {
                             bottom_left: 40.0,
@@ -217,7 +218,7 @@ impl PerfStats {
                     ..Default::default()
                 },
                 NodeContext {
-                    bg_color: COLOR_SUCCESS,
+                    background: COLOR_SUCCESS.into(),
                     border: Border {
                         thickness: 20.0,
                         radius: BorderRadius::all(40.0),
@@ -317,7 +318,7 @@ fn main() {
         .with_env_filter(EnvFilter::new("demo"))
         .init();
 
-    let (mut glfw, mut window, events) = init_open_gl(1000, 800, true, true);
+    let (mut glfw, mut window, events) = init_open_gl(1000, 800, WindowOptions::default());
 
     let rect_shader = Shader::new_from_name(&ShaderName::Rect).unwrap();
     let text_shader = Shader::new_from_name(&ShaderName::Text).unwrap();
@@ -329,7 +330,7 @@ fn main() {
         &PathBuf::from_str("assets/fonts/LiberationMono.ttf").unwrap(),
     )
     .unwrap();
-    let line_r = LineRenderer::new(line_shader);
+    let line_r = LineRenderer::new(line_shader).with_antialiasing(true);
     let sprite_r = SpriteRenderer::new(Shader::empty(), SpriteAtlas::empty());
 
     let mut state = Renderer::new(
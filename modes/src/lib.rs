@@ -6,8 +6,8 @@ use std::{collections::HashMap, str::FromStr};
 
 use anyhow::anyhow;
 use colored::Colorize as _;
-use keybinds::{KeyInput, Keybinds};
-use strum::EnumString;
+use keybinds::{KeyInput, KeySeq, Keybinds};
+use strum::{Display, EnumString};
 
 #[derive(Debug, Clone)]
 pub struct ModeStack<M, A>
@@ -46,13 +46,24 @@ where
         bindings: &mut HashMap<M, Keybinds<A>>,
         input: I,
     ) -> Option<A> {
+        self.dispatch_detailed(bindings, input)
+            .map(|(_, action)| action)
+    }
+
+    /// Like [Self::dispatch] but also returns the mode which captured the input. Useful for
+    /// finding out why a binding in an outer mode is shadowed by an inner one.
+    pub fn dispatch_detailed<I: Into<KeyInput> + Clone>(
+        &mut self,
+        bindings: &mut HashMap<M, Keybinds<A>>,
+        input: I,
+    ) -> Option<(M, A)> {
         let mut action = None;
         let key: KeyInput = input.into();
         for mode in self.stack.iter().rev() {
             if let Some(key_binds) = bindings.get_mut(mode) {
                 match key_binds.dispatch(key.clone()) {
                     Some(a) => {
-                        action = Some(*a);
+                        action = Some((*mode, *a));
                         break;
                     }
                     None => {}
@@ -69,6 +80,23 @@ where
         action
     }
 
+    /// Passes a mouse input to all active modes in the same order as [Self::dispatch]. The first
+    /// mode with a binding matching `input` exactly decides the action.
+    pub fn dispatch_mouse(
+        &self,
+        mouse: &HashMap<M, Vec<MouseBinding<A>>>,
+        input: MouseInput,
+    ) -> Option<A> {
+        for mode in self.stack.iter().rev() {
+            if let Some(mouse_binds) = mouse.get(mode)
+                && let Some((_, action)) = mouse_binds.iter().find(|(i, _)| *i == input)
+            {
+                return Some(*action);
+            }
+        }
+        None
+    }
+
     pub fn is_active(&self, mode: &M) -> bool {
         self.stack.contains(mode)
     }
@@ -85,16 +113,41 @@ where
         }
     }
 
-    pub fn pop_until(&mut self, stop_at: &M) {
+    /// Pops modes off the stack until `stop_at` is the outermost mode. Returns `false` and leaves
+    /// the stack untouched if `stop_at` isn't on the stack.
+    pub fn pop_until(&mut self, stop_at: &M) -> bool {
+        if !self.stack.contains(stop_at) {
+            return false;
+        }
         while self.stack.last() != Some(stop_at) {
             self.stack.pop();
         }
+        true
     }
 
     pub fn push(&mut self, mode: M) {
         self.stack.push(mode);
     }
 
+    /// Pushes `mode` if it isn't active. Otherwise its outermost occurrence is removed along with
+    /// every mode pushed after it. Like [Self::pop] this never removes the base mode, so toggling
+    /// the base mode while nothing else is on top of it does nothing.
+    pub fn toggle(&mut self, mode: M) {
+        match self.stack.iter().rposition(|m| *m == mode) {
+            Some(0) => self.stack.truncate(1),
+            Some(idx) => self.stack.truncate(idx),
+            None => self.stack.push(mode),
+        }
+    }
+
+    /// Swaps the outermost mode for `mode`, or pushes it if the stack is empty
+    pub fn replace_top(&mut self, mode: M) {
+        match self.stack.last_mut() {
+            Some(top) => *top = mode,
+            None => self.stack.push(mode),
+        }
+    }
+
     pub fn outermost(&'a self) -> Option<&'a M> {
         self.stack.last()
     }
@@ -104,7 +157,7 @@ where
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumString)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumString, Display)]
 pub enum MouseButton {
     Left,
     Middle,
@@ -162,17 +215,37 @@ impl FromStr for MouseInput {
     }
 }
 
-#[derive(Debug, EnumString)]
+impl fmt::Display for MouseInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.shift {
+            write!(f, "Shift+")?;
+        }
+        write!(f, "{}", self.button)
+    }
+}
+
+#[derive(Debug, EnumString, Display)]
 enum Command {
     Bind,
     MouseBind,
     Set,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    /// The line was skipped, but the rest of the config is still usable
+    Warning,
+}
+
 #[derive(Debug, Clone)]
 pub struct ConfigError {
     pub line_number: usize,
     pub message: String,
+    pub severity: Severity,
 }
 
 impl ConfigError {
@@ -180,22 +253,51 @@ impl ConfigError {
         Self {
             line_number,
             message,
+            severity: Severity::Error,
+        }
+    }
+
+    pub fn warning(line_number: usize, message: String) -> Self {
+        Self {
+            line_number,
+            message,
+            severity: Severity::Warning,
         }
     }
 }
 
 impl fmt::Display for ConfigError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self.severity {
+            Severity::Error => self.message.bright_red(),
+            Severity::Warning => format!("warning: {}", self.message).yellow(),
+        };
         write!(
             f,
             "{} {}: {}",
             "Line".bright_blue(),
             self.line_number.to_string().bright_yellow(),
-            self.message.bright_red()
+            message
         )
     }
 }
 
+/// The line on which each key sequence and mouse input was bound, per mode. Only needed while
+/// parsing to point out which line a duplicate binding conflicts with.
+struct BoundInputs<Mode> {
+    keys: HashMap<Mode, Vec<(KeySeq, usize)>>,
+    mouse: HashMap<Mode, Vec<(MouseInput, usize)>>,
+}
+
+impl<Mode> BoundInputs<Mode> {
+    fn new() -> Self {
+        Self {
+            keys: HashMap::new(),
+            mouse: HashMap::new(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct ConfigParseResult<Mode, BindableMessage, MouseAction>
 where
@@ -204,6 +306,7 @@ where
     MouseAction: PartialEq + Eq + FromStr + Clone + Copy,
 {
     pub config: Config<Mode, BindableMessage, MouseAction>,
+    /// Errors and warnings, in the order of the lines they were found on
     pub errors: Vec<ConfigError>,
 }
 
@@ -224,8 +327,15 @@ where
         self.errors.push(ConfigError::new(line_number, message));
     }
 
+    pub fn add_warning(&mut self, line_number: usize, message: String) {
+        self.errors.push(ConfigError::warning(line_number, message));
+    }
+
+    /// Warnings don't count, the config can still be used if there are any
     pub fn has_errors(&self) -> bool {
-        !self.errors.is_empty()
+        self.errors
+            .iter()
+            .any(|error| error.severity == Severity::Error)
     }
 
     pub fn format_errors(&self) -> String {
@@ -233,7 +343,12 @@ where
             return String::new();
         }
 
-        let mut output = format!("{}\n", "Configuration parsing errors:".bright_red().bold());
+        let header = if self.has_errors() {
+            "Configuration parsing errors:".bright_red().bold()
+        } else {
+            "Configuration parsing warnings:".yellow().bold()
+        };
+        let mut output = format!("{header}\n");
         for error in &self.errors {
             output.push_str(&format!("  {error}\n"));
         }
@@ -265,6 +380,18 @@ where
         }
     }
 
+    /// The key sequence bound to `action` in `mode`, formatted like in the config file. Used to
+    /// show shortcuts next to the actions they trigger. If the action is bound to several
+    /// sequences the first one bound is returned.
+    pub fn keys_for_action(&self, mode: Mode, action: BindableMessage) -> Option<String> {
+        self.bindings
+            .get(&mode)?
+            .as_slice()
+            .iter()
+            .find(|keybind| keybind.action == action)
+            .map(|keybind| keybind.seq.to_string())
+    }
+
     /// Splits a line from the config file into parts, taking into quoted strings into accound since they are needed for multi key bindings
     fn parse_line_parts(line: &str) -> Result<Vec<String>, String> {
         let mut parts = Vec::new();
@@ -302,7 +429,9 @@ where
 
     fn parse_line(
         line: &str,
-        config: &mut Config<Mode, BindableMessage, MouseAction>,
+        line_number: usize,
+        result: &mut ConfigParseResult<Mode, BindableMessage, MouseAction>,
+        bound: &mut BoundInputs<Mode>,
     ) -> Result<(), String> {
         let parts = Self::parse_line_parts(line)?;
         if parts.is_empty() {
@@ -331,8 +460,27 @@ where
                     Mode::from_str(mode_str).map_err(|_| format!("Unknown mode: {mode_str}"))?;
                 let action = BindableMessage::from_str(action_str)
                     .map_err(|_| format!("Unknown action: {action_str}"))?;
+                let key_seq = KeySeq::from_str(key_str)
+                    .map_err(|e| format!("Failed to bind key '{key_str}': {e}"))?;
+
+                let bound_keys = bound.keys.entry(mode).or_default();
+                if let Some(index) = bound_keys.iter().position(|(seq, _)| *seq == key_seq) {
+                    let (_, previous_line) = bound_keys.remove(index);
+                    result.add_warning(
+                        line_number,
+                        format!(
+                            "Key '{key_str}' in mode {mode_str} is already bound on line {previous_line}"
+                        ),
+                    );
+                    // The later binding replaces the earlier one
+                    if let Some(keyboard) = result.config.bindings.get_mut(&mode) {
+                        let mut binds = std::mem::take(keyboard).into_vec();
+                        binds.retain(|bind| bind.seq != key_seq);
+                        *keyboard = Keybinds::new(binds);
+                    }
+                }
 
-                match config.bindings.get_mut(&mode) {
+                match result.config.bindings.get_mut(&mode) {
                     Some(keyboard) => {
                         keyboard
                             .bind(key_str, action)
@@ -343,9 +491,10 @@ where
                         new_kb
                             .bind(key_str, action)
                             .map_err(|e| format!("Failed to bind key '{key_str}': {e}"))?;
-                        config.bindings.insert(mode, new_kb);
+                        result.config.bindings.insert(mode, new_kb);
                     }
                 }
+                bound_keys.push((key_seq, line_number));
             }
             Command::MouseBind => {
                 if parts.len() != 4 {
@@ -366,16 +515,35 @@ where
                 let mouse_action = MouseAction::from_str(action_str)
                     .map_err(|_| format!("Unknown mouse action: {action_str}"))?;
 
-                match config.mouse.get_mut(&mode) {
+                let bound_mouse = bound.mouse.entry(mode).or_default();
+                if let Some(index) = bound_mouse
+                    .iter()
+                    .position(|(input, _)| *input == mouse_input)
+                {
+                    let (_, previous_line) = bound_mouse.remove(index);
+                    result.add_warning(
+                        line_number,
+                        format!(
+                            "Mouse input '{mouse_input_str}' in mode {mode_str} is already bound on line {previous_line}"
+                        ),
+                    );
+                    // The later binding replaces the earlier one
+                    if let Some(mouse_list) = result.config.mouse.get_mut(&mode) {
+                        mouse_list.retain(|(input, _)| *input != mouse_input);
+                    }
+                }
+
+                match result.config.mouse.get_mut(&mode) {
                     Some(mouse_list) => {
                         mouse_list.push((mouse_input, mouse_action));
                     }
                     None => {
                         let mut new_ml = vec![];
                         new_ml.push((mouse_input, mouse_action));
-                        config.mouse.insert(mode, new_ml);
+                        result.config.mouse.insert(mode, new_ml);
                     }
                 }
+                bound_mouse.push((mouse_input, line_number));
             }
             Command::Set => {
                 if parts.len() != 3 {
@@ -398,6 +566,7 @@ where
 
     pub fn parse_with_errors(s: &str) -> ConfigParseResult<Mode, BindableMessage, MouseAction> {
         let mut result = ConfigParseResult::new();
+        let mut bound = BoundInputs::new();
         let lines: Vec<&str> = s.lines().collect();
 
         for (line_number, line) in lines.iter().enumerate() {
@@ -408,7 +577,7 @@ where
                 continue;
             }
 
-            if let Err(error) = Self::parse_line(trimmed, &mut result.config) {
+            if let Err(error) = Self::parse_line(trimmed, line_num, &mut result, &mut bound) {
                 result.add_error(line_num, error);
             }
         }
@@ -417,6 +586,59 @@ where
     }
 }
 
+impl<Mode, BindableMessage, MouseAction> Config<Mode, BindableMessage, MouseAction>
+where
+    Mode: PartialEq + Eq + FromStr + Clone + Copy + Hash + fmt::Display,
+    BindableMessage: PartialEq + Eq + FromStr + Clone + Copy + fmt::Display,
+    MouseAction: PartialEq + Eq + FromStr + Clone + Copy + fmt::Display,
+{
+    /// Quotes a part of a config line if it contains whitespace, the inverse of
+    /// [Self::parse_line_parts]
+    fn quote_part(part: &str) -> String {
+        if part.contains(char::is_whitespace) {
+            format!("\"{part}\"")
+        } else {
+            part.to_string()
+        }
+    }
+
+    /// Writes the config in the same format as it is parsed by [Self::parse_with_errors]. Modes
+    /// are sorted by name so that the same config always produces the same string.
+    pub fn to_config_string(&self) -> String {
+        let mut output = String::new();
+
+        let mut bindings: Vec<_> = self.bindings.iter().collect();
+        bindings.sort_by_key(|(mode, _)| mode.to_string());
+        for (mode, keybinds) in bindings {
+            for keybind in keybinds.as_slice() {
+                output.push_str(&format!(
+                    "{} {} {} {}\n",
+                    Command::Bind,
+                    mode,
+                    Self::quote_part(&keybind.seq.to_string()),
+                    keybind.action
+                ));
+            }
+        }
+
+        let mut mouse: Vec<_> = self.mouse.iter().collect();
+        mouse.sort_by_key(|(mode, _)| mode.to_string());
+        for (mode, mouse_binds) in mouse {
+            for (mouse_input, action) in mouse_binds {
+                output.push_str(&format!(
+                    "{} {} {} {}\n",
+                    Command::MouseBind,
+                    mode,
+                    Self::quote_part(&mouse_input.to_string()),
+                    action
+                ));
+            }
+        }
+
+        output
+    }
+}
+
 impl<Mode, BindableMessage, MouseAction> FromStr for Config<Mode, BindableMessage, MouseAction>
 where
     Mode: PartialEq + Eq + FromStr + Clone + Copy + Hash,
@@ -435,3 +657,230 @@ where
         Ok(parse_result.config)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumString, Display)]
+    enum TestMode {
+        Base,
+        Edit,
+        Command,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Display)]
+    enum TestAction {
+        Pan,
+        Select,
+    }
+
+    fn mouse(button: MouseButton) -> MouseInput {
+        MouseInput {
+            button,
+            modifiers: MouseModifiers::default(),
+        }
+    }
+
+    #[test]
+    fn pop_until_stops_at_target() {
+        let mut stack: ModeStack<TestMode, ()> = ModeStack::with_base(TestMode::Base);
+        stack.push(TestMode::Edit);
+        stack.push(TestMode::Command);
+
+        assert!(stack.pop_until(&TestMode::Edit));
+        assert_eq!(stack.modes(), &[TestMode::Base, TestMode::Edit]);
+    }
+
+    #[test]
+    fn pop_until_missing_mode_terminates() {
+        let mut stack: ModeStack<TestMode, ()> = ModeStack::with_base(TestMode::Base);
+        stack.push(TestMode::Edit);
+
+        assert!(!stack.pop_until(&TestMode::Command));
+        assert_eq!(stack.modes(), &[TestMode::Base, TestMode::Edit]);
+    }
+
+    #[test]
+    fn toggle_removes_outermost_occurrence() {
+        let mut stack: ModeStack<TestMode, ()> = ModeStack::with_base(TestMode::Base);
+        stack.toggle(TestMode::Edit);
+        assert_eq!(stack.modes(), &[TestMode::Base, TestMode::Edit]);
+
+        stack.push(TestMode::Command);
+        stack.push(TestMode::Edit);
+        stack.push(TestMode::Command);
+        stack.toggle(TestMode::Edit);
+        assert_eq!(
+            stack.modes(),
+            &[TestMode::Base, TestMode::Edit, TestMode::Command]
+        );
+
+        stack.toggle(TestMode::Base);
+        assert_eq!(stack.modes(), &[TestMode::Base]);
+
+        stack.replace_top(TestMode::Edit);
+        assert_eq!(stack.modes(), &[TestMode::Edit]);
+    }
+
+    #[test]
+    fn dispatch_detailed_reports_capturing_mode() {
+        let contents = r#"
+Bind Base a    Pan
+Bind Base b    Pan
+Bind Edit a    Select
+"#;
+        let mut config: Config<TestMode, TestAction, TestAction> =
+            Config::from_str(contents).unwrap();
+        let mut stack: ModeStack<TestMode, TestAction> = ModeStack::with_base(TestMode::Base);
+        stack.push(TestMode::Edit);
+
+        assert_eq!(
+            stack.dispatch_detailed(&mut config.bindings, KeyInput::from_str("a").unwrap()),
+            Some((TestMode::Edit, TestAction::Select))
+        );
+        assert_eq!(
+            stack.dispatch_detailed(&mut config.bindings, KeyInput::from_str("b").unwrap()),
+            Some((TestMode::Base, TestAction::Pan))
+        );
+    }
+
+    #[test]
+    fn dispatch_mouse_prefers_innermost_mode() {
+        let mut stack: ModeStack<TestMode, TestAction> = ModeStack::with_base(TestMode::Base);
+        stack.push(TestMode::Edit);
+        let mut bindings = HashMap::new();
+        bindings.insert(
+            TestMode::Base,
+            vec![
+                (mouse(MouseButton::Left), TestAction::Pan),
+                (mouse(MouseButton::Middle), TestAction::Pan),
+            ],
+        );
+        bindings.insert(
+            TestMode::Edit,
+            vec![(mouse(MouseButton::Left), TestAction::Select)],
+        );
+
+        assert_eq!(
+            stack.dispatch_mouse(&bindings, mouse(MouseButton::Left)),
+            Some(TestAction::Select)
+        );
+        assert_eq!(
+            stack.dispatch_mouse(&bindings, mouse(MouseButton::Middle)),
+            Some(TestAction::Pan)
+        );
+        assert_eq!(
+            stack.dispatch_mouse(&bindings, mouse(MouseButton::Right)),
+            None
+        );
+    }
+
+    #[test]
+    fn config_round_trips_through_string() {
+        let contents = r#"
+Bind      Base Esc          Select
+Bind      Edit "g g"        Pan
+Bind      Edit Ctrl+x       Select
+MouseBind Base Middle       Pan
+MouseBind Edit Shift+Left   Select
+"#;
+        let config: Config<TestMode, TestAction, TestAction> = Config::from_str(contents).unwrap();
+        let written = config.to_config_string();
+        let reparsed: Config<TestMode, TestAction, TestAction> =
+            Config::from_str(&written).unwrap();
+
+        assert_eq!(config.bindings, reparsed.bindings);
+        assert_eq!(config.mouse, reparsed.mouse);
+        assert_eq!(written, reparsed.to_config_string());
+    }
+
+    #[test]
+    fn config_string_is_sorted_by_mode() {
+        let contents = r#"
+Bind      Edit x      Pan
+Bind      Command y   Select
+Bind      Base z      Pan
+MouseBind Edit Left   Pan
+MouseBind Base Right  Select
+"#;
+        let config: Config<TestMode, TestAction, TestAction> = Config::from_str(contents).unwrap();
+        let modes: Vec<_> = config
+            .to_config_string()
+            .lines()
+            .map(|line| line.split(' ').take(2).collect::<Vec<_>>().join(" "))
+            .collect();
+
+        assert_eq!(
+            modes,
+            [
+                "Bind Base",
+                "Bind Command",
+                "Bind Edit",
+                "MouseBind Base",
+                "MouseBind Edit"
+            ]
+        );
+    }
+
+    #[test]
+    fn keys_for_action_finds_first_binding() {
+        let contents = r#"
+Bind Edit Ctrl+x   Select
+Bind Edit "g g"    Select
+Bind Base Esc      Pan
+"#;
+        let config: Config<TestMode, TestAction, TestAction> = Config::from_str(contents).unwrap();
+
+        assert_eq!(
+            config.keys_for_action(TestMode::Edit, TestAction::Select),
+            Some("Ctrl+x".to_string())
+        );
+        assert_eq!(
+            config.keys_for_action(TestMode::Edit, TestAction::Pan),
+            None
+        );
+        assert_eq!(
+            config.keys_for_action(TestMode::Command, TestAction::Pan),
+            None
+        );
+    }
+
+    #[test]
+    fn conflicting_bindings_are_reported() {
+        let contents = r#"
+Bind      Base Esc    Select
+Bind      Base Esc    Pan
+MouseBind Edit Left   Pan
+MouseBind Edit Left   Select
+Bind      Edit Esc    Pan
+"#;
+        let result = Config::<TestMode, TestAction, TestAction>::parse_with_errors(contents);
+
+        assert!(!result.has_errors());
+        assert_eq!(result.errors.len(), 2);
+        assert_eq!(result.errors[0].line_number, 3);
+        assert_eq!(result.errors[0].severity, Severity::Warning);
+        assert!(result.errors[0].message.contains("line 2"));
+        assert_eq!(result.errors[1].line_number, 5);
+        assert_eq!(result.errors[1].severity, Severity::Warning);
+        assert!(result.errors[1].message.contains("line 4"));
+        // The last binding wins
+        assert_eq!(
+            result
+                .config
+                .keys_for_action(TestMode::Base, TestAction::Pan),
+            Some("Esc".to_string())
+        );
+        assert_eq!(
+            result
+                .config
+                .keys_for_action(TestMode::Base, TestAction::Select),
+            None
+        );
+        assert_eq!(
+            result.config.mouse[&TestMode::Edit],
+            vec![(mouse(MouseButton::Left), TestAction::Select)]
+        );
+    }
+}
@@ -14,12 +14,13 @@ use curvo::prelude::{
 use glfw::{Action, Context as _, Key, Modifiers, Scancode};
 use nalgebra::{Point3, Rotation3, Translation3, Vector3};
 use rust_ui::{
+    WindowOptions,
     geometry::Vector,
     init_open_gl,
     render::{
         Border, BorderRadius, COLOR_DANGER, COLOR_LIGHT, COLOR_SUCCESS, Color, Text,
         line::LineRenderer,
-        mesh::{MeshRenderer, Vertex},
+        mesh::{Camera, MeshRenderer, RenderMode, Vertex},
         rect::RectRenderer,
         renderer::{Anchor, AppState, NodeContext, RenderLayout, Renderer, flags},
         sprite::{SpriteAtlas, SpriteRenderer},
@@ -122,7 +123,7 @@ impl PerfStats {
                     ..Default::default()
                 },
                 NodeContext {
-                    bg_color: Color::new(0.0, 0.0, 0.0, 0.5),
+                    background: Color::new(0.0, 0.0, 0.0, 0.5).into(),
                     ..Default::default()
                 },
             )
@@ -158,7 +159,7 @@ impl PerfStats {
                     ..Default::default()
                 },
                 NodeContext {
-                    bg_color: self.header_bg,
+                    background: self.header_bg.into(),
                     border: Border {
                         radius: BorderRadius {
                             bottom_left: 40.0,
@@ -221,7 +222,7 @@ impl PerfStats {
                     ..Default::default()
                 },
                 NodeContext {
-                    bg_color: COLOR_SUCCESS,
+                    background: COLOR_SUCCESS.into(),
                     border: Border {
                         thickness: 20.0,
                         radius: BorderRadius::all(40.0),
@@ -377,13 +378,13 @@ fn main() {
         .with_env_filter(EnvFilter::new("nurbs,rust_ui"))
         .init();
 
-    let (mut glfw, mut window, events) = init_open_gl(1000, 800, true, true);
+    let (mut glfw, mut window, events) = init_open_gl(1000, 800, WindowOptions::default());
 
     let rect_shader = Shader::new_from_name(&ShaderName::Rect).unwrap();
     let text_shader = Shader::new_from_name(&ShaderName::Text).unwrap();
     let mesh_shader = Shader::new_from_name(&ShaderName::Mesh).unwrap();
     let (vertices, indices) = generate_curve();
-    let mesh_r = MeshRenderer::new(vertices, indices, mesh_shader);
+    let mut mesh_r = MeshRenderer::new(vertices, indices, mesh_shader);
 
     let line_shader = Shader::new_from_name(&ShaderName::Line).unwrap();
 
@@ -428,8 +429,7 @@ fn main() {
     sys.refresh_processes(ProcessesToUpdate::Some(&[pid]), false);
     let mut ram_usage = sys.process(pid).unwrap().memory();
 
-    let mut polar_angle = 0.0;
-    let mut horizontal_angle = 0.0;
+    let mut camera = Camera::default();
     let mut delta_polar = 0.0;
     let mut delta_horiz = 0.0;
 
@@ -496,14 +496,20 @@ fn main() {
                             }
                             _ => {}
                         },
+                        Key::F if action == Action::Press => {
+                            mesh_r.render_mode = match mesh_r.render_mode {
+                                RenderMode::Solid => RenderMode::Wireframe,
+                                RenderMode::Wireframe => RenderMode::Points,
+                                RenderMode::Points => RenderMode::Solid,
+                            };
+                        }
                         _ => {}
                     }
                 }
                 _ => {}
             }
         }
-        polar_angle += delta_polar;
-        horizontal_angle += delta_horiz;
+        camera.orbit(Vector::new(delta_polar, delta_horiz));
         state.update();
         state.app_state.update(avg_sleep_ms, ram_usage);
 
@@ -517,10 +523,10 @@ fn main() {
 
         unsafe {
             gl::ClearColor(0.2, 0.2, 0.2, 1.0);
-            gl::Clear(gl::COLOR_BUFFER_BIT);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
         }
         state.render();
-        mesh_r.draw(polar_angle, horizontal_angle);
+        mesh_r.draw(&camera.view_projection(state.width as f32 / state.height as f32));
 
         window.swap_buffers();
 
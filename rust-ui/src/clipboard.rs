@@ -0,0 +1,63 @@
+use std::{
+    ffi::{CStr, CString},
+    ptr,
+};
+
+use glfw::{Glfw, ffi};
+
+/// Somewhere for text widgets to copy text to and paste it from. See
+/// [crate::render::renderer::Renderer::clipboard]
+pub trait Clipboard {
+    fn get(&mut self) -> Option<String>;
+    fn set(&mut self, contents: &str);
+}
+
+/// A clipboard that only lives as long as the program. Used when no system clipboard is set up.
+#[derive(Debug, Default)]
+pub struct LocalClipboard {
+    contents: Option<String>,
+}
+
+impl Clipboard for LocalClipboard {
+    fn get(&mut self) -> Option<String> {
+        self.contents.clone()
+    }
+
+    fn set(&mut self, contents: &str) {
+        self.contents = Some(contents.to_string());
+    }
+}
+
+/// The system clipboard as exposed by GLFW. Since GLFW 3.3 the clipboard isn't tied to a window,
+/// so only a handle keeping GLFW initialized is held.
+#[derive(Debug)]
+pub struct GlfwClipboard {
+    _glfw: Glfw,
+}
+
+impl GlfwClipboard {
+    pub fn new(glfw: &Glfw) -> Self {
+        Self {
+            _glfw: glfw.clone(),
+        }
+    }
+}
+
+impl Clipboard for GlfwClipboard {
+    fn get(&mut self) -> Option<String> {
+        let contents = unsafe { ffi::glfwGetClipboardString(ptr::null_mut()) };
+        if contents.is_null() {
+            return None;
+        }
+        let contents = unsafe { CStr::from_ptr(contents) };
+        Some(contents.to_string_lossy().into_owned())
+    }
+
+    fn set(&mut self, contents: &str) {
+        // Interior nul bytes can't be passed to GLFW, so the text is cut off at the first one
+        let contents = contents.split('\0').next().unwrap_or_default();
+        if let Ok(contents) = CString::new(contents) {
+            unsafe { ffi::glfwSetClipboardString(ptr::null_mut(), contents.as_ptr()) };
+        }
+    }
+}
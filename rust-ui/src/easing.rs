@@ -0,0 +1,103 @@
+/// Maps the normalized time of an animation onto its normalized progress
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    #[default]
+    EaseInOut,
+    /// A CSS style cubic Bézier through `(0, 0)`, `(x1, y1)`, `(x2, y2)` and `(1, 1)`. The x
+    /// coordinates should lie in `[0, 1]` for the curve to be a function of time
+    CubicBezier(f32, f32, f32, f32),
+}
+
+impl Easing {
+    /// Maps `t` in `[0, 1]` to the progress at that time. Values outside the range are clamped
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+            Easing::CubicBezier(x1, y1, x2, y2) => {
+                let s = solve_bezier_parameter(x1, x2, t);
+                bezier(y1, y2, s)
+            }
+        }
+    }
+}
+
+/// One coordinate of a cubic Bézier with end points at 0 and 1
+fn bezier(p1: f32, p2: f32, s: f32) -> f32 {
+    let inv = 1.0 - s;
+    3.0 * inv * inv * s * p1 + 3.0 * inv * s * s * p2 + s * s * s
+}
+
+fn bezier_derivative(p1: f32, p2: f32, s: f32) -> f32 {
+    let inv = 1.0 - s;
+    3.0 * inv * inv * p1 + 6.0 * inv * s * (p2 - p1) + 3.0 * s * s * (1.0 - p2)
+}
+
+/// Finds the curve parameter where the x coordinate equals `x`. Newton's method converges in a
+/// few steps for most curves, bisection takes over when the slope is too flat.
+fn solve_bezier_parameter(x1: f32, x2: f32, x: f32) -> f32 {
+    let mut s = x;
+    for _ in 0..8 {
+        let error = bezier(x1, x2, s) - x;
+        if error.abs() < 1e-6 {
+            return s;
+        }
+        let slope = bezier_derivative(x1, x2, s);
+        if slope.abs() < 1e-6 {
+            break;
+        }
+        s -= error / slope;
+    }
+
+    let (mut low, mut high) = (0.0, 1.0);
+    s = x;
+    for _ in 0..32 {
+        let value = bezier(x1, x2, s);
+        if (value - x).abs() < 1e-6 {
+            break;
+        }
+        if value < x {
+            low = s;
+        } else {
+            high = s;
+        }
+        s = (low + high) / 2.0;
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoints_are_fixed() {
+        let all = [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+            Easing::CubicBezier(0.25, 0.1, 0.25, 1.0),
+            Easing::CubicBezier(0.4, 0.0, 0.2, 1.0),
+            Easing::CubicBezier(0.0, 0.0, 1.0, 1.0),
+        ];
+        for easing in all {
+            assert!(easing.apply(0.0).abs() < 1e-5, "{easing:?} at 0");
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-5, "{easing:?} at 1");
+        }
+    }
+
+    #[test]
+    fn linear_bezier_is_identity() {
+        let easing = Easing::CubicBezier(1.0 / 3.0, 1.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0);
+        for t in [0.1, 0.25, 0.5, 0.9] {
+            assert!((easing.apply(t) - t).abs() < 1e-4);
+        }
+    }
+}
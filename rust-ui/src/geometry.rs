@@ -1,4 +1,4 @@
-use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
 
 use num::Num;
 use serde::{Deserialize, Serialize};
@@ -54,6 +54,44 @@ where
     }
 }
 
+impl Vector<f32> {
+    pub fn dot(&self, other: Vector<f32>) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    pub fn length_squared(&self) -> f32 {
+        self.dot(*self)
+    }
+
+    pub fn length(&self) -> f32 {
+        self.length_squared().sqrt()
+    }
+
+    /// Returns a unit vector pointing in the same direction. The zero vector stays zero instead of
+    /// turning into NaN.
+    pub fn normalized(&self) -> Vector<f32> {
+        let length = self.length();
+        if length == 0.0 {
+            Vector::zero()
+        } else {
+            self.scaled(1.0 / length)
+        }
+    }
+
+    /// Linear interpolation between `self` (t = 0) and `other` (t = 1)
+    pub fn lerp(&self, other: Vector<f32>, t: f32) -> Vector<f32> {
+        *self + (other - *self) * t
+    }
+}
+
+impl Mul<f32> for Vector<f32> {
+    type Output = Vector<f32>;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        self.scaled(rhs)
+    }
+}
+
 impl<T> Add for Vector<T>
 where
     T: Num + Copy,
@@ -257,6 +295,25 @@ where
         self.x0.x <= v.x && self.x1.x >= v.x && self.x0.y <= v.y && self.x1.y >= v.y
     }
 
+    /// Returns true if the rectangles share some area. Rectangles only touching along an edge
+    /// don't intersect.
+    pub fn intersects(&self, other: &Rect<T>) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// The overlapping area of two rectangles, if there is any
+    pub fn intersection(&self, other: &Rect<T>) -> Option<Rect<T>> {
+        let max = |a: T, b: T| if a > b { a } else { b };
+        let min = |a: T, b: T| if a < b { a } else { b };
+        let x0 = Vector::new(max(self.x0.x, other.x0.x), max(self.x0.y, other.x0.y));
+        let x1 = Vector::new(min(self.x1.x, other.x1.x), min(self.x1.y, other.x1.y));
+        if x0.x < x1.x && x0.y < x1.y {
+            Some(Rect { x0, x1 })
+        } else {
+            None
+        }
+    }
+
     /// Divides a rect into two equally sized rects to the left and right of each other
     pub fn split_horizontally(&self) -> (Self, Self) {
         (
@@ -306,3 +363,63 @@ impl From<Rect<f64>> for Rect<f32> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalized_zero_vector_is_zero() {
+        let v = Vector::<f32>::zero().normalized();
+        assert_eq!(v, Vector::zero());
+    }
+
+    #[test]
+    fn normalized_has_unit_length() {
+        let v = Vector::new(3.0, 4.0).normalized();
+        assert!((v.length() - 1.0).abs() < 1e-6);
+        assert!((v.x - 0.6).abs() < 1e-6);
+        assert!((v.y - 0.8).abs() < 1e-6);
+    }
+
+    #[test]
+    fn vector_arithmetic() {
+        let a = Vector::new(1.0, 2.0);
+        let b = Vector::new(3.0, -1.0);
+        assert_eq!(a.dot(b), 1.0);
+        assert_eq!(a + b, Vector::new(4.0, 1.0));
+        assert_eq!(a - b, Vector::new(-2.0, 3.0));
+        assert_eq!(a * 2.0, Vector::new(2.0, 4.0));
+        assert_eq!(a.lerp(b, 0.5), Vector::new(2.0, 0.5));
+        assert_eq!(Vector::new(3.0, 4.0).length_squared(), 25.0);
+    }
+
+    #[test]
+    fn rect_contains_point() {
+        let r = Rect::from_pos_size(Vector::new(0.0, 0.0), Vector::new(10.0, 5.0));
+        assert!(r.contains(Vector::new(5.0, 2.5)));
+        assert!(r.contains(Vector::new(10.0, 5.0)));
+        assert!(!r.contains(Vector::new(10.1, 2.0)));
+    }
+
+    #[test]
+    fn rect_intersection() {
+        let a = Rect::from_pos_size(Vector::new(0.0, 0.0), Vector::new(10.0, 10.0));
+        let b = Rect::from_pos_size(Vector::new(5.0, 5.0), Vector::new(10.0, 10.0));
+        let c = Rect::from_pos_size(Vector::new(10.0, 0.0), Vector::new(5.0, 5.0));
+
+        assert!(a.intersects(&b));
+        assert_eq!(
+            a.intersection(&b),
+            Some(Rect::from_points(
+                Vector::new(5.0, 5.0),
+                Vector::new(10.0, 10.0)
+            ))
+        );
+        assert!(
+            !a.intersects(&c),
+            "Touching edges shouldn't count as intersecting"
+        );
+        assert_eq!(a.intersection(&c), None);
+    }
+}
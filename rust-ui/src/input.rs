@@ -1,7 +1,76 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::str::FromStr;
+
 use glfw::{Action, Key, Modifiers, MouseButton};
 
 use keybinds::{Key as KeybindsKey, KeyInput, Mods};
-use modes::{MouseButton as MyMouseButton, MouseInput, MouseModifiers};
+use modes::{Config, ModeStack, MouseButton as MyMouseButton, MouseInput, MouseModifiers};
+
+use crate::render::renderer::AppState;
+use crate::render::widgets::UiBuilder;
+
+/// Implemented by app states that let the [crate::render::renderer::Renderer] translate key
+/// presses into actions with [ModeBindings]
+pub trait ActionHandler<M, A>: AppState
+where
+    M: PartialEq + Eq + FromStr + Clone + Copy + Hash,
+    A: Clone + Copy + Debug,
+{
+    /// Called with the action bound to a key press in the innermost mode that has one. `modes`
+    /// is the stack the binding was looked up in, so actions can enter and leave modes.
+    fn handle_action(&mut self, action: A, modes: &mut ModeStack<M, A>, ui: &UiBuilder<Self>);
+}
+
+/// Key bindings held by the renderer, see [crate::render::renderer::Renderer::bindings]
+pub trait Bindings<T>
+where
+    T: AppState,
+{
+    /// Passes the action bound to `input`, if any, to `app`. Returns false if nothing is bound
+    /// to it.
+    fn dispatch(&mut self, input: KeyInput, app: &mut T, ui: &UiBuilder<T>) -> bool;
+}
+
+/// A [ModeStack] and the [Config] its key bindings are read from
+pub struct ModeBindings<M, A, MA>
+where
+    M: PartialEq + Eq + FromStr + Clone + Copy + Hash,
+    A: PartialEq + Eq + FromStr + Clone + Copy + Debug,
+    MA: PartialEq + Eq + FromStr + Clone + Copy,
+{
+    pub modes: ModeStack<M, A>,
+    pub config: Config<M, A, MA>,
+}
+
+impl<M, A, MA> ModeBindings<M, A, MA>
+where
+    M: PartialEq + Eq + FromStr + Clone + Copy + Hash,
+    A: PartialEq + Eq + FromStr + Clone + Copy + Debug,
+    MA: PartialEq + Eq + FromStr + Clone + Copy,
+{
+    pub fn new(modes: ModeStack<M, A>, config: Config<M, A, MA>) -> Self {
+        Self { modes, config }
+    }
+}
+
+impl<T, M, A, MA> Bindings<T> for ModeBindings<M, A, MA>
+where
+    T: ActionHandler<M, A>,
+    M: PartialEq + Eq + FromStr + Clone + Copy + Hash,
+    A: PartialEq + Eq + FromStr + Clone + Copy + Debug,
+    MA: PartialEq + Eq + FromStr + Clone + Copy,
+{
+    fn dispatch(&mut self, input: KeyInput, app: &mut T, ui: &UiBuilder<T>) -> bool {
+        match self.modes.dispatch(&mut self.config.bindings, input) {
+            Some(action) => {
+                app.handle_action(action, &mut self.modes, ui);
+                true
+            }
+            None => false,
+        }
+    }
+}
 
 /// Converts a GLFW key and modifiers to a KeyInput from the keybinds crate.
 /// Returns None if the key is not supported.
@@ -82,6 +151,19 @@ pub fn glfw_key_to_key_input(key: Key, modifiers: Modifiers) -> Option<KeyInput>
     Some(KeyInput::new(base_key, mods))
 }
 
+/// Whether pressing `key` types a character, which arrives separately through
+/// [crate::render::renderer::Renderer::handle_char]. Text inputs claim these keys so that typing
+/// doesn't trigger shortcuts bound to them.
+pub fn types_char(key: Key, modifiers: Modifiers) -> bool {
+    if modifiers.intersects(Modifiers::Control | Modifiers::Alt | Modifiers::Super) {
+        return false;
+    }
+    let code = key as i32;
+    (Key::Space as i32..=Key::World2 as i32).contains(&code)
+        || (Key::Kp0 as i32..=Key::KpAdd as i32).contains(&code)
+        || key == Key::KpEqual
+}
+
 /// Converts a GLFW mouse button and modifiers to a MouseInput.
 /// Returns None if the button is not supported or action is not press/release.
 pub fn glfw_mouse_to_mouse_input(
@@ -135,3 +217,82 @@ pub fn glfw_scroll_to_mouse_input(x: f64, y: f64, modifiers: Modifiers) -> Optio
 
     Some(MouseInput { button, modifiers })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use keybinds::Keybinds;
+
+    use super::*;
+    use crate::render::renderer::tests::DummyState;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum TestMode {
+        Base,
+    }
+
+    impl FromStr for TestMode {
+        type Err = ();
+
+        fn from_str(_: &str) -> Result<Self, Self::Err> {
+            Ok(TestMode::Base)
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum TestAction {
+        Save,
+    }
+
+    impl FromStr for TestAction {
+        type Err = ();
+
+        fn from_str(_: &str) -> Result<Self, Self::Err> {
+            Ok(TestAction::Save)
+        }
+    }
+
+    impl ActionHandler<TestMode, TestAction> for DummyState {
+        fn handle_action(
+            &mut self,
+            action: TestAction,
+            _modes: &mut ModeStack<TestMode, TestAction>,
+            _ui: &UiBuilder<Self>,
+        ) {
+            self.log.push(format!("{action:?}"));
+        }
+    }
+
+    #[test]
+    fn typed_keys_are_told_apart_from_shortcuts() {
+        assert!(types_char(Key::A, Modifiers::empty()));
+        assert!(types_char(Key::Num1, Modifiers::Shift));
+        assert!(types_char(Key::Kp5, Modifiers::empty()));
+        assert!(!types_char(Key::A, Modifiers::Control));
+        assert!(!types_char(Key::Enter, Modifiers::empty()));
+        assert!(!types_char(Key::KpEnter, Modifiers::empty()));
+        assert!(!types_char(Key::Escape, Modifiers::empty()));
+    }
+
+    #[test]
+    fn bound_keys_become_actions() {
+        let mut keybinds = Keybinds::default();
+        keybinds.bind("Ctrl+s", TestAction::Save).unwrap();
+        let mut bindings = ModeBindings::new(
+            ModeStack::with_base(TestMode::Base),
+            Config::<TestMode, TestAction, TestAction> {
+                bindings: HashMap::from([(TestMode::Base, keybinds)]),
+                mouse: HashMap::new(),
+            },
+        );
+        let mut app = DummyState::default();
+        let ui = UiBuilder::new();
+
+        let save = glfw_key_to_key_input(Key::S, Modifiers::Control).unwrap();
+        assert!(bindings.dispatch(save, &mut app, &ui));
+        let unbound = glfw_key_to_key_input(Key::S, Modifiers::empty()).unwrap();
+        assert!(!bindings.dispatch(unbound, &mut app, &ui));
+        assert_eq!(app.log, vec!["Save"]);
+    }
+}
@@ -7,6 +7,8 @@
 use glfw::Context;
 use std::env;
 
+pub mod clipboard;
+pub mod easing;
 pub mod geometry;
 pub mod input;
 pub mod perf_overlay;
@@ -14,11 +16,33 @@ pub mod render;
 pub mod shader;
 pub mod style;
 
+/// Window and context settings for [init_open_gl]
+#[derive(Debug, Clone, Copy)]
+pub struct WindowOptions {
+    pub resizable: bool,
+    /// Show the title bar and borders provided by the window manager
+    pub decorated: bool,
+    pub vsync: bool,
+    /// Number of MSAA samples per pixel, `None` disables multisampling
+    pub samples: Option<u32>,
+}
+
+impl Default for WindowOptions {
+    fn default() -> Self {
+        Self {
+            resizable: true,
+            decorated: true,
+            vsync: true,
+            samples: Some(4),
+        }
+    }
+}
+
+/// Opens a window with a current OpenGL context
 pub fn init_open_gl(
     width: u32,
     height: u32,
-    resizable: bool,
-    vsync: bool,
+    options: WindowOptions,
 ) -> (
     glfw::Glfw,
     glfw::PWindow,
@@ -26,6 +50,63 @@ pub fn init_open_gl(
 ) {
     let mut glfw = glfw::init(glfw::fail_on_errors).unwrap();
 
+    context_hints(&mut glfw, options.samples);
+    glfw.window_hint(glfw::WindowHint::Resizable(options.resizable));
+    glfw.window_hint(glfw::WindowHint::Decorated(options.decorated));
+
+    let (mut window, events) = glfw
+        .create_window(width, height, "App", glfw::WindowMode::Windowed)
+        .unwrap();
+
+    window.make_current();
+    window.set_key_polling(true);
+    window.set_mouse_button_polling(true);
+    window.set_cursor_pos_polling(true);
+    window.set_framebuffer_size_polling(true);
+    window.set_scroll_polling(true);
+    window.set_char_polling(true);
+    window.set_content_scale_polling(true);
+
+    load_gl(&mut window);
+
+    set_vsync(&mut glfw, options.vsync);
+
+    setup_gl_state(width, height, options.samples.is_some());
+
+    (glfw, window, events)
+}
+
+/// Creates an OpenGL context backed by a hidden window. Nothing is presented on screen, instead
+/// pair it with [render::renderer::Renderer::render_to_texture] to render offscreen for
+/// screenshots or tests. The window has to be kept alive for as long as the context is used.
+pub fn init_headless(width: u32, height: u32) -> (glfw::Glfw, glfw::PWindow) {
+    let mut glfw = glfw::init(glfw::fail_on_errors).unwrap();
+    // Offscreen targets are single sampled textures
+    context_hints(&mut glfw, None);
+    glfw.window_hint(glfw::WindowHint::Visible(false));
+
+    let (mut window, _) = glfw
+        .create_window(width, height, "Headless", glfw::WindowMode::Windowed)
+        .unwrap();
+
+    window.make_current();
+    load_gl(&mut window);
+    setup_gl_state(width, height, false);
+
+    (glfw, window)
+}
+
+/// Toggles waiting for the display's vertical blank when swapping buffers. Applies to the
+/// context that is current on the calling thread.
+pub fn set_vsync(glfw: &mut glfw::Glfw, on: bool) {
+    if on {
+        glfw.set_swap_interval(glfw::SwapInterval::Sync(1));
+    } else {
+        glfw.set_swap_interval(glfw::SwapInterval::None);
+    }
+}
+
+fn context_hints(glfw: &mut glfw::Glfw, samples: Option<u32>) {
     // Configure OpenGL context based on target architecture
     #[cfg(target_arch = "aarch64")]
     {
@@ -41,23 +122,12 @@ pub fn init_open_gl(
         glfw.window_hint(glfw::WindowHint::OpenGlProfile(
             glfw::OpenGlProfileHint::Core,
         ));
-        glfw.window_hint(glfw::WindowHint::Samples(Some(4)));
     }
 
-    glfw.window_hint(glfw::WindowHint::Resizable(resizable));
-
-    let (mut window, events) = glfw
-        .create_window(width, height, "App", glfw::WindowMode::Windowed)
-        .unwrap();
-
-    window.make_current();
-    window.set_key_polling(true);
-    window.set_mouse_button_polling(true);
-    window.set_cursor_pos_polling(true);
-    window.set_framebuffer_size_polling(true);
-    window.set_scroll_polling(true);
-    window.set_char_polling(true);
+    glfw.window_hint(glfw::WindowHint::Samples(samples));
+}
 
+fn load_gl(window: &mut glfw::PWindow) {
     gl::load_with(|ptr| {
         let f = window.get_proc_address(ptr);
         match f {
@@ -65,21 +135,37 @@ pub fn init_open_gl(
             None => std::ptr::null(),
         }
     });
+}
 
-    if vsync {
-        glfw.set_swap_interval(glfw::SwapInterval::Sync(1));
-    } else {
-        glfw.set_swap_interval(glfw::SwapInterval::Sync(0));
-    }
-
+fn setup_gl_state(width: u32, height: u32, multisample: bool) {
     unsafe {
         gl::Viewport(0, 0, width as i32, height as i32);
         gl::Enable(gl::BLEND);
-        gl::Enable(gl::MULTISAMPLE);
+        if multisample {
+            gl::Enable(gl::MULTISAMPLE);
+        }
         gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
     }
+}
 
-    (glfw, window, events)
+/// Converts a cursor position reported by glfw into logical pixels for
+/// [render::renderer::Renderer::handle_mouse_position]. Depending on the platform the cursor is
+/// reported in screen coordinates or framebuffer pixels, the ratio between the window and
+/// framebuffer sizes accounts for that.
+pub fn logical_cursor_position(
+    window: &glfw::PWindow,
+    x: f64,
+    y: f64,
+    scale_factor: f32,
+) -> geometry::Vector<f32> {
+    let (window_width, _) = window.get_size();
+    let (framebuffer_width, _) = window.get_framebuffer_size();
+    let pixel_ratio = if window_width > 0 {
+        framebuffer_width as f32 / window_width as f32
+    } else {
+        1.0
+    };
+    geometry::Vector::new(x as f32, y as f32).scaled(pixel_ratio / scale_factor)
 }
 
 pub fn print_env() {
@@ -1,13 +1,62 @@
-use std::{marker::PhantomData, time::Duration};
+use std::{collections::VecDeque, marker::PhantomData, time::Duration};
 
 use crate::{
-    geometry::Vector,
+    geometry::{Rect, Vector},
     render::{
-        Color, Text,
-        renderer::{Anchor, AppState, NodeContext, RenderLayout, flags},
+        COLOR_SUCCESS, Color, FrameStats, Text,
+        renderer::{Anchor, AppState, NodeContext, RenderLayout, Renderer, flags},
+        widgets::{DefaultAtom, UiBuilder, UiData},
     },
 };
-use taffy::{AvailableSpace, Dimension, FlexDirection, Size, Style, TaffyTree, prelude::length};
+use taffy::{
+    AvailableSpace, Dimension, FlexDirection, Layout, NodeId, Size, Style, TaffyTree,
+    prelude::length,
+};
+
+/// Number of frames kept in the frame time history
+const HISTORY_LEN: usize = 120;
+
+/// Draws the frame time history as a line. The top of the graph is the frame time budget, and
+/// slower frames are cut off there.
+#[derive(Debug, Clone)]
+struct FrameTimeGraph {
+    frame_times: Vec<f64>,
+    budget_ms: f64,
+}
+
+impl<T> UiData<T> for FrameTimeGraph
+where
+    T: AppState,
+{
+    fn custom_render(
+        &self,
+        _id: &NodeId,
+        _ctx: &NodeContext<T>,
+        _layout: &Layout,
+        renderer: &mut Renderer<T>,
+        bbox: Rect<f32>,
+    ) {
+        let step = bbox.width() / (HISTORY_LEN - 1) as f32;
+        // The newest frame is always drawn at the right edge
+        let start = HISTORY_LEN - self.frame_times.len();
+        let points: Vec<Vector<f32>> = self
+            .frame_times
+            .iter()
+            .enumerate()
+            .map(|(i, ms)| {
+                let fraction = (ms / self.budget_ms).clamp(0.0, 1.0) as f32;
+                Vector::new(
+                    bbox.x0.x + (start + i) as f32 * step,
+                    bbox.x1.y - fraction * bbox.height(),
+                )
+            })
+            .collect();
+        let window_size = renderer.logical_size();
+        renderer
+            .line_r
+            .draw_polyline(&points, COLOR_SUCCESS, 1.0, window_size);
+    }
+}
 
 pub struct PerformanceOverlay<T>
 where
@@ -18,6 +67,10 @@ where
     pub visible: bool,
     pub avg_sleep_ms: f64,
     pub ram_usage: u64,
+    /// Frame times in milliseconds of the last [HISTORY_LEN] frames, oldest first
+    pub frame_times: VecDeque<f64>,
+    /// What was drawn during the previous frame, see [Renderer::frame_stats]
+    pub frame_stats: FrameStats,
 }
 
 impl<T> Default for PerformanceOverlay<T>
@@ -40,15 +93,32 @@ where
             visible: false,
             avg_sleep_ms: 0.0,
             ram_usage: 0,
+            frame_times: VecDeque::with_capacity(HISTORY_LEN),
+            frame_stats: FrameStats::default(),
         }
     }
 
-    pub fn update(&mut self, avg_sleep_ms: f64, ram_usage: u64) {
+    /// Should be called once per frame
+    pub fn update(&mut self, avg_sleep_ms: f64, ram_usage: u64, frame_stats: FrameStats) {
         self.avg_sleep_ms = avg_sleep_ms;
         self.ram_usage = ram_usage;
+        self.frame_stats = frame_stats;
+        if self.frame_times.len() == HISTORY_LEN {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(self.frame_time_ms());
     }
 
-    pub fn generate_layout(&mut self, size: crate::geometry::Vector<f32>) -> RenderLayout<T> {
+    /// The part of the frame budget that wasn't spent sleeping
+    pub fn frame_time_ms(&self) -> f64 {
+        self.max_frame_time.as_millis() as f64 - self.avg_sleep_ms
+    }
+
+    pub fn generate_layout(
+        &mut self,
+        size: crate::geometry::Vector<f32>,
+        ui: &UiBuilder<T>,
+    ) -> RenderLayout<T> {
         let mut tree = TaffyTree::new();
 
         let title = tree
@@ -75,10 +145,7 @@ where
                 NodeContext {
                     flags: flags::TEXT,
                     text: Text {
-                        text: format!(
-                            "Frame time: {:.2} ms",
-                            self.max_frame_time.as_millis() as f64 - self.avg_sleep_ms
-                        ),
+                        text: format!("Frame time: {:.2} ms", self.frame_time_ms()),
                         font_size: 14,
                         color: Color::new(1.0, 1.0, 1.0, 1.0),
                         ..Default::default()
@@ -104,6 +171,52 @@ where
             )
             .unwrap();
 
+        let graph_id = DefaultAtom::from("perf_overlay_frame_times");
+        ui.insert_state(
+            graph_id.clone(),
+            FrameTimeGraph {
+                frame_times: self.frame_times.iter().copied().collect(),
+                budget_ms: self.max_frame_time.as_secs_f64() * 1000.0,
+            },
+        );
+        let frame_time_graph = tree
+            .new_leaf_with_context(
+                Style {
+                    size: Size {
+                        width: length(HISTORY_LEN as f32 * 2.0),
+                        height: length(40.0),
+                    },
+                    ..Default::default()
+                },
+                NodeContext {
+                    background: Color::new(0.0, 0.0, 0.0, 0.5).into(),
+                    persistent_id: Some(graph_id),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let draw_calls = tree
+            .new_leaf_with_context(
+                Style::default(),
+                NodeContext {
+                    flags: flags::TEXT,
+                    text: Text {
+                        text: format!(
+                            "Draw calls: {} ({} triangles, {} instances)",
+                            self.frame_stats.draw_calls,
+                            self.frame_stats.triangles,
+                            self.frame_stats.instances
+                        ),
+                        font_size: 14,
+                        color: Color::new(1.0, 1.0, 1.0, 1.0),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
         let root = tree
             .new_leaf_with_context(
                 Style {
@@ -121,7 +234,7 @@ where
                     ..Default::default()
                 },
                 NodeContext {
-                    bg_color: Color::new(0.0, 0.0, 0.0, 0.5),
+                    background: Color::new(0.0, 0.0, 0.0, 0.5).into(),
                     ..Default::default()
                 },
             )
@@ -129,6 +242,8 @@ where
 
         tree.add_child(root, title).unwrap();
         tree.add_child(root, frame_time).unwrap();
+        tree.add_child(root, frame_time_graph).unwrap();
+        tree.add_child(root, draw_calls).unwrap();
         tree.add_child(root, ram_usage).unwrap();
 
         RenderLayout {
@@ -142,6 +257,7 @@ where
             root_pos: Vector::zero(),
             anchor: Anchor::BottomRight,
             scissor: false,
+            z: 0,
         }
     }
 }
@@ -0,0 +1,191 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    easing::Easing,
+    render::{
+        Background, Color,
+        renderer::{AppState, lerp},
+        widgets::UiData,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionProperty {
+    All,
+    /// Only the background color
+    Colors,
+    Opacity,
+}
+
+/// Describes which properties of a node ease towards new values instead of snapping to them.
+/// Transitions are only applied to nodes with a persistent id since their progress has to survive
+/// the layout being rebuilt every frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transition {
+    /// `None` disables transitions for the node
+    pub property: Option<TransitionProperty>,
+    pub duration: Duration,
+    pub easing: Easing,
+}
+
+impl Default for Transition {
+    fn default() -> Self {
+        Self {
+            property: None,
+            duration: Duration::from_millis(150),
+            easing: Easing::default(),
+        }
+    }
+}
+
+impl Transition {
+    pub fn animates_colors(&self) -> bool {
+        matches!(
+            self.property,
+            Some(TransitionProperty::All | TransitionProperty::Colors)
+        )
+    }
+
+    pub fn animates_opacity(&self) -> bool {
+        matches!(
+            self.property,
+            Some(TransitionProperty::All | TransitionProperty::Opacity)
+        )
+    }
+}
+
+pub trait Lerp: Copy + PartialEq {
+    fn lerp(self, other: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        lerp(self, other, t)
+    }
+}
+
+impl Lerp for Color {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Color::new(
+            lerp(self.r, other.r, t),
+            lerp(self.g, other.g, t),
+            lerp(self.b, other.b, t),
+            lerp(self.a, other.a, t),
+        )
+    }
+}
+
+impl Lerp for Background {
+    /// Gradients don't blend with other backgrounds and switch over at the end of the transition
+    fn lerp(self, other: Self, t: f32) -> Self {
+        match (self, other) {
+            (Background::Solid(a), Background::Solid(b)) => Background::Solid(a.lerp(b, t)),
+            _ if t < 1.0 => self,
+            _ => other,
+        }
+    }
+}
+
+/// A value moving from `from` to `to`, starting at `started`
+#[derive(Debug, Clone, Copy)]
+struct Tween<V> {
+    from: V,
+    to: V,
+    started: Instant,
+}
+
+impl<V: Lerp> Tween<V> {
+    fn value(&self, now: Instant, transition: &Transition) -> V {
+        let t = if transition.duration.is_zero() {
+            1.0
+        } else {
+            now.duration_since(self.started).as_secs_f32() / transition.duration.as_secs_f32()
+        };
+        self.from.lerp(self.to, transition.easing.apply(t))
+    }
+
+    fn is_running(&self, now: Instant, transition: &Transition) -> bool {
+        self.from != self.to && now.duration_since(self.started) < transition.duration
+    }
+}
+
+/// Steps a tween towards `target`, restarting it from the current value if the target changed
+fn advance<V: Lerp>(
+    tween: &mut Option<Tween<V>>,
+    target: V,
+    now: Instant,
+    transition: &Transition,
+) -> V {
+    let tween = tween.get_or_insert(Tween {
+        from: target,
+        to: target,
+        started: now,
+    });
+    if tween.to != target {
+        *tween = Tween {
+            from: tween.value(now, transition),
+            to: target,
+            started: now,
+        };
+    }
+    tween.value(now, transition)
+}
+
+/// Per node progress of its transitions. Stored in the [crate::render::widgets::UiBuilder] state
+/// map next to the node's own widget state.
+#[derive(Debug, Default)]
+pub struct TransitionState {
+    background: Option<Tween<Background>>,
+    opacity: Option<Tween<f32>>,
+}
+
+impl<T> UiData<T> for TransitionState where T: AppState {}
+
+impl TransitionState {
+    pub fn background(
+        &mut self,
+        target: Background,
+        now: Instant,
+        transition: &Transition,
+    ) -> Background {
+        advance(&mut self.background, target, now, transition)
+    }
+
+    pub fn opacity(&mut self, target: f32, now: Instant, transition: &Transition) -> f32 {
+        advance(&mut self.opacity, target, now, transition)
+    }
+
+    /// Is any of the tweens still moving towards its target at `now`?
+    pub fn is_animating(&self, now: Instant, transition: &Transition) -> bool {
+        self.background
+            .is_some_and(|tween| tween.is_running(now, transition))
+            || self
+                .opacity
+                .is_some_and(|tween| tween.is_running(now, transition))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opacity_eases_towards_new_target() {
+        let transition = Transition {
+            property: Some(TransitionProperty::Opacity),
+            duration: Duration::from_millis(100),
+            easing: Easing::Linear,
+        };
+        let start = Instant::now();
+        let mut state = TransitionState::default();
+        assert_eq!(state.opacity(1.0, start, &transition), 1.0);
+        assert_eq!(state.opacity(0.0, start, &transition), 1.0);
+
+        let halfway = state.opacity(0.0, start + Duration::from_millis(50), &transition);
+        assert!((halfway - 0.5).abs() < 1e-4);
+        let done = state.opacity(0.0, start + Duration::from_millis(200), &transition);
+        assert_eq!(done, 0.0);
+        assert!(state.is_animating(start + Duration::from_millis(50), &transition));
+        assert!(!state.is_animating(start + Duration::from_millis(200), &transition));
+    }
+}
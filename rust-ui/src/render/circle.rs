@@ -1,6 +1,10 @@
 use std::ffi::c_void;
 
-use crate::{geometry::Vector, render::Color, shader::Shader};
+use crate::{
+    geometry::Vector,
+    render::{Color, FrameCounter},
+    shader::Shader,
+};
 
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
@@ -14,6 +18,8 @@ pub struct CircleRenderer {
     vao: u32,
     vbo: u32,
     segments: usize,
+    /// Counts what is drawn, see [crate::render::renderer::Renderer::frame_stats]
+    frame_counter: FrameCounter,
 }
 
 impl CircleRenderer {
@@ -48,9 +54,18 @@ impl CircleRenderer {
             vao,
             vbo,
             segments,
+            frame_counter: FrameCounter::default(),
         }
     }
 
+    /// Counts the draw calls of this renderer into `frame_counter`, usually the one of a
+    /// [crate::render::renderer::Renderer] so that they are part of its
+    /// [crate::render::renderer::Renderer::frame_stats]
+    pub fn with_frame_counter(mut self, frame_counter: FrameCounter) -> Self {
+        self.frame_counter = frame_counter;
+        self
+    }
+
     pub fn draw(
         &self,
         center: Vector<f32>,
@@ -141,6 +156,7 @@ impl CircleRenderer {
             gl::LineWidth(thickness);
 
             gl::BindVertexArray(self.vao);
+            self.frame_counter.count_draw_call(0, 1);
             gl::DrawArrays(gl::LINE_LOOP, 0, self.segments as i32);
             gl::BindVertexArray(0);
 
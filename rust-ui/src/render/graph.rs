@@ -9,7 +9,7 @@ use tracing::{debug, info};
 
 use crate::{
     geometry::{Rect, Vector},
-    render::{Color, graph, rect::vertices, renderer::visual_log},
+    render::{Color, FrameCounter, graph, rect::vertices, renderer::visual_log},
     shader::Shader,
 };
 use anyhow::{Result, anyhow};
@@ -63,7 +63,7 @@ impl Interpolation {
 /// Renders a line graph onto a single quad somehwere on the screen.
 #[derive(Debug)]
 pub struct GraphRenderer {
-    shader: Shader,
+    pub(crate) shader: Shader,
     quad_vao: GLuint,
     quad_vbo: GLuint,
     texture_id: GLuint,
@@ -74,6 +74,8 @@ pub struct GraphRenderer {
     viewport_limits: [Rect<f32>; MAX_TRACES as usize],
     data_x_limits: [Vector<f32>; MAX_TRACES as usize],
     active_traces: usize,
+    /// Counts what is drawn, see [crate::render::renderer::Renderer::frame_stats]
+    pub(crate) frame_counter: FrameCounter,
 }
 
 impl GraphRenderer {
@@ -146,6 +148,7 @@ impl GraphRenderer {
             viewport_limits: [Rect::default(); MAX_TRACES as usize],
             data_x_limits: [Vector::zero(); MAX_TRACES as usize],
             active_traces: 0,
+            frame_counter: FrameCounter::default(),
         }
     }
 
@@ -244,6 +247,7 @@ impl GraphRenderer {
             gl::BindTexture(gl::TEXTURE_2D, self.texture_id);
 
             gl::BindVertexArray(self.quad_vao);
+            self.frame_counter.count_draw_call(2, 1);
             gl::DrawArrays(gl::TRIANGLES, 0, 6);
             gl::BindVertexArray(0);
 
@@ -1,11 +1,53 @@
-use std::ffi::c_void;
+use std::{
+    f32::consts::{PI, TAU},
+    ffi::c_void,
+};
 
-use crate::{geometry::Vector, render::Color, shader::Shader};
+use gl::types::GLenum;
+
+use crate::{
+    geometry::Vector,
+    render::{Color, FrameCounter},
+    shader::Shader,
+};
 
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
 struct LineVertex {
     pub position: [f32; 3],
+    /// Signed distance from the center of the line, only used when antialiasing
+    pub edge: f32,
+}
+
+impl LineVertex {
+    fn new(position: [f32; 3]) -> Self {
+        Self {
+            position,
+            edge: 0.0,
+        }
+    }
+}
+
+/// How the ends of a thick polyline are drawn
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum LineCap {
+    /// The line ends exactly at its end points
+    #[default]
+    Butt,
+    /// A half circle is added around each end point
+    Round,
+    /// The line is extended past each end point by half its thickness
+    Square,
+}
+
+/// How the corners between segments of a thick polyline are filled
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum LineJoin {
+    /// Extends the outer edges until they meet. Falls back to [LineJoin::Bevel] at sharp corners
+    #[default]
+    Miter,
+    Round,
+    Bevel,
 }
 
 /// Draws primitive lines within a 3D space. Can also be used to draw 2D lines.
@@ -14,6 +56,10 @@ pub struct LineRenderer {
     pub shader: Shader,
     vao: u32,
     vbo: u32,
+    /// See [LineRenderer::with_antialiasing]
+    antialias: bool,
+    /// Counts what is drawn, see [crate::render::renderer::Renderer::frame_stats]
+    pub(crate) frame_counter: FrameCounter,
 }
 
 impl LineRenderer {
@@ -38,15 +84,48 @@ impl LineRenderer {
                 std::mem::size_of::<LineVertex>() as i32,
                 std::ptr::null(),
             );
+            // Edge distance attribute
+            gl::EnableVertexAttribArray(1);
+            gl::VertexAttribPointer(
+                1,
+                1,
+                gl::FLOAT,
+                gl::FALSE,
+                std::mem::size_of::<LineVertex>() as i32,
+                (3 * std::mem::size_of::<f32>()) as *const c_void,
+            );
 
             gl::BindBuffer(gl::ARRAY_BUFFER, 0);
             gl::BindVertexArray(0);
         }
 
-        Self { shader, vao, vbo }
+        Self {
+            shader,
+            vao,
+            vbo,
+            antialias: false,
+            frame_counter: FrameCounter::default(),
+        }
+    }
+
+    /// Fades the edges of lines drawn by [LineRenderer::draw], [LineRenderer::draw_dashed] and
+    /// [LineRenderer::draw_dashed_loop] out over about a pixel instead of leaving them jagged.
+    /// Polylines, arcs and 3D lines are always drawn without antialiasing.
+    pub fn with_antialiasing(mut self, antialias: bool) -> Self {
+        self.antialias = antialias;
+        self
+    }
+
+    /// Counts the draw calls of this renderer into `frame_counter`, usually the one of a
+    /// [crate::render::renderer::Renderer] so that they are part of its
+    /// [crate::render::renderer::Renderer::frame_stats]
+    pub fn with_frame_counter(mut self, frame_counter: FrameCounter) -> Self {
+        self.frame_counter = frame_counter;
+        self
     }
 
-    /// Draw 2D lines in window space
+    /// Draw 2D lines in window space with [LineCap::Butt] ends. Antialiased if enabled with
+    /// [LineRenderer::with_antialiasing].
     pub fn draw(
         &self,
         start: Vector<f32>,
@@ -55,21 +134,152 @@ impl LineRenderer {
         thickness: f32,
         window_size: Vector<f32>,
     ) {
+        self.draw_segments(&[(start, end)], color, thickness, window_size);
+    }
+
+    /// Draw dashed 2D lines in window space. Dotted lines can be drawn by using a `dash_len`
+    /// close to the `thickness`.
+    pub fn draw_dashed(
+        &self,
+        start: Vector<f32>,
+        end: Vector<f32>,
+        color: Color,
+        thickness: f32,
+        dash_len: f32,
+        gap_len: f32,
+        window_size: Vector<f32>,
+    ) {
+        let dashes = dash_segments(start, end, dash_len, gap_len);
+        self.draw_segments(&dashes, color, thickness, window_size);
+    }
+
+    /// Draws a dashed outline through `points` in window space, closing it back to the first
+    /// point. The dash pattern continues around corners and is shifted `phase` pixels along the
+    /// outline, so increasing the phase every frame makes the dashes march around the shape.
+    pub fn draw_dashed_loop(
+        &self,
+        points: &[Vector<f32>],
+        color: Color,
+        thickness: f32,
+        dash_len: f32,
+        gap_len: f32,
+        phase: f32,
+        window_size: Vector<f32>,
+    ) {
+        let dashes = dashed_loop_segments(points, dash_len, gap_len, phase);
+        self.draw_segments(&dashes, color, thickness, window_size);
+    }
+
+    /// Draws each separate segment as a quad of the given `thickness` in a single draw call
+    fn draw_segments(
+        &self,
+        segments: &[(Vector<f32>, Vector<f32>)],
+        color: Color,
+        thickness: f32,
+        window_size: Vector<f32>,
+    ) {
+        let (vertices, aa_half_width): (Vec<LineVertex>, f32) = if self.antialias {
+            let vertices = segments
+                .iter()
+                .flat_map(|&(start, end)| aa_quad(start, end, thickness))
+                .collect();
+            (vertices, thickness / 2.0)
+        } else {
+            let vertices = segment_triangles(segments, thickness)
+                .into_iter()
+                .map(|p| LineVertex::new([p.x, p.y, 0.0]))
+                .collect();
+            (vertices, 0.0)
+        };
+        if vertices.is_empty() {
+            return;
+        }
+
+        let ident: glm::Mat4 = glm::identity();
+        let projection = glm::ortho(0.0, window_size.x, window_size.y, 0.0, -1.0, 1.0);
+        self.draw_vertices(
+            &vertices,
+            gl::TRIANGLES,
+            color,
+            thickness,
+            aa_half_width,
+            &projection,
+            &ident,
+            &ident,
+        );
+    }
+
+    /// Draw a connected 2D line through all `points` in window space with a single draw call. The
+    /// line is built from triangles so that interior vertices get mitered joins, falling back to
+    /// bevel joins at sharp corners.
+    pub fn draw_polyline(
+        &self,
+        points: &[Vector<f32>],
+        color: Color,
+        thickness: f32,
+        window_size: Vector<f32>,
+    ) {
+        self.draw_polyline_styled(
+            points,
+            color,
+            thickness,
+            LineCap::default(),
+            LineJoin::default(),
+            window_size,
+        );
+    }
+
+    /// Same as [LineRenderer::draw_polyline] but with control over the caps and joins
+    pub fn draw_polyline_styled(
+        &self,
+        points: &[Vector<f32>],
+        color: Color,
+        thickness: f32,
+        cap: LineCap,
+        join: LineJoin,
+        window_size: Vector<f32>,
+    ) {
+        let vertices: Vec<LineVertex> = polyline_triangles(points, thickness, cap, join)
+            .into_iter()
+            .map(|p| LineVertex::new([p.x, p.y, 0.0]))
+            .collect();
+        if vertices.is_empty() {
+            return;
+        }
+
         let ident: glm::Mat4 = glm::identity();
         let projection = glm::ortho(0.0, window_size.x, window_size.y, 0.0, -1.0, 1.0);
-        let start_3d = glm::vec3(start.x, start.y, 0.0);
-        let end_3d = glm::vec3(end.x, end.y, 0.0);
-        self.draw_3d(
-            start_3d,
-            end_3d,
+        self.draw_vertices(
+            &vertices,
+            gl::TRIANGLES,
             color,
             thickness,
+            0.0,
             &projection,
             &ident,
             &ident,
         );
     }
 
+    /// Draw a circular arc in window space as a polyline with `segments` straight pieces. Angles
+    /// are in radians and the arc is drawn from `start_angle` towards `end_angle`, so a negative
+    /// sweep draws the arc in the opposite direction.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_arc(
+        &self,
+        center: Vector<f32>,
+        radius: f32,
+        start_angle: f32,
+        end_angle: f32,
+        color: Color,
+        thickness: f32,
+        segments: usize,
+        window_size: Vector<f32>,
+    ) {
+        let points = arc_points(center, radius, start_angle, end_angle, segments);
+        self.draw_polyline(&points, color, thickness, window_size);
+    }
+
     /// Allows you to specify your own projection matrix for a more general drawing. Supports 3D positions.
     pub fn draw_3d(
         &self,
@@ -82,14 +292,34 @@ impl LineRenderer {
         view: &glm::Mat4,
     ) {
         let vertices = [
-            LineVertex {
-                position: [start.x, start.y, start.z],
-            },
-            LineVertex {
-                position: [end.x, end.y, end.z],
-            },
+            LineVertex::new([start.x, start.y, start.z]),
+            LineVertex::new([end.x, end.y, end.z]),
         ];
+        self.draw_vertices(
+            &vertices,
+            gl::LINES,
+            color,
+            thickness,
+            0.0,
+            projection,
+            model,
+            view,
+        );
+    }
 
+    /// Draws all vertices as primitives of the given `mode` in a single draw call
+    #[allow(clippy::too_many_arguments)]
+    fn draw_vertices(
+        &self,
+        vertices: &[LineVertex],
+        mode: GLenum,
+        color: Color,
+        thickness: f32,
+        aa_half_width: f32,
+        projection: &glm::Mat4,
+        model: &glm::Mat4,
+        view: &glm::Mat4,
+    ) {
         self.shader.use_shader();
 
         self.shader.set_uniform("model", model);
@@ -97,13 +327,14 @@ impl LineRenderer {
         self.shader.set_uniform("projection", projection);
         let color_vec = glm::make_vec4(&[color.r, color.g, color.b, color.a]);
         self.shader.set_uniform("color", &color_vec);
+        self.shader.set_uniform("halfWidth", &aa_half_width);
 
         // Upload vertex data
         unsafe {
             gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
             gl::BufferData(
                 gl::ARRAY_BUFFER,
-                (std::mem::size_of::<LineVertex>() * vertices.len()) as isize,
+                std::mem::size_of_val(vertices) as isize,
                 vertices.as_ptr() as *const c_void,
                 gl::DYNAMIC_DRAW,
             );
@@ -112,7 +343,13 @@ impl LineRenderer {
             gl::LineWidth(thickness);
 
             gl::BindVertexArray(self.vao);
-            gl::DrawArrays(gl::LINES, 0, 2);
+            let triangles = if mode == gl::TRIANGLES {
+                vertices.len() / 3
+            } else {
+                0
+            };
+            self.frame_counter.count_draw_call(triangles, 1);
+            gl::DrawArrays(mode, 0, vertices.len() as i32);
             gl::BindVertexArray(0);
 
             // Reset line thickness
@@ -121,6 +358,231 @@ impl LineRenderer {
     }
 }
 
+/// Splits the line from `start` to `end` into dashes of `dash_len` separated by `gap_len`. The
+/// last dash is cut short at `end`. Dashes shorter than a pixel are widened to avoid flickering
+/// artifacts and a non-positive gap results in a single solid segment.
+fn dash_segments(
+    start: Vector<f32>,
+    end: Vector<f32>,
+    dash_len: f32,
+    gap_len: f32,
+) -> Vec<(Vector<f32>, Vector<f32>)> {
+    let length = (end - start).length();
+    if length < f32::EPSILON {
+        return vec![];
+    }
+    if gap_len <= 0.0 {
+        return vec![(start, end)];
+    }
+    let dash_len = dash_len.max(1.0);
+    let dir = (end - start).normalized();
+
+    let mut out = vec![];
+    let mut t = 0.0;
+    while t < length {
+        let dash_end = (t + dash_len).min(length);
+        out.push((start + dir * t, start + dir * dash_end));
+        t += dash_len + gap_len;
+    }
+    out
+}
+
+/// Dashes along the closed polygon through `points`. A point at distance `s` along the outline
+/// lies in a dash if `s + phase` falls within the first `dash_len` pixels of a period of the
+/// pattern.
+fn dashed_loop_segments(
+    points: &[Vector<f32>],
+    dash_len: f32,
+    gap_len: f32,
+    phase: f32,
+) -> Vec<(Vector<f32>, Vector<f32>)> {
+    if points.len() < 2 {
+        return vec![];
+    }
+    let dash_len = dash_len.max(1.0);
+    let period = dash_len + gap_len.max(0.0);
+
+    let mut out = vec![];
+    let mut travelled = 0.0;
+    for (i, &start) in points.iter().enumerate() {
+        let end = points[(i + 1) % points.len()];
+        let length = (end - start).length();
+        if length < f32::EPSILON {
+            continue;
+        }
+        if gap_len <= 0.0 {
+            out.push((start, end));
+            continue;
+        }
+        let dir = (end - start).normalized();
+        let mut t = 0.0;
+        while t < length {
+            let u = (travelled + t + phase).rem_euclid(period);
+            if u < dash_len {
+                let dash_end = (t + dash_len - u).min(length);
+                out.push((start + dir * t, start + dir * dash_end));
+                t = dash_end;
+            } else {
+                t += period - u;
+            }
+        }
+        travelled += length;
+    }
+    out
+}
+
+/// Triangulates every segment as its own butt capped line, see [polyline_triangles]
+fn segment_triangles(segments: &[(Vector<f32>, Vector<f32>)], thickness: f32) -> Vec<Vector<f32>> {
+    segments
+        .iter()
+        .flat_map(|&(start, end)| {
+            polyline_triangles(&[start, end], thickness, LineCap::Butt, LineJoin::Miter)
+        })
+        .collect()
+}
+
+/// Samples `segments + 1` evenly spaced points along a circular arc
+fn arc_points(
+    center: Vector<f32>,
+    radius: f32,
+    start_angle: f32,
+    end_angle: f32,
+    segments: usize,
+) -> Vec<Vector<f32>> {
+    let segments = segments.max(1);
+    (0..=segments)
+        .map(|i| {
+            let angle = start_angle + (end_angle - start_angle) * i as f32 / segments as f32;
+            center + Vector::new(angle.cos(), angle.sin()) * radius
+        })
+        .collect()
+}
+
+/// Builds a quad around the line which is a pixel wider than `thickness` on each side to leave
+/// room for the fading edge. Each vertex carries its distance from the center of the line.
+fn aa_quad(start: Vector<f32>, end: Vector<f32>, thickness: f32) -> Vec<LineVertex> {
+    if (end - start).length() < f32::EPSILON || thickness <= 0.0 {
+        return vec![];
+    }
+    let dir = (end - start).normalized();
+    let extent = thickness / 2.0 + 1.0;
+    let offset = Vector::new(-dir.y, dir.x) * extent;
+    let vertex = |p: Vector<f32>, edge: f32| LineVertex {
+        position: [p.x, p.y, 0.0],
+        edge,
+    };
+    vec![
+        vertex(start + offset, extent),
+        vertex(start - offset, -extent),
+        vertex(end + offset, extent),
+        vertex(end + offset, extent),
+        vertex(start - offset, -extent),
+        vertex(end - offset, -extent),
+    ]
+}
+
+/// Miters longer than this many half thicknesses are replaced by bevels
+const MITER_LIMIT: f32 = 4.0;
+
+/// Tessellates a polyline into a triangle list. Each segment becomes a quad, the outer side of
+/// each interior vertex is filled according to `join` and the end points are extended according
+/// to `cap`.
+fn polyline_triangles(
+    points: &[Vector<f32>],
+    thickness: f32,
+    cap: LineCap,
+    join: LineJoin,
+) -> Vec<Vector<f32>> {
+    let mut points = points.to_vec();
+    points.dedup_by(|a, b| (*a - *b).length() < f32::EPSILON);
+    if points.len() < 2 || thickness <= 0.0 {
+        return vec![];
+    }
+    let half = thickness / 2.0;
+    let normal = |a: Vector<f32>, b: Vector<f32>| {
+        let dir = (b - a).normalized();
+        Vector::new(-dir.y, dir.x)
+    };
+
+    let mut out = vec![];
+    let last = points.len() - 1;
+    let start_normal = normal(points[0], points[1]);
+    let end_normal = normal(points[last - 1], points[last]);
+    match cap {
+        LineCap::Butt => {}
+        LineCap::Square => {
+            points[0] = points[0] - (points[1] - points[0]).normalized() * half;
+            points[last] = points[last] + (points[last] - points[last - 1]).normalized() * half;
+        }
+        LineCap::Round => {
+            // Sweeping half a turn from the normal passes through the outward direction
+            fan(points[0], start_normal, PI, half, &mut out);
+            fan(points[last], end_normal, -PI, half, &mut out);
+        }
+    }
+
+    for segment in points.windows(2) {
+        let (a, b) = (segment[0], segment[1]);
+        let offset = normal(a, b) * half;
+        out.extend([a + offset, a - offset, b + offset]);
+        out.extend([b + offset, a - offset, b - offset]);
+    }
+
+    for corner in points.windows(3) {
+        let (a, p, b) = (corner[0], corner[1], corner[2]);
+        let n_in = normal(a, p);
+        let n_out = normal(p, b);
+        let d_in = (p - a).normalized();
+        let d_out = (b - p).normalized();
+        let turn = d_in.x * d_out.y - d_in.y * d_out.x;
+        if turn.abs() < 1e-6 {
+            continue;
+        }
+        // The gap opens on the side facing away from the turn
+        let outer = if turn > 0.0 { -1.0 } else { 1.0 };
+        let edge_in = p + n_in * (outer * half);
+        let edge_out = p + n_out * (outer * half);
+
+        match join {
+            LineJoin::Round => {
+                let (from, to) = (n_in * outer, n_out * outer);
+                let sweep = (from.x * to.y - from.y * to.x).atan2(from.dot(to));
+                fan(p, from, sweep, half, &mut out);
+            }
+            LineJoin::Miter | LineJoin::Bevel => {
+                out.extend([p, edge_in, edge_out]);
+                let miter_dir = (n_in + n_out).normalized();
+                let cos_half_angle = miter_dir.dot(n_out);
+                if join == LineJoin::Miter && cos_half_angle > 1.0 / MITER_LIMIT {
+                    let miter = p + miter_dir * (outer * half / cos_half_angle);
+                    out.extend([edge_in, miter, edge_out]);
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Adds a triangle fan around `center` covering the circle sector which starts in the direction
+/// of the unit vector `from` and spans `sweep` radians
+fn fan(
+    center: Vector<f32>,
+    from: Vector<f32>,
+    sweep: f32,
+    radius: f32,
+    out: &mut Vec<Vector<f32>>,
+) {
+    let start = from.y.atan2(from.x);
+    let segments = ((sweep.abs() / TAU) * 32.0).ceil().max(1.0) as usize;
+    let point = |i: usize| {
+        let angle = start + sweep * i as f32 / segments as f32;
+        center + Vector::new(angle.cos(), angle.sin()) * radius
+    };
+    for i in 0..segments {
+        out.extend([center, point(i), point(i + 1)]);
+    }
+}
+
 impl Drop for LineRenderer {
     fn drop(&mut self) {
         unsafe {
@@ -129,3 +591,170 @@ impl Drop for LineRenderer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::{
+        LineCap, LineJoin, aa_quad, arc_points, dash_segments, dashed_loop_segments,
+        polyline_triangles, segment_triangles,
+    };
+    use crate::geometry::Vector;
+
+    #[test]
+    fn dashes_cover_line_and_end_at_endpoint() {
+        let segments = dash_segments(Vector::new(0.0, 0.0), Vector::new(25.0, 0.0), 5.0, 5.0);
+        let starts: Vec<f32> = segments.iter().map(|(s, _)| s.x).collect();
+        assert_eq!(starts, vec![0.0, 10.0, 20.0]);
+        assert_eq!(segments.last().unwrap().1.x, 25.0);
+
+        let segments = dash_segments(Vector::new(0.0, 0.0), Vector::new(22.0, 0.0), 5.0, 5.0);
+        assert_eq!(segments.last().unwrap().1.x, 22.0);
+    }
+
+    #[test]
+    fn loop_dashes_continue_around_corners() {
+        let square = [
+            Vector::new(0.0, 0.0),
+            Vector::new(6.0, 0.0),
+            Vector::new(6.0, 6.0),
+            Vector::new(0.0, 6.0),
+        ];
+        let segments = dashed_loop_segments(&square, 4.0, 4.0, 0.0);
+        // The gap after the first dash continues past the first corner
+        assert_eq!(segments[0], (Vector::new(0.0, 0.0), Vector::new(4.0, 0.0)));
+        assert_eq!(segments[1], (Vector::new(6.0, 2.0), Vector::new(6.0, 6.0)));
+        // The last dash wraps around to the edge closing the loop
+        assert_eq!(segments[3], (Vector::new(0.0, 6.0), Vector::new(0.0, 4.0)));
+        assert_eq!(segments.len(), 4);
+
+        // Shifting the phase moves the dashes backwards along the outline
+        let shifted = dashed_loop_segments(&square, 4.0, 4.0, 2.0);
+        assert_eq!(shifted[0], (Vector::new(0.0, 0.0), Vector::new(2.0, 0.0)));
+        assert_eq!(shifted[1], (Vector::new(6.0, 0.0), Vector::new(6.0, 4.0)));
+    }
+
+    #[test]
+    fn degenerate_dashes() {
+        let p = Vector::new(3.0, 4.0);
+        assert!(dash_segments(p, p, 5.0, 5.0).is_empty());
+
+        let solid = dash_segments(Vector::new(0.0, 0.0), Vector::new(0.0, 10.0), 2.0, 0.0);
+        assert_eq!(solid.len(), 1);
+
+        // Sub-pixel dashes are widened to a pixel
+        let tiny = dash_segments(Vector::new(0.0, 0.0), Vector::new(10.0, 0.0), 0.001, 1.0);
+        assert_eq!(tiny.len(), 5);
+    }
+
+    #[test]
+    fn dashes_are_separate_quads_of_the_line_thickness() {
+        let dashes = dash_segments(Vector::new(0.0, 0.0), Vector::new(10.0, 0.0), 2.0, 2.0);
+        let triangles = segment_triangles(&dashes, 4.0);
+        assert_eq!(triangles.len(), dashes.len() * 6);
+        assert!(triangles.iter().all(|p| p.y.abs() == 2.0));
+    }
+
+    #[test]
+    fn polyline_segment_is_a_quad() {
+        let triangles = polyline_triangles(
+            &[Vector::new(0.0, 0.0), Vector::new(10.0, 0.0)],
+            2.0,
+            LineCap::Butt,
+            LineJoin::Miter,
+        );
+        assert_eq!(triangles.len(), 6);
+        assert!(triangles.iter().all(|p| p.y.abs() == 1.0));
+    }
+
+    #[test]
+    fn polyline_corner_is_mitered() {
+        let points = [
+            Vector::new(0.0, 0.0),
+            Vector::new(10.0, 0.0),
+            Vector::new(10.0, 10.0),
+        ];
+        let triangles = polyline_triangles(&points, 2.0, LineCap::Butt, LineJoin::Miter);
+        // Two quads, a bevel and a miter
+        assert_eq!(triangles.len(), 6 + 6 + 3 + 3);
+        let miter = triangles[16];
+        assert!((miter - Vector::new(11.0, -1.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn polyline_ignores_degenerate_input() {
+        let p = Vector::new(1.0, 1.0);
+        assert!(polyline_triangles(&[p], 2.0, LineCap::Butt, LineJoin::Miter).is_empty());
+        assert!(polyline_triangles(&[p, p], 2.0, LineCap::Round, LineJoin::Miter).is_empty());
+        assert!(
+            polyline_triangles(
+                &[p, Vector::new(2.0, 1.0)],
+                0.0,
+                LineCap::Butt,
+                LineJoin::Miter
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn square_caps_extend_the_line() {
+        let points = [Vector::new(0.0, 0.0), Vector::new(10.0, 0.0)];
+        let triangles = polyline_triangles(&points, 2.0, LineCap::Square, LineJoin::Miter);
+        let min_x = triangles.iter().map(|p| p.x).fold(f32::MAX, f32::min);
+        let max_x = triangles.iter().map(|p| p.x).fold(f32::MIN, f32::max);
+        assert_eq!((min_x, max_x), (-1.0, 11.0));
+    }
+
+    #[test]
+    fn round_caps_and_joins_stay_within_radius() {
+        let points = [
+            Vector::new(0.0, 0.0),
+            Vector::new(10.0, 0.0),
+            Vector::new(10.0, 10.0),
+        ];
+        let triangles = polyline_triangles(&points, 2.0, LineCap::Round, LineJoin::Round);
+        assert!(triangles.len() > 12);
+        // Every vertex lies within half the thickness of the polyline
+        for p in &triangles {
+            let horizontal = p.y.abs();
+            let vertical = (*p - Vector::new(10.0, p.y.clamp(0.0, 10.0))).length();
+            assert!(
+                horizontal.min(vertical) <= 1.0 + 1e-4,
+                "{:?} is too far from the line",
+                p
+            );
+        }
+        let min_x = triangles.iter().map(|p| p.x).fold(f32::MAX, f32::min);
+        assert!((min_x + 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn aa_quad_leaves_room_for_feathering() {
+        let quad = aa_quad(Vector::new(0.0, 0.0), Vector::new(10.0, 0.0), 2.0);
+        assert_eq!(quad.len(), 6);
+        for v in &quad {
+            assert_eq!(v.position[1], v.edge);
+            assert_eq!(v.edge.abs(), 2.0);
+        }
+        assert!(aa_quad(Vector::new(1.0, 1.0), Vector::new(1.0, 1.0), 2.0).is_empty());
+    }
+
+    #[test]
+    fn arc_points_follow_the_circle() {
+        let center = Vector::new(5.0, 5.0);
+        let points = arc_points(center, 2.0, 0.0, PI, 8);
+        assert_eq!(points.len(), 9);
+        assert!((points[0] - Vector::new(7.0, 5.0)).length() < 1e-5);
+        assert!((points[8] - Vector::new(3.0, 5.0)).length() < 1e-5);
+        assert!(
+            points
+                .iter()
+                .all(|p| ((*p - center).length() - 2.0).abs() < 1e-5)
+        );
+
+        // Zero segments still produce a single straight piece
+        assert_eq!(arc_points(center, 2.0, 0.0, PI, 0).len(), 2);
+    }
+}
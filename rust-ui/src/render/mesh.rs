@@ -1,6 +1,12 @@
-use std::{ffi::c_void, mem::offset_of};
+use std::{collections::HashMap, f32::consts::FRAC_PI_2, ffi::c_void, mem::offset_of, path::Path};
 
-use crate::shader::Shader;
+use anyhow::{Result, anyhow};
+
+use crate::{
+    geometry::Vector,
+    render::{Color, FrameCounter},
+    shader::Shader,
+};
 
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
@@ -9,26 +15,173 @@ pub struct Vertex {
     pub normal: [f32; 3],
 }
 
+/// A perspective camera looking at `target` from `position`. The orbit helpers assume that `up`
+/// is the y-axis.
+#[derive(Debug, Clone, Copy)]
+pub struct Camera {
+    pub position: glm::Vec3,
+    pub target: glm::Vec3,
+    pub up: glm::Vec3,
+    /// Vertical field of view in radians
+    pub fov: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            position: glm::vec3(0.0, 0.0, 10.0),
+            target: glm::vec3(0.0, 0.0, 0.0),
+            up: glm::vec3(0.0, 1.0, 0.0),
+            fov: 45.0f32.to_radians(),
+            near: 0.1,
+            far: 100.0,
+        }
+    }
+}
+
+impl Camera {
+    pub fn view(&self) -> glm::Mat4 {
+        glm::look_at(&self.position, &self.target, &self.up)
+    }
+
+    pub fn projection(&self, aspect_ratio: f32) -> glm::Mat4 {
+        glm::perspective(aspect_ratio, self.fov, self.near, self.far)
+    }
+
+    /// The matrix [MeshRenderer::draw] expects
+    pub fn view_projection(&self, aspect_ratio: f32) -> glm::Mat4 {
+        self.projection(aspect_ratio) * self.view()
+    }
+
+    /// The ray under the pixel `screen_pos` of a viewport of `viewport_size` pixels, as an origin
+    /// on the near plane and a normalized direction. Meant for [MeshRenderer::pick].
+    pub fn ray(
+        &self,
+        screen_pos: Vector<f32>,
+        viewport_size: Vector<f32>,
+    ) -> (glm::Vec3, glm::Vec3) {
+        let inverse = self
+            .view_projection(viewport_size.x / viewport_size.y)
+            .try_inverse()
+            .unwrap_or_else(glm::Mat4::identity);
+        // Normalized device coordinates have y pointing up while the screen's points down
+        let ndc_x = 2.0 * screen_pos.x / viewport_size.x - 1.0;
+        let ndc_y = 1.0 - 2.0 * screen_pos.y / viewport_size.y;
+        let unproject = |z: f32| {
+            let p = inverse * glm::vec4(ndc_x, ndc_y, z, 1.0);
+            p.xyz() / p.w
+        };
+        let near = unproject(-1.0);
+        let far = unproject(1.0);
+        (near, (far - near).normalize())
+    }
+
+    fn distance(&self) -> f32 {
+        glm::distance(&self.position, &self.target)
+    }
+
+    /// Rotates the camera around its target. `delta.x` turns around the up axis and `delta.y`
+    /// tilts up or down, both in radians. Tilting stops just short of the poles so the view never
+    /// flips over.
+    pub fn orbit(&mut self, delta: Vector<f32>) {
+        let offset = self.position - self.target;
+        let radius = offset.norm();
+        if radius == 0.0 {
+            return;
+        }
+        let yaw = offset.x.atan2(offset.z) - delta.x;
+        let pitch =
+            ((offset.y / radius).asin() + delta.y).clamp(-FRAC_PI_2 + 0.01, FRAC_PI_2 - 0.01);
+        self.position = self.target
+            + glm::vec3(
+                pitch.cos() * yaw.sin(),
+                pitch.sin(),
+                pitch.cos() * yaw.cos(),
+            ) * radius;
+    }
+
+    /// Moves the camera and its target sideways so that the scene follows a mouse moved by
+    /// `delta` pixels in a viewport `viewport_height` pixels tall
+    pub fn pan(&mut self, delta: Vector<f32>, viewport_height: f32) {
+        let forward = (self.target - self.position).normalize();
+        let right = forward.cross(&self.up).normalize();
+        let up = right.cross(&forward);
+        let units_per_pixel = 2.0 * self.distance() * (self.fov / 2.0).tan() / viewport_height;
+        let offset = (up * delta.y - right * delta.x) * units_per_pixel;
+        self.position += offset;
+        self.target += offset;
+    }
+
+    /// Moves the camera towards its target for positive `amount` (e.g. scroll wheel steps) and away
+    /// for negative. Each step covers 10% of the remaining distance.
+    pub fn zoom(&mut self, amount: f32) {
+        let distance = (self.distance() * 0.9f32.powf(amount)).max(self.near * 2.0);
+        let direction = (self.position - self.target).normalize();
+        self.position = self.target + direction * distance;
+    }
+}
+
+/// A directional light together with the ambient light that reaches every surface
+#[derive(Debug, Clone, Copy)]
+pub struct Lighting {
+    /// The direction the light travels in. Doesn't need to be normalized.
+    pub direction: glm::Vec3,
+    pub color: Color,
+    pub ambient: Color,
+}
+
+impl Default for Lighting {
+    fn default() -> Self {
+        Self {
+            direction: glm::vec3(-1.0, -2.0, -1.5),
+            color: Color::new(1.0, 1.0, 1.0, 1.0),
+            ambient: Color::new(0.2, 0.2, 0.2, 1.0),
+        }
+    }
+}
+
+/// How the triangles of a mesh are rasterized
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RenderMode {
+    #[default]
+    Solid,
+    /// Only the edges of the triangles
+    Wireframe,
+    /// Only the vertices
+    Points,
+}
+
 /// Renders a single mesh
 #[derive(Debug)]
 pub struct MeshRenderer {
     shader: Shader,
+    pub color: Color,
+    pub lighting: Lighting,
+    pub render_mode: RenderMode,
     vertices: Vec<Vertex>,
     indices: Vec<u32>,
     vao: u32,
     vbo: u32,
     ebo: u32,
+    /// Counts what is drawn, see [crate::render::renderer::Renderer::frame_stats]
+    frame_counter: FrameCounter,
 }
 
 impl MeshRenderer {
     pub fn new(vertices: Vec<Vertex>, indices: Vec<u32>, shader: Shader) -> Self {
         let mut out = MeshRenderer {
             shader,
+            color: Color::new(0.8, 0.8, 0.8, 1.0),
+            lighting: Lighting::default(),
+            render_mode: RenderMode::default(),
             vertices,
             indices,
             vao: 0,
             vbo: 0,
             ebo: 0,
+            frame_counter: FrameCounter::default(),
         };
         unsafe {
             gl::GenVertexArrays(1, &mut out.vao);
@@ -78,37 +231,306 @@ impl MeshRenderer {
         out
     }
 
-    /// Angles in radians
-    pub fn draw(&self, polar_angle: f32, vertical_angle: f32) {
+    /// Loads the positions and normals of a Wavefront OBJ file. Polygons are split into triangles
+    /// and vertices without a normal get the average normal of the faces around them.
+    pub fn from_obj(path: &Path, shader: Shader) -> Result<Self> {
+        let source = std::fs::read_to_string(path)?;
+        let (vertices, indices) = parse_obj(&source)?;
+        Ok(Self::new(vertices, indices, shader))
+    }
+
+    /// Counts the draw calls of this renderer into `frame_counter`, usually the one of a
+    /// [crate::render::renderer::Renderer] so that they are part of its
+    /// [crate::render::renderer::Renderer::frame_stats]
+    pub fn with_frame_counter(mut self, frame_counter: FrameCounter) -> Self {
+        self.frame_counter = frame_counter;
+        self
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_lighting(mut self, lighting: Lighting) -> Self {
+        self.lighting = lighting;
+        self
+    }
+
+    pub fn with_render_mode(mut self, render_mode: RenderMode) -> Self {
+        self.render_mode = render_mode;
+        self
+    }
+
+    /// Finds the nearest triangle hit by the ray from `ray_origin` along `ray_dir`. Returns the
+    /// triangle's index (its position in the index buffer divided by three) and the distance to
+    /// the hit in multiples of `ray_dir`.
+    pub fn pick(&self, ray_origin: glm::Vec3, ray_dir: glm::Vec3) -> Option<(usize, f32)> {
+        self.indices
+            .chunks_exact(3)
+            .enumerate()
+            .filter_map(|(i, triangle)| {
+                let corner =
+                    |j: usize| glm::Vec3::from(self.vertices[triangle[j] as usize].position);
+                ray_triangle_intersection(ray_origin, ray_dir, [corner(0), corner(1), corner(2)])
+                    .map(|t| (i, t))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    /// Draws the mesh as seen through `view_projection`, usually from [Camera::view_projection].
+    /// The mesh is depth tested, so the depth buffer should be cleared along with the color buffer
+    /// every frame.
+    pub fn draw(&self, view_projection: &glm::Mat4) {
         self.shader.use_shader();
-        let mut model = glm::Mat4::identity();
-        model = glm::rotate(&model, polar_angle, &glm::vec3(0.0, 1.0, 0.0));
-        model = glm::rotate(&model, vertical_angle, &glm::vec3(1.0, 0.0, 0.0));
-        let view = glm::look_at(
-            &glm::vec3(0.0, 0.0, 10.0), // camera position
-            &glm::vec3(0.0, 0.0, 0.0),  // look at origin
-            &glm::vec3(0.0, 1.0, 0.0),  // up vector
-        );
-        let projection = glm::perspective(
-            45.0f32.to_radians(),
-            1000.0 / 800.0, // aspect ratio
-            0.1,            // near plane
-            100.0,          // far plane
-        );
+        let model = glm::Mat4::identity();
         self.shader.set_uniform("model", &model);
-        self.shader.set_uniform("view", &view);
-        self.shader.set_uniform("projection", &projection);
+        self.shader.set_uniform("viewProjection", view_projection);
+        let color_vec = |c: Color| glm::make_vec4(&[c.r, c.g, c.b, c.a]);
+        self.shader
+            .set_uniform("objectColor", &color_vec(self.color));
+        self.shader
+            .set_uniform("lightDirection", &self.lighting.direction.normalize());
+        self.shader
+            .set_uniform("lightColor", &color_vec(self.lighting.color));
+        self.shader
+            .set_uniform("ambientColor", &color_vec(self.lighting.ambient));
         unsafe {
-            gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
+            gl::Enable(gl::DEPTH_TEST);
+            match self.render_mode {
+                RenderMode::Solid => {}
+                RenderMode::Wireframe => gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE),
+                RenderMode::Points => {
+                    gl::PolygonMode(gl::FRONT_AND_BACK, gl::POINT);
+                    gl::PointSize(3.0);
+                }
+            }
             gl::BindVertexArray(self.vao);
+            self.frame_counter
+                .count_draw_call(self.indices.len() / 3, 1);
             gl::DrawElements(
                 gl::TRIANGLES,
-                (size_of::<u32>() * self.indices.len()) as i32,
+                self.indices.len() as i32,
                 gl::UNSIGNED_INT,
                 std::ptr::null(),
             );
             gl::BindVertexArray(0);
             gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+            gl::Disable(gl::DEPTH_TEST);
+        }
+    }
+}
+
+/// Möller–Trumbore intersection. Returns how far along `dir` the ray hits the triangle, if at all.
+/// Both sides of the triangle count as hits.
+fn ray_triangle_intersection(
+    origin: glm::Vec3,
+    dir: glm::Vec3,
+    triangle: [glm::Vec3; 3],
+) -> Option<f32> {
+    let edge1 = triangle[1] - triangle[0];
+    let edge2 = triangle[2] - triangle[0];
+    let p = dir.cross(&edge2);
+    let determinant = edge1.dot(&p);
+    if determinant.abs() < f32::EPSILON {
+        // The ray is parallel to the triangle
+        return None;
+    }
+    let inverse_determinant = 1.0 / determinant;
+    let s = origin - triangle[0];
+    let u = s.dot(&p) * inverse_determinant;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = s.cross(&edge1);
+    let v = dir.dot(&q) * inverse_determinant;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = edge2.dot(&q) * inverse_determinant;
+    (t > 0.0).then_some(t)
+}
+
+/// Resolves a one-based, possibly negative (relative to the end), OBJ index
+fn obj_index(index: &str, len: usize, line: usize) -> Result<usize> {
+    let index: i64 = index
+        .parse()
+        .map_err(|_| anyhow!("Couldn't parse index on line {}: {}", line, index))?;
+    let resolved = if index < 0 {
+        len as i64 + index
+    } else {
+        index - 1
+    };
+    if resolved < 0 || resolved >= len as i64 {
+        return Err(anyhow!("Index out of range on line {}: {}", line, index));
+    }
+    Ok(resolved as usize)
+}
+
+fn parse_obj(source: &str) -> Result<(Vec<Vertex>, Vec<u32>)> {
+    let mut positions: Vec<[f32; 3]> = vec![];
+    let mut normals: Vec<[f32; 3]> = vec![];
+    let mut vertices: Vec<Vertex> = vec![];
+    let mut indices: Vec<u32> = vec![];
+    // Vertices are shared between faces whenever both the position and the normal match
+    let mut lookup: HashMap<(usize, Option<usize>), u32> = HashMap::new();
+    let mut missing_normals: Vec<bool> = vec![];
+
+    for (i, l) in source.lines().enumerate() {
+        let mut parts = l.split_whitespace();
+        match parts.next() {
+            Some(kind @ ("v" | "vn")) => {
+                let mut xyz = [0.0; 3];
+                for c in xyz.iter_mut() {
+                    *c = parts
+                        .next()
+                        .and_then(|p| p.parse().ok())
+                        .ok_or_else(|| anyhow!("Couldn't parse vector on line {}: {}", i + 1, l))?;
+                }
+                if kind == "v" {
+                    positions.push(xyz);
+                } else {
+                    normals.push(xyz);
+                }
+            }
+            Some("f") => {
+                let mut face = vec![];
+                for corner in parts {
+                    let mut refs = corner.split('/');
+                    let position = obj_index(refs.next().unwrap_or(""), positions.len(), i + 1)?;
+                    let normal = match refs.nth(1) {
+                        Some(n) if !n.is_empty() => Some(obj_index(n, normals.len(), i + 1)?),
+                        _ => None,
+                    };
+                    let index = *lookup.entry((position, normal)).or_insert_with(|| {
+                        vertices.push(Vertex {
+                            position: positions[position],
+                            normal: normal.map(|n| normals[n]).unwrap_or([0.0; 3]),
+                        });
+                        missing_normals.push(normal.is_none());
+                        (vertices.len() - 1) as u32
+                    });
+                    face.push(index);
+                }
+                if face.len() < 3 {
+                    return Err(anyhow!(
+                        "Face with fewer than three corners on line {}",
+                        i + 1
+                    ));
+                }
+                let corner = |i: u32| glm::Vec3::from(vertices[i as usize].position);
+                let face_normal =
+                    (corner(face[1]) - corner(face[0])).cross(&(corner(face[2]) - corner(face[0])));
+                for &index in &face {
+                    if missing_normals[index as usize] {
+                        let normal = &mut vertices[index as usize].normal;
+                        for (n, f) in normal.iter_mut().zip(face_normal.iter()) {
+                            *n += f;
+                        }
+                    }
+                }
+                for w in 1..face.len() - 1 {
+                    indices.extend([face[0], face[w], face[w + 1]]);
+                }
+            }
+            // Texture coordinates, groups, materials and comments don't affect the geometry
+            _ => {}
+        }
+    }
+
+    for (vertex, _) in vertices
+        .iter_mut()
+        .zip(missing_normals)
+        .filter(|(_, missing)| *missing)
+    {
+        let normal = glm::Vec3::from(vertex.normal);
+        if normal.norm() > 0.0 {
+            vertex.normal = normal.normalize().into();
         }
     }
+
+    Ok((vertices, indices))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Camera, parse_obj, ray_triangle_intersection};
+    use crate::geometry::Vector;
+
+    #[test]
+    fn orbiting_keeps_the_distance_to_the_target() {
+        let mut camera = Camera::default();
+        camera.orbit(Vector::new(1.0, 0.5));
+        assert!((glm::distance(&camera.position, &camera.target) - 10.0).abs() < 1e-4);
+        assert!(
+            camera.position.y > 0.0,
+            "Tilting up should raise the camera"
+        );
+
+        camera.orbit(Vector::new(0.0, 10.0));
+        assert!(
+            camera.position.y < 10.0,
+            "The camera should stop short of the pole"
+        );
+    }
+
+    #[test]
+    fn zoom_and_pan_move_the_camera() {
+        let mut camera = Camera::default();
+        camera.zoom(1.0);
+        assert!((camera.position.z - 9.0).abs() < 1e-4);
+        camera.zoom(-1.0);
+        assert!((camera.position.z - 10.0).abs() < 1e-4);
+
+        camera.pan(Vector::new(10.0, 0.0), 100.0);
+        assert!(
+            camera.target.x < 0.0,
+            "Dragging right should move the camera left"
+        );
+        assert_eq!(camera.position.x, camera.target.x);
+    }
+
+    #[test]
+    fn obj_parsing() {
+        let source = "# A unit square split into two triangles\n\
+            v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+            vn 0 0 1\n\
+            f 1//1 2//1 3//1 4//1\n";
+        let (vertices, indices) = parse_obj(source).unwrap();
+        assert_eq!(vertices.len(), 4);
+        assert_eq!(indices, vec![0, 1, 2, 0, 2, 3]);
+        assert_eq!(vertices[2].position, [1.0, 1.0, 0.0]);
+
+        // Without normals they are computed from the faces
+        let (vertices, _) = parse_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2/5 -1\n").unwrap();
+        assert_eq!(vertices[0].normal, [0.0, 0.0, 1.0]);
+
+        assert!(parse_obj("v 0 0 0\nf 1 2 3\n").is_err());
+    }
+
+    #[test]
+    fn picking_triangles() {
+        let triangle = [
+            glm::vec3(-1.0, -1.0, 0.0),
+            glm::vec3(1.0, -1.0, 0.0),
+            glm::vec3(0.0, 1.0, 0.0),
+        ];
+        let origin = glm::vec3(0.0, 0.0, 5.0);
+        let t = ray_triangle_intersection(origin, glm::vec3(0.0, 0.0, -1.0), triangle);
+        assert_eq!(t, Some(5.0));
+        assert_eq!(
+            ray_triangle_intersection(origin, glm::vec3(0.0, 0.0, 1.0), triangle),
+            None,
+            "Triangles behind the ray shouldn't be hit"
+        );
+        assert_eq!(
+            ray_triangle_intersection(origin, glm::vec3(1.0, 0.0, -1.0), triangle),
+            None
+        );
+
+        let camera = Camera::default();
+        let (origin, dir) = camera.ray(Vector::new(50.0, 50.0), Vector::new(100.0, 100.0));
+        assert!((dir - glm::vec3(0.0, 0.0, -1.0)).norm() < 1e-4);
+        assert!((origin.z - 9.9).abs() < 1e-3);
+    }
 }
@@ -1,3 +1,4 @@
+pub mod animation;
 pub mod circle;
 pub mod graph;
 pub mod line;
@@ -9,6 +10,61 @@ pub mod sprite;
 pub mod text;
 pub mod widgets;
 
+use std::{cell::Cell, rc::Rc};
+
+use string_cache::DefaultAtom;
+
+use crate::geometry::Vector;
+
+/// Counts of what has been drawn in the last frame that drew anything, see
+/// [renderer::Renderer::frame_stats]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FrameStats {
+    pub draw_calls: usize,
+    /// Triangles drawn, counting those of every instance. Points and lines aren't triangles
+    pub triangles: usize,
+    /// Instances drawn, where a draw call without instancing draws a single instance
+    pub instances: usize,
+}
+
+/// Tally of what has been drawn, shared by every renderer owned by a [renderer::Renderer]. Cloning
+/// it gives another handle to the same tally, see [renderer::Renderer::frame_counter]
+#[derive(Debug, Default, Clone)]
+pub struct FrameCounter {
+    stats: Rc<Cell<FrameStats>>,
+    reset_pending: Rc<Cell<bool>>,
+}
+
+impl FrameCounter {
+    /// Counts a draw call of `instances` instances made of `triangles` triangles each. Called by
+    /// the renderers next to every `gl::Draw*`
+    pub(crate) fn count_draw_call(&self, triangles: usize, instances: usize) {
+        self.start_frame();
+        let mut stats = self.stats.get();
+        stats.draw_calls += 1;
+        stats.triangles += triangles * instances;
+        stats.instances += instances;
+        self.stats.set(stats);
+    }
+
+    pub fn stats(&self) -> FrameStats {
+        self.stats.get()
+    }
+
+    /// Starts a new tally once the next frame is drawn. Frames that skip drawing keep the numbers
+    /// of the last one that did.
+    pub(crate) fn reset(&self) {
+        self.reset_pending.set(true);
+    }
+
+    /// Clears the tally if [Self::reset] was called since the last frame was drawn
+    pub(crate) fn start_frame(&self) {
+        if self.reset_pending.replace(false) {
+            self.stats.set(FrameStats::default());
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub struct Color {
     pub r: f32,
@@ -21,6 +77,85 @@ impl Color {
     pub fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
         Self { r, g, b, a }
     }
+
+    /// Multiplies the alpha channel by `opacity`
+    pub fn faded(self, opacity: f32) -> Self {
+        Self {
+            a: self.a * opacity,
+            ..self
+        }
+    }
+}
+
+/// The fill of a node's rectangle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Background {
+    Solid(Color),
+    /// Fades from `from` to `to` across the node. The `angle` is in radians, with zero pointing
+    /// right and positive angles turning clockwise since the y-axis points down.
+    LinearGradient {
+        from: Color,
+        to: Color,
+        angle: f32,
+    },
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Self::Solid(Color::default())
+    }
+}
+
+impl From<Color> for Background {
+    fn from(color: Color) -> Self {
+        Self::Solid(color)
+    }
+}
+
+impl Background {
+    pub fn is_transparent(&self) -> bool {
+        match self {
+            Background::Solid(color) => color.a == 0.0,
+            Background::LinearGradient { from, to, .. } => from.a == 0.0 && to.a == 0.0,
+        }
+    }
+
+    pub fn faded(self, opacity: f32) -> Self {
+        match self {
+            Background::Solid(color) => Background::Solid(color.faded(opacity)),
+            Background::LinearGradient { from, to, angle } => Background::LinearGradient {
+                from: from.faded(opacity),
+                to: to.faded(opacity),
+                angle,
+            },
+        }
+    }
+
+    pub fn set_alpha(&mut self, alpha: f32) {
+        match self {
+            Background::Solid(color) => color.a = alpha,
+            Background::LinearGradient { from, to, .. } => {
+                from.a = alpha;
+                to.a = alpha;
+            }
+        }
+    }
+
+    /// Turns a solid background into a gradient with that color at both ends and returns the
+    /// gradient's colors and angle for modification
+    pub fn as_gradient_mut(&mut self) -> (&mut Color, &mut Color, &mut f32) {
+        if let Background::Solid(color) = *self {
+            *self = Background::LinearGradient {
+                from: color,
+                to: color,
+                angle: 0.0,
+            };
+        }
+        match self {
+            Background::LinearGradient { from, to, angle } => (from, to, angle),
+            Background::Solid(_) => unreachable!(),
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone, Copy)]
@@ -59,6 +194,42 @@ impl Border {
     }
 }
 
+/// A blurred copy of a node's rectangle drawn behind it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shadow {
+    /// Distance in pixels over which the shadow fades out
+    pub blur: f32,
+    pub offset: Vector<f32>,
+    pub color: Color,
+}
+
+impl Default for Shadow {
+    fn default() -> Self {
+        Self {
+            blur: 8.0,
+            offset: Vector::zero(),
+            color: Color::new(0.0, 0.0, 0.0, 0.25),
+        }
+    }
+}
+
+/// A line drawn around the outside of a node's rectangle. Unlike a border it doesn't take up any
+/// space in the layout, which makes it suitable for focus rings and selection indicators.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Outline {
+    pub thickness: f32,
+    pub color: Color,
+}
+
+impl Default for Outline {
+    fn default() -> Self {
+        Self {
+            thickness: 1.0,
+            color: COLOR_PRIMARY,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Default)]
 pub enum TextAlignment {
     #[default]
@@ -67,12 +238,32 @@ pub enum TextAlignment {
     Right,
 }
 
+/// What to do with text that is wider than the box it is drawn in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextOverflow {
+    /// Breaks the text into several lines
+    #[default]
+    Wrap,
+    /// Keeps the text on one line and replaces whatever doesn't fit with a trailing "…"
+    Ellipsis,
+    /// Keeps the text on one line and drops whatever doesn't fit
+    Clip,
+    /// Keeps the text on one line and lets it spill out of the box
+    Visible,
+}
+
 #[derive(Debug, Clone)]
 pub struct Text {
     pub text: String,
     pub font_size: u32,
     pub color: Color,
     pub alignment: TextAlignment,
+    /// Name of a font registered with [text::TextRenderer::add_font]. Uses the default font if
+    /// `None`
+    pub font: Option<DefaultAtom>,
+    pub overflow: TextOverflow,
+    /// Distance between consecutive lines as a multiple of the font size
+    pub line_height: f32,
 }
 
 impl Default for Text {
@@ -82,6 +273,9 @@ impl Default for Text {
             font_size: 12,
             color: Color::new(0.0, 0.0, 0.0, 1.0),
             alignment: Default::default(),
+            font: None,
+            overflow: Default::default(),
+            line_height: 1.2,
         }
     }
 }
@@ -93,6 +287,9 @@ impl Text {
             font_size,
             color,
             alignment: Default::default(),
+            font: None,
+            overflow: Default::default(),
+            line_height: 1.2,
         }
     }
 
@@ -100,6 +297,21 @@ impl Text {
         self.alignment = aligment;
         self
     }
+
+    pub fn with_font(mut self, font: &str) -> Self {
+        self.font = Some(DefaultAtom::from(font));
+        self
+    }
+
+    pub fn with_overflow(mut self, overflow: TextOverflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    pub fn with_line_height(mut self, line_height: f32) -> Self {
+        self.line_height = line_height;
+        self
+    }
 }
 
 // Nord color scheme - https://www.nordtheme.com/
@@ -215,3 +427,30 @@ pub const COLOR_SUCCESS: Color = NORD14; // Aurora green
 pub const COLOR_DANGER: Color = NORD11; // Aurora red
 pub const COLOR_BLACK: Color = NORD0; // Polar night darkest
 pub const COLOR_FACE_HOVER: Color = NORD13; // Aurora yellow - for face highlighting
+
+#[cfg(test)]
+mod tests {
+    use super::{FrameCounter, FrameStats};
+
+    #[test]
+    fn skipped_frames_keep_the_last_stats() {
+        let counter = FrameCounter::default();
+        counter.count_draw_call(2, 3);
+        let drawn = FrameStats {
+            draw_calls: 1,
+            triangles: 6,
+            instances: 3,
+        };
+        assert_eq!(counter.stats(), drawn);
+
+        // A frame that draws nothing
+        counter.reset();
+        assert_eq!(counter.stats(), drawn);
+
+        counter.reset();
+        counter.start_frame();
+        assert_eq!(counter.stats(), FrameStats::default());
+        counter.count_draw_call(2, 1);
+        assert_eq!(counter.stats().draw_calls, 1);
+    }
+}
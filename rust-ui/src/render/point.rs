@@ -1,6 +1,9 @@
 use std::ffi::c_void;
 
-use crate::{render::Color, shader::Shader};
+use crate::{
+    render::{Color, FrameCounter},
+    shader::Shader,
+};
 
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
@@ -13,6 +16,8 @@ pub struct PointRenderer {
     pub shader: Shader,
     vao: u32,
     vbo: u32,
+    /// Counts what is drawn, see [crate::render::renderer::Renderer::frame_stats]
+    frame_counter: FrameCounter,
 }
 
 impl PointRenderer {
@@ -42,7 +47,20 @@ impl PointRenderer {
             gl::BindVertexArray(0);
         }
 
-        Self { shader, vao, vbo }
+        Self {
+            shader,
+            vao,
+            vbo,
+            frame_counter: FrameCounter::default(),
+        }
+    }
+
+    /// Counts the draw calls of this renderer into `frame_counter`, usually the one of a
+    /// [crate::render::renderer::Renderer] so that they are part of its
+    /// [crate::render::renderer::Renderer::frame_stats]
+    pub fn with_frame_counter(mut self, frame_counter: FrameCounter) -> Self {
+        self.frame_counter = frame_counter;
+        self
     }
 
     /// Draw a point in 3D space
@@ -80,6 +98,7 @@ impl PointRenderer {
             gl::PointSize(size);
 
             gl::BindVertexArray(self.vao);
+            self.frame_counter.count_draw_call(0, 1);
             gl::DrawArrays(gl::POINTS, 0, 1);
             gl::BindVertexArray(0);
 
@@ -1,12 +1,12 @@
 use std::ffi::c_void;
 
 use crate::{
-    geometry::Rect,
-    render::{Border, Color},
+    geometry::{Rect, Vector},
+    render::{Background, Border, BorderRadius, Color, FrameCounter, Outline, Shadow},
     shader::Shader,
 };
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct ScissorRegion {
     x: i32,
     y: i32,
@@ -14,12 +14,88 @@ pub struct ScissorRegion {
     height: i32,
 }
 
+impl ScissorRegion {
+    /// The area covered by both regions. Empty if they don't overlap
+    fn intersection(&self, other: &ScissorRegion) -> ScissorRegion {
+        let current_rect = Rect::from_pos_size(
+            Vector::new(self.x, self.y),
+            Vector::new(self.width, self.height),
+        );
+        let new_rect = Rect::from_pos_size(
+            Vector::new(other.x, other.y),
+            Vector::new(other.width, other.height),
+        );
+        match current_rect.intersection(&new_rect) {
+            Some(intersection) => ScissorRegion {
+                x: intersection.x0.x,
+                y: intersection.x0.y,
+                width: intersection.width(),
+                height: intersection.height(),
+            },
+            None => ScissorRegion {
+                x: 0,
+                y: 0,
+                width: 0,
+                height: 0,
+            },
+        }
+    }
+}
+
+/// Per-rectangle data for batched drawing. Mirrors the instance attributes of the
+/// `rounded_rect_instanced` shader.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(C)]
+struct RectInstance {
+    position: [f32; 2],
+    size: [f32; 2],
+    bg_color: [f32; 4],
+    bg_color_to: [f32; 4],
+    border_color: [f32; 4],
+    /// top-left top-right bottom-left bottom-right
+    border_radius: [f32; 4],
+    /// Border thickness, edge softness and gradient angle
+    params: [f32; 3],
+}
+
+impl RectInstance {
+    fn new(rect: Rect<f32>, background: Background, border: Border, edge_softness: f32) -> Self {
+        let (from, to, angle) = match background {
+            Background::Solid(color) => (color, color, 0.0),
+            Background::LinearGradient { from, to, angle } => (from, to, angle),
+        };
+        let rgba = |c: Color| [c.r, c.g, c.b, c.a];
+        Self {
+            position: [rect.x0.x, rect.x0.y],
+            size: [rect.width(), rect.height()],
+            bg_color: rgba(from),
+            bg_color_to: rgba(to),
+            border_color: rgba(border.color),
+            border_radius: [
+                border.radius.top_left,
+                border.radius.top_right,
+                border.radius.bottom_left,
+                border.radius.bottom_right,
+            ],
+            params: [border.thickness, edge_softness, angle],
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct RectRenderer {
-    shader: Shader,
+    pub(crate) shader: Shader,
     quad_vao: u32,
     quad_vbo: u32,
     scissor_stack: Vec<ScissorRegion>,
+    /// Draws batched rectangles, see [RectRenderer::with_batch_shader]
+    pub(crate) batch_shader: Option<Shader>,
+    batch_vao: u32,
+    instance_vbo: u32,
+    /// Rectangles waiting for [RectRenderer::flush]. `None` when not batching
+    batch: Option<Vec<RectInstance>>,
+    /// Counts what is drawn, see [crate::render::renderer::Renderer::frame_stats]
+    pub(crate) frame_counter: FrameCounter,
 }
 
 #[rustfmt::skip]
@@ -69,12 +145,140 @@ impl RectRenderer {
                 quad_vao,
                 quad_vbo,
                 scissor_stack: vec![],
+                batch_shader: None,
+                batch_vao: 0,
+                instance_vbo: 0,
+                batch: None,
+                frame_counter: FrameCounter::default(),
+            }
+        }
+    }
+
+    /// Enables batched drawing using the [crate::shader::ShaderName::RectInstanced] shader. Without
+    /// it, [RectRenderer::push] draws each rectangle immediately.
+    pub fn with_batch_shader(mut self, batch_shader: Shader) -> Self {
+        let stride = size_of::<RectInstance>() as i32;
+        // (location, float count) of each instance attribute in the order of [RectInstance]
+        let attributes = [(1, 2), (2, 2), (3, 4), (4, 4), (5, 4), (6, 4), (7, 3)];
+        unsafe {
+            gl::GenVertexArrays(1, &mut self.batch_vao);
+            gl::GenBuffers(1, &mut self.instance_vbo);
+            gl::BindVertexArray(self.batch_vao);
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.quad_vbo);
+            gl::EnableVertexAttribArray(0);
+            gl::VertexAttribPointer(
+                0,
+                4,
+                gl::FLOAT,
+                gl::FALSE,
+                (4 * size_of::<f32>()) as i32,
+                std::ptr::null(),
+            );
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.instance_vbo);
+            let mut offset = 0;
+            for (location, count) in attributes {
+                gl::EnableVertexAttribArray(location);
+                gl::VertexAttribPointer(
+                    location,
+                    count,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    stride,
+                    (offset * size_of::<f32>()) as *const c_void,
+                );
+                gl::VertexAttribDivisor(location, 1);
+                offset += count as usize;
+            }
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BindVertexArray(0);
+        }
+        self.batch_shader = Some(batch_shader);
+        self
+    }
+
+    /// Starts collecting rectangles from [RectRenderer::push] instead of drawing them one by one.
+    /// Nothing is drawn until [RectRenderer::flush], so anything that should end up on top of the
+    /// batch has to be drawn after flushing.
+    pub fn begin_batch(&mut self) {
+        self.batch.get_or_insert_with(Vec::new).clear();
+    }
+
+    /// Adds a rectangle to the current batch. Draws it right away if no batch has been started or
+    /// batching isn't enabled.
+    pub fn push(
+        &mut self,
+        rect: Rect<f32>,
+        background: Background,
+        border: Border,
+        edge_softness: f32,
+    ) {
+        if background.is_transparent()
+            && (border.color == Color::new(0.0, 0.0, 0.0, 0.0) || border.thickness == 0.0)
+        {
+            return;
+        }
+        if self.batch_shader.is_some()
+            && let Some(batch) = &mut self.batch
+        {
+            batch.push(RectInstance::new(rect, background, border, edge_softness));
+        } else {
+            self.draw_background(rect, background, border, edge_softness);
+        }
+    }
+
+    /// Draws the rectangles batched so far and starts a new batch if one was running. Needed
+    /// before drawing anything else that should end up on top of them.
+    pub fn break_batch(&mut self) {
+        if self.batch.is_some() {
+            self.flush();
+            self.begin_batch();
+        }
+    }
+
+    /// Draws every rectangle pushed since [RectRenderer::begin_batch] with a single instanced draw
+    /// call and ends the batch
+    pub fn flush(&mut self) {
+        let Some(batch) = self.batch.take() else {
+            return;
+        };
+        let Some(shader) = self.batch_shader else {
+            return;
+        };
+        if !batch.is_empty() {
+            shader.use_shader();
+            unsafe {
+                gl::BindVertexArray(self.batch_vao);
+                gl::BindBuffer(gl::ARRAY_BUFFER, self.instance_vbo);
+                gl::BufferData(
+                    gl::ARRAY_BUFFER,
+                    size_of_val(&batch[..]) as isize,
+                    batch.as_ptr() as *const c_void,
+                    gl::DYNAMIC_DRAW,
+                );
+                self.frame_counter.count_draw_call(2, batch.len());
+                gl::DrawArraysInstanced(gl::TRIANGLES, 0, 6, batch.len() as i32);
+                gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+                gl::BindVertexArray(0);
             }
         }
     }
 
     pub fn draw(&self, rect: Rect<f32>, bg_color: Color, border: Border, edge_softness: f32) {
-        if bg_color == Color::new(0.0, 0.0, 0.0, 0.0)
+        self.draw_background(rect, Background::Solid(bg_color), border, edge_softness);
+    }
+
+    /// Same as [RectRenderer::draw] but supports any kind of [Background]
+    pub fn draw_background(
+        &self,
+        rect: Rect<f32>,
+        background: Background,
+        border: Border,
+        edge_softness: f32,
+    ) {
+        if background.is_transparent()
             && (border.color == Color::new(0.0, 0.0, 0.0, 0.0) || border.thickness == 0.0)
         {
             return;
@@ -98,8 +302,15 @@ impl RectRenderer {
 
         // Set uniforms
         self.shader.set_uniform("model", &model);
-        let bg_color_vec = glm::make_vec4(&[bg_color.r, bg_color.g, bg_color.b, bg_color.a]);
+        let (from, to, angle) = match background {
+            Background::Solid(color) => (color, color, 0.0),
+            Background::LinearGradient { from, to, angle } => (from, to, angle),
+        };
+        let bg_color_vec = glm::make_vec4(&[from.r, from.g, from.b, from.a]);
         self.shader.set_uniform("bgColor", &bg_color_vec);
+        let bg_color_to_vec = glm::make_vec4(&[to.r, to.g, to.b, to.a]);
+        self.shader.set_uniform("bgColorTo", &bg_color_to_vec);
+        self.shader.set_uniform("gradientAngle", &angle);
 
         let border_color_vec = glm::make_vec4(&[
             border.color.r,
@@ -126,11 +337,50 @@ impl RectRenderer {
 
         unsafe {
             gl::BindVertexArray(self.quad_vao);
+            self.frame_counter.count_draw_call(2, 1);
             gl::DrawArrays(gl::TRIANGLES, 0, 6);
             gl::BindVertexArray(0);
         }
     }
 
+    /// Draws the shadow of a rectangle with the given corner radii. Reuses the rounded rectangle
+    /// shader with the blur as edge softness so the shadow fades out smoothly.
+    pub fn draw_shadow(&self, rect: Rect<f32>, shadow: Shadow, radius: BorderRadius) {
+        let shadow_rect = Rect {
+            x0: rect.x0 + shadow.offset,
+            x1: rect.x1 + shadow.offset,
+        };
+        let border = Border {
+            thickness: 0.0,
+            radius,
+            color: Color::default(),
+        };
+        self.draw(shadow_rect, shadow.color, border, shadow.blur.max(1.0));
+    }
+
+    /// Draws an outline just outside a rectangle with the given corner radii. Rounded corners
+    /// are widened by the thickness of the outline so it follows the rectangle at a constant
+    /// distance.
+    pub fn draw_outline(&self, rect: Rect<f32>, outline: Outline, radius: BorderRadius) {
+        let t = outline.thickness;
+        let outline_rect = Rect {
+            x0: rect.x0 - Vector::new(t, t),
+            x1: rect.x1 + Vector::new(t, t),
+        };
+        let widen = |r: f32| if r > 0.0 { r + t } else { 0.0 };
+        let border = Border {
+            thickness: t,
+            radius: BorderRadius {
+                top_left: widen(radius.top_left),
+                top_right: widen(radius.top_right),
+                bottom_left: widen(radius.bottom_left),
+                bottom_right: widen(radius.bottom_right),
+            },
+            color: outline.color,
+        };
+        self.draw(outline_rect, Color::new(0.0, 0.0, 0.0, 0.0), border, 1.0);
+    }
+
     pub fn push_scissor_region(
         &mut self,
         x: f32,
@@ -139,6 +389,8 @@ impl RectRenderer {
         height: f32,
         window_height: i32,
     ) {
+        // Batched rectangles were pushed under the previous scissor region
+        self.break_batch();
         // Convert Clay coordinates (top-left origin) to OpenGL coordinates (bottom-left origin)
         let clay_y = y as i32;
         let clay_height = height as i32;
@@ -151,32 +403,9 @@ impl RectRenderer {
             height: clay_height,
         };
 
-        if !self.scissor_stack.is_empty() {
-            let current = &self.scissor_stack[self.scissor_stack.len() - 1];
-
-            // Calculate intersection
-            let left = std::cmp::max(current.x, new_region.x);
-            let right = std::cmp::min(current.x + current.width, new_region.x + new_region.width);
-            let bottom = std::cmp::max(current.y, new_region.y);
-            let top = std::cmp::min(current.y + current.height, new_region.y + new_region.height);
-
-            // Ensure valid intersection
-            if left < right && bottom < top {
-                new_region = ScissorRegion {
-                    x: left,
-                    y: bottom,
-                    width: right - left,
-                    height: top - bottom,
-                };
-            } else {
-                // No intersection - create empty region
-                new_region = ScissorRegion {
-                    x: 0,
-                    y: 0,
-                    width: 0,
-                    height: 0,
-                };
-            }
+        // Nested regions can never draw outside of any of the regions enclosing them
+        if let Some(current) = self.scissor_stack.last() {
+            new_region = current.intersection(&new_region);
         }
 
         self.scissor_stack.push(new_region);
@@ -192,6 +421,7 @@ impl RectRenderer {
     }
 
     pub fn pop_scissor_region(&mut self) {
+        self.break_batch();
         if !self.scissor_stack.is_empty() {
             self.scissor_stack.pop();
             if !self.scissor_stack.is_empty() {
@@ -215,7 +445,76 @@ impl Drop for RectRenderer {
         unsafe {
             gl::DeleteVertexArrays(1, &self.quad_vao);
             gl::DeleteBuffers(1, &self.quad_vbo);
+            if self.batch_shader.is_some() {
+                gl::DeleteVertexArrays(1, &self.batch_vao);
+                gl::DeleteBuffers(1, &self.instance_vbo);
+            }
             gl::Disable(gl::SCISSOR_TEST);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{RectInstance, ScissorRegion};
+    use crate::{
+        geometry::{Rect, Vector},
+        render::{Background, Border, BorderRadius, Color},
+    };
+
+    #[test]
+    fn rect_instance_packs_background_and_border() {
+        let rect = Rect::from_points(Vector::new(10.0, 20.0), Vector::new(40.0, 30.0));
+        let from = Color::new(1.0, 0.0, 0.0, 1.0);
+        let to = Color::new(0.0, 0.0, 1.0, 1.0);
+        let border = Border {
+            thickness: 2.0,
+            radius: BorderRadius::all(4.0),
+            color: Color::new(0.0, 1.0, 0.0, 1.0),
+        };
+        let instance = RectInstance::new(
+            rect,
+            Background::LinearGradient {
+                from,
+                to,
+                angle: 1.5,
+            },
+            border,
+            1.0,
+        );
+
+        assert_eq!(instance.position, [10.0, 20.0]);
+        assert_eq!(instance.size, [30.0, 10.0]);
+        assert_eq!(instance.bg_color, [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(instance.bg_color_to, [0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(instance.border_radius, [4.0; 4]);
+        assert_eq!(instance.params, [2.0, 1.0, 1.5]);
+    }
+
+    #[test]
+    fn nested_scissor_regions_intersect() {
+        let outer = ScissorRegion {
+            x: 0,
+            y: 0,
+            width: 100,
+            height: 100,
+        };
+        let inner = ScissorRegion {
+            x: 50,
+            y: 80,
+            width: 100,
+            height: 100,
+        };
+        assert_eq!(
+            outer.intersection(&inner),
+            ScissorRegion {
+                x: 50,
+                y: 80,
+                width: 50,
+                height: 20,
+            }
+        );
+        let outside = ScissorRegion { x: 200, ..inner };
+        assert_eq!(outer.intersection(&outside).width, 0);
+    }
+}
@@ -1,20 +1,30 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use dashmap::DashMap;
+use gl::types::GLuint;
 use glfw::{Action, Key, Modifiers, MouseButton, Scancode};
+use image::RgbaImage;
 use string_cache::DefaultAtom;
 use tracing::{debug, error};
 
 use crate::{
+    clipboard::{Clipboard, LocalClipboard},
     geometry::Vector,
+    input::{Bindings, glfw_key_to_key_input},
     render::{
-        Border, COLOR_LIGHT, Color, Text,
+        Background, Border, COLOR_LIGHT, Color, FrameCounter, FrameStats, Outline, Shadow, Text,
+        TextAlignment,
+        animation::{Transition, TransitionState},
         graph::GraphRenderer,
         line::LineRenderer,
         rect::RectRenderer,
         sprite::{SpriteKey, SpriteRenderer},
         text::{TextRenderer, total_size},
-        widgets::{UiBuilder, scrollable::ScrollableBuilder},
+        widgets::{UiBuilder, context_menu::ContextMenu, scrollable::ScrollableBuilder},
     },
 };
 use taffy::prelude::*;
@@ -39,10 +49,18 @@ pub mod flags {
     pub const TEXT_SINGLE_LINE: Flag     = 1 << 7;
     /// Should the rectangle be used to render a (line) graph
     pub const GRAPH: Flag                = 1 << 8;
+    /// Clicking the node gives it keyboard focus. Requires the node to have a persistent id
+    pub const FOCUSABLE: Flag            = 1 << 9;
+    /// Tab is passed to [super::NodeContext::on_key] instead of moving focus to the next node
+    pub const CAPTURES_TAB: Flag         = 1 << 10;
 }
 
 // TODO: Investigate if this can be changed to an FnOnce somehow
 pub type EventListener<T> = Arc<dyn Fn(&mut Renderer<T>)>;
+/// Returns true if the key was handled, which keeps it from reaching the bindings and app state
+pub type KeyListener<T> = Arc<dyn Fn(&mut Renderer<T>, Key, Action, Modifiers) -> bool>;
+/// Returns true if the character was handled, which keeps it from reaching the app state
+pub type CharListener<T> = Arc<dyn Fn(&mut Renderer<T>, char) -> bool>;
 
 pub struct DelayedRender<T>
 where
@@ -89,14 +107,26 @@ where
     T: AppState,
 {
     pub flags: Flag,
-    pub bg_color: Color,
+    pub background: Background,
     pub bg_color_hover: Color,
     pub border: Border,
+    pub shadow: Option<Shadow>,
+    /// Drawn around the node without affecting the layout
+    pub outline: Option<Outline>,
+    /// Multiplied into the alpha of everything drawn for this node and all of its descendants.
+    /// Custom rendering is not faded
+    pub opacity: f32,
+    /// Eases changes to the background and opacity. Requires a persistent id
+    pub transition: Transition,
     pub text: Text,
     pub sprite_key: T::SpriteKey,
+    /// Multiplied into the sprite's colors, white draws the sprite as is
+    pub sprite_tint: Color,
     pub offset: Vector<f32>,
     pub delayed_marker: Option<DelayedMarker>,
     // Event listeners
+    /// Called when the scroll wheel moves while the cursor is over the node. The movement is
+    /// available to the listener through [Renderer::scroll_delta]
     pub on_scroll: Option<EventListener<T>>,
     pub on_mouse_enter: Option<EventListener<T>>,
     pub on_mouse_exit: Option<EventListener<T>>,
@@ -107,6 +137,15 @@ where
     pub on_right_mouse_up: Option<EventListener<T>>,
     pub on_middle_mouse_down: Option<EventListener<T>>,
     pub on_middle_mouse_up: Option<EventListener<T>>,
+    /// Called when the left mouse button is pressed twice in quick succession over the node. See
+    /// [Renderer::mouse_double_clicked]
+    pub on_double_click: Option<EventListener<T>>,
+    /// Called for key events while the node has keyboard focus. See [Renderer::focused]
+    pub on_key: Option<KeyListener<T>>,
+    /// Called for text input while the node has keyboard focus. See [Renderer::focused]
+    pub on_char: Option<CharListener<T>>,
+    /// Shown next to the mouse once it has rested on the node for [Renderer::tooltip_delay]
+    pub tooltip: Option<String>,
     // Clipping
     pub scissor: bool,
     // Persistent state
@@ -121,11 +160,16 @@ where
     fn clone(&self) -> Self {
         Self {
             flags: self.flags.clone(),
-            bg_color: self.bg_color.clone(),
-            bg_color_hover: self.bg_color_hover.clone(),
+            background: self.background.clone(),
+            bg_color_hover: self.bg_color_hover,
             border: self.border.clone(),
+            shadow: self.shadow,
+            outline: self.outline,
+            opacity: self.opacity,
+            transition: self.transition,
             text: self.text.clone(),
             sprite_key: self.sprite_key.clone(),
+            sprite_tint: self.sprite_tint,
             offset: self.offset.clone(),
             delayed_marker: self.delayed_marker.clone(),
             on_scroll: self.on_scroll.clone(),
@@ -138,6 +182,10 @@ where
             on_right_mouse_up: self.on_right_mouse_up.clone(),
             on_middle_mouse_down: self.on_middle_mouse_down.clone(),
             on_middle_mouse_up: self.on_middle_mouse_up.clone(),
+            on_double_click: self.on_double_click.clone(),
+            on_key: self.on_key.clone(),
+            on_char: self.on_char.clone(),
+            tooltip: self.tooltip.clone(),
             scissor: self.scissor.clone(),
             persistent_id: self.persistent_id.clone(),
             cursor_idx: self.cursor_idx.clone(),
@@ -152,11 +200,16 @@ where
     fn default() -> Self {
         Self {
             flags: Default::default(),
-            bg_color: Default::default(),
+            background: Default::default(),
             bg_color_hover: Default::default(),
             border: Default::default(),
+            shadow: Default::default(),
+            outline: Default::default(),
+            opacity: 1.0,
+            transition: Default::default(),
             text: Default::default(),
             sprite_key: Default::default(),
+            sprite_tint: Color::new(1.0, 1.0, 1.0, 1.0),
             offset: Default::default(),
             delayed_marker: Default::default(),
             on_scroll: Default::default(),
@@ -168,6 +221,10 @@ where
             on_right_mouse_up: Default::default(),
             on_middle_mouse_down: Default::default(),
             on_middle_mouse_up: Default::default(),
+            on_double_click: Default::default(),
+            on_key: Default::default(),
+            on_char: Default::default(),
+            tooltip: Default::default(),
             scissor: Default::default(),
             persistent_id: Default::default(),
             cursor_idx: Default::default(),
@@ -180,6 +237,36 @@ impl<T> NodeContext<T>
 where
     T: AppState,
 {
+    /// Does the node occlude whatever is behind it from the mouse?
+    fn captures_mouse(&self) -> bool {
+        !self.background.is_transparent()
+            || self.flags & (flags::TEXT | flags::SPRITE | flags::HOVER_BG | flags::FOCUSABLE) != 0
+            || self.on_left_mouse_down.is_some()
+            || self.on_left_mouse_up.is_some()
+            || self.on_right_mouse_down.is_some()
+            || self.on_right_mouse_up.is_some()
+            || self.on_middle_mouse_down.is_some()
+            || self.on_middle_mouse_up.is_some()
+            || self.on_double_click.is_some()
+            || self.on_scroll.is_some()
+            || self.on_mouse_move.is_some()
+            || self.on_mouse_enter.is_some()
+    }
+
+    /// Does the node occlude whatever is behind it from the mouse at `point`? `bbox` is where the
+    /// node is drawn and `clip` the nearest clipping rectangle of the node or its ancestors. Both
+    /// event dispatch and [Renderer::hit_test] go through this so they agree on the hit node.
+    fn occludes(
+        &self,
+        bbox: crate::geometry::Rect<f32>,
+        clip: Option<crate::geometry::Rect<f32>>,
+        point: Vector<f32>,
+    ) -> bool {
+        bbox.contains(point)
+            && clip.is_none_or(|clip| clip.contains(point))
+            && self.captures_mouse()
+    }
+
     pub fn set_listeners(&mut self, listeners: Listeners<T>) {
         self.on_scroll = listeners.on_scroll;
         self.on_mouse_exit = listeners.on_mouse_exit;
@@ -190,6 +277,9 @@ where
         self.on_right_mouse_down = listeners.on_right_mouse_down;
         self.on_middle_mouse_up = listeners.on_middle_mouse_up;
         self.on_middle_mouse_down = listeners.on_middle_mouse_down;
+        self.on_double_click = listeners.on_double_click;
+        self.on_key = listeners.on_key;
+        self.on_char = listeners.on_char;
     }
 }
 
@@ -197,6 +287,7 @@ pub struct Listeners<T>
 where
     T: AppState,
 {
+    /// See [NodeContext::on_scroll]
     pub on_scroll: Option<EventListener<T>>,
     pub on_mouse_enter: Option<EventListener<T>>,
     pub on_mouse_exit: Option<EventListener<T>>,
@@ -206,6 +297,12 @@ where
     pub on_right_mouse_up: Option<EventListener<T>>,
     pub on_middle_mouse_down: Option<EventListener<T>>,
     pub on_middle_mouse_up: Option<EventListener<T>>,
+    /// See [NodeContext::on_double_click]
+    pub on_double_click: Option<EventListener<T>>,
+    /// See [NodeContext::on_key]
+    pub on_key: Option<KeyListener<T>>,
+    /// See [NodeContext::on_char]
+    pub on_char: Option<CharListener<T>>,
 }
 
 impl<T> Default for Listeners<T>
@@ -223,6 +320,9 @@ where
             on_right_mouse_up: Default::default(),
             on_middle_mouse_down: Default::default(),
             on_middle_mouse_up: Default::default(),
+            on_double_click: Default::default(),
+            on_key: Default::default(),
+            on_char: Default::default(),
         }
     }
 }
@@ -251,10 +351,13 @@ where
 {
     /// The current frame number
     pub frame: usize,
-    /// The window width
+    /// The framebuffer width in pixels
     pub width: u32,
-    /// The window height
+    /// The framebuffer height in pixels
     pub height: u32,
+    /// Physical pixels per logical pixel, as reported by the window's content scale. Layouts are
+    /// computed in logical pixels so they keep their physical size across displays
+    pub scale_factor: f32,
     /// Is the mouse left mouse button currently pressed down
     pub mouse_left_down: bool,
     /// Is the mouse right mouse button currently pressed down
@@ -273,11 +376,44 @@ where
     pub scroll_delta: Vector<f32>,
     /// The mouse position last frame
     pub last_mouse_pos: Vector<f32>,
+    /// When the current frame started updating. Drives transitions so that every node in a frame
+    /// advances by the same amount
+    pub frame_start: Instant,
+    /// Was the left mouse button pressed for the second time in quick succession this frame
+    pub mouse_double_clicked: bool,
+    /// Has anything happened that could change the next frame? See [Self::render_if_dirty]
+    dirty: bool,
+    /// The longest time between two presses that still counts as a double click
+    pub double_click_threshold: Duration,
+    /// How far the mouse may move between two presses that count as a double click
+    pub double_click_tolerance: f32,
+    /// Time and position of the last left mouse button press
+    last_click: Option<(Instant, Vector<f32>)>,
+    /// How long the mouse has to rest on a node before its [NodeContext::tooltip] is shown
+    pub tooltip_delay: Duration,
+    /// Tooltip of the topmost hovered node, when the mouse started resting on it and where
+    hovered_tooltip: Option<(String, Instant, Vector<f32>)>,
+    /// Tooltip found while collecting event listeners this frame and the layer it was found in
+    tooltip_candidate: Option<(String, i32)>,
+    /// Focusable node found under a left press while collecting event listeners this frame
+    focus_candidate: Option<DefaultAtom>,
+    /// The open context menu, drawn above the app layers. See [Renderer::open_context_menu]
+    pub context_menu: Option<ContextMenu<T>>,
+    /// Translates key presses into actions for the app, see [crate::input::ModeBindings]. Keys
+    /// without a binding are passed to [AppState::handle_key] as usual.
+    pub bindings: Option<Box<dyn Bindings<T>>>,
+    /// Persistent id of the node receiving key events through [NodeContext::on_key]
+    pub focused: Option<DefaultAtom>,
+    /// Used by text widgets to copy and paste. Only shared within the program unless replaced with
+    /// a [crate::clipboard::GlfwClipboard]
+    pub clipboard: Box<dyn Clipboard>,
     pub rect_r: RectRenderer,
     pub text_r: TextRenderer,
     pub line_r: LineRenderer,
     pub sprite_r: SpriteRenderer<T::SpriteKey>,
     pub graph_r: GraphRenderer,
+    /// Shared by the renderers above to count what they draw, see [Self::frame_stats]
+    frame_counter: FrameCounter,
     /// Event listeners which have been triggered and are waiting to be called
     pending_event_listeners: Vec<EventListener<T>>,
     hover_states: HashMap<NodeId, bool>,
@@ -296,6 +432,8 @@ where
     // --- Event capture
     /// Has the mouse hit any ui elements in a layer? This is the layer in which it happened
     pub mouse_hit_layer: i32,
+    /// The node in [Self::mouse_hit_layer] which captured the mouse
+    last_hit_node: Option<NodeId>,
     /// The UI builder used for constructing layouts and managing widget state
     pub ui_builder: UiBuilder<T>,
     /// Used to keep track of UI elements that should be drawn later than the layer they were specified in.
@@ -309,17 +447,24 @@ where
     T: AppState,
 {
     pub fn new(
-        rect_renderer: RectRenderer,
-        text_renderer: TextRenderer,
-        line_renderer: LineRenderer,
-        sprite_renderer: SpriteRenderer<T::SpriteKey>,
-        graph_renderer: GraphRenderer,
+        mut rect_renderer: RectRenderer,
+        mut text_renderer: TextRenderer,
+        mut line_renderer: LineRenderer,
+        mut sprite_renderer: SpriteRenderer<T::SpriteKey>,
+        mut graph_renderer: GraphRenderer,
         initial_state: T,
     ) -> Self {
+        let frame_counter = FrameCounter::default();
+        rect_renderer.frame_counter = frame_counter.clone();
+        text_renderer.frame_counter = frame_counter.clone();
+        line_renderer.frame_counter = frame_counter.clone();
+        sprite_renderer.frame_counter = frame_counter.clone();
+        graph_renderer.frame_counter = frame_counter.clone();
         Self {
             frame: 0,
             width: 1000,
             height: 800,
+            scale_factor: 1.0,
             mouse_left_down: false,
             mouse_left_was_down: false,
             mouse_right_down: false,
@@ -329,11 +474,26 @@ where
             mouse_pos: Vector::zero(),
             scroll_delta: Vector::zero(),
             last_mouse_pos: Vector::zero(),
+            frame_start: Instant::now(),
+            mouse_double_clicked: false,
+            dirty: true,
+            double_click_threshold: Duration::from_millis(400),
+            double_click_tolerance: 4.0,
+            last_click: None,
+            tooltip_delay: Duration::from_millis(500),
+            hovered_tooltip: None,
+            tooltip_candidate: None,
+            focus_candidate: None,
+            context_menu: None,
+            bindings: None,
+            focused: None,
+            clipboard: Box::new(LocalClipboard::default()),
             rect_r: rect_renderer,
             text_r: text_renderer,
             line_r: line_renderer,
             sprite_r: sprite_renderer,
             graph_r: graph_renderer,
+            frame_counter,
             pending_event_listeners: vec![],
             hover_states: HashMap::new(),
             app_state: initial_state,
@@ -345,32 +505,47 @@ where
             debug_expanded: true,
             layers: Arc::new(vec![]),
             mouse_hit_layer: -1,
+            last_hit_node: None,
             ui_builder: UiBuilder::new(),
             delayed_renders: vec![],
         }
     }
 
-    fn enable_scissor_for_layer(&self, root_pos: Vector<f32>, size: Vector<f32>) {
-        let opengl_y = self.height as f32 - root_pos.y - size.y;
-        unsafe {
-            gl::Enable(gl::SCISSOR_TEST);
-            gl::Scissor(
-                root_pos.x as i32,
-                opengl_y as i32,
-                size.x as i32,
-                size.y as i32,
-            );
-        }
+    /// The size of the window in logical pixels, which is what layouts are computed in
+    pub fn logical_size(&self) -> Vector<f32> {
+        Vector::new(
+            self.width as f32 / self.scale_factor,
+            self.height as f32 / self.scale_factor,
+        )
     }
 
-    fn disable_scissor(&self) {
-        unsafe {
-            gl::Disable(gl::SCISSOR_TEST);
-        }
+    /// Orthographic projection mapping logical pixels with the origin in the top left corner onto
+    /// the whole framebuffer
+    pub fn projection(&self) -> glm::Mat4 {
+        let size = self.logical_size();
+        glm::ortho(0.0, size.x, size.y, 0.0, -1.0, 1.0)
+    }
+
+    /// Clips drawing to the intersection of the given area and the current clip until the
+    /// matching [Self::pop_scissor]
+    fn push_scissor(&mut self, pos: Vector<f32>, size: Vector<f32>) {
+        // Scissor regions are specified in framebuffer pixels
+        let pos = pos.scaled(self.scale_factor);
+        let size = size.scaled(self.scale_factor);
+        self.rect_r
+            .push_scissor_region(pos.x, pos.y, size.x, size.y, self.height as i32);
+    }
+
+    fn pop_scissor(&mut self) {
+        self.rect_r.pop_scissor_region();
     }
 
     /// Runs all the triggered but not yet called event listeners
     fn run_event_listeners(&mut self) {
+        // Listeners change state that the layout was computed from
+        if !self.pending_event_listeners.is_empty() {
+            self.dirty = true;
+        }
         while let Some(el) = self.pending_event_listeners.pop() {
             (*el)(self)
         }
@@ -381,8 +556,37 @@ where
         self.mouse_left_was_down = self.mouse_left_down;
         self.mouse_right_was_down = self.mouse_right_down;
         self.mouse_middle_was_down = self.mouse_middle_down;
+        self.mouse_double_clicked = false;
         self.scroll_delta.x = 0.0;
         self.scroll_delta.y = 0.0;
+        self.frame_counter.reset();
+    }
+
+    /// Draw calls, triangles and instances drawn by all renderers since [Self::pre_update]. Read it
+    /// after rendering and before the next [Self::pre_update] to get the numbers for a whole frame.
+    /// Frames skipped by [Self::render_if_dirty] keep the numbers of the last frame that was drawn.
+    pub fn frame_stats(&self) -> FrameStats {
+        self.frame_counter.stats()
+    }
+
+    /// Another handle to the tally behind [Self::frame_stats]. Renderers that aren't owned by this
+    /// one can count into it through `with_frame_counter`, e.g.
+    /// [crate::render::mesh::MeshRenderer::with_frame_counter]
+    pub fn frame_counter(&self) -> FrameCounter {
+        self.frame_counter.clone()
+    }
+
+    /// Counts into `frame_counter` instead of a tally of its own. Lets renderers that have to be
+    /// created before this one, such as those owned by the app state, be part of
+    /// [Self::frame_stats].
+    pub fn with_frame_counter(mut self, frame_counter: FrameCounter) -> Self {
+        self.rect_r.frame_counter = frame_counter.clone();
+        self.text_r.frame_counter = frame_counter.clone();
+        self.line_r.frame_counter = frame_counter.clone();
+        self.sprite_r.frame_counter = frame_counter.clone();
+        self.graph_r.frame_counter = frame_counter.clone();
+        self.frame_counter = frame_counter;
+        self
     }
 
     /// Should be called on every frame, before the application states update method *(if it has
@@ -390,8 +594,10 @@ where
     pub fn update(&mut self) {
         let _span = tracy_client::span!("App update");
         self.frame += 1;
+        self.frame_start = Instant::now();
         self.ui_builder.update(self.frame);
         self.mouse_hit_layer = -1;
+        self.last_hit_node = None;
 
         match self.debug_drag {
             MouseDragState::Pressed(pressed_at) => {
@@ -407,7 +613,54 @@ where
         self.run_event_listeners();
     }
 
-    /// Passes key presses to the application state
+    /// Makes the next [Self::render_if_dirty] draw a frame. Needed when the application state
+    /// changes without any input, such as when data arrives in the background.
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Re-reads the sources of every shader used by the renderers, see [crate::shader::Shader::reload]. Meant to
+    /// be bound to a hotkey while iterating on GLSL. Shaders that fail to compile are logged and
+    /// keep their previous program. Uniforms such as the projection have to be set again.
+    pub fn reload_shaders(&mut self) {
+        let shaders = [
+            Some(self.rect_r.shader),
+            self.rect_r.batch_shader,
+            Some(self.text_r.shader),
+            Some(self.line_r.shader),
+            Some(self.sprite_r.shader),
+            Some(self.graph_r.shader),
+        ];
+        for shader in shaders.into_iter().flatten() {
+            if let Some(name) = shader.name()
+                && let Err(e) = shader.reload()
+            {
+                error!("Failed to reload shader {:?}: {e}", name);
+            }
+        }
+        self.dirty = true;
+    }
+
+    /// Calls [Self::update] and [Self::render], but only if input, [Self::mark_dirty], a running
+    /// transition or a pending tooltip could have changed the frame since the last time. Returns
+    /// false if nothing was drawn, in which case the screen shouldn't be cleared and the buffers
+    /// don't need to be swapped.
+    pub fn render_if_dirty(&mut self) -> bool {
+        if !self.dirty {
+            return false;
+        }
+        self.dirty = false;
+        self.update();
+        self.render();
+        true
+    }
+
+    /// Passes key presses to the focused node's [NodeContext::on_key], then to the bindings and
+    /// the application state unless the node handled them
     pub fn handle_key(
         &mut self,
         key: Key,
@@ -415,12 +668,44 @@ where
         action: Action,
         modifiers: Modifiers,
     ) {
+        self.dirty = true;
+        if key == Key::Escape && action == Action::Press && self.context_menu.is_some() {
+            self.close_context_menu();
+            return;
+        }
+        if key == Key::Tab
+            && (action == Action::Press || action == Action::Repeat)
+            && tab_moves_focus(self.focused_context())
+            && self.focus_next(modifiers.contains(Modifiers::Shift))
+        {
+            return;
+        }
+        if let Some(on_key) = self.focused_context().and_then(|ctx| ctx.on_key.clone())
+            && (*on_key)(self, key, action, modifiers)
+        {
+            return;
+        }
+        if (action == Action::Press || action == Action::Repeat)
+            && let Some(bindings) = &mut self.bindings
+            && let Some(input) = glfw_key_to_key_input(key, modifiers)
+            && bindings.dispatch(input, &mut self.app_state, &self.ui_builder)
+        {
+            return;
+        }
         self.app_state
             .handle_key(key, scancode, action, modifiers, &self.ui_builder);
     }
 
-    /// Passes character input to application state
+    /// Passes character input to the focused node's [NodeContext::on_char], then to the
+    /// application state unless the node handled it
     pub fn handle_char(&mut self, unicode: u32) {
+        self.dirty = true;
+        if let Some(ch) = char::from_u32(unicode)
+            && let Some(on_char) = self.focused_context().and_then(|ctx| ctx.on_char.clone())
+            && (*on_char)(self, ch)
+        {
+            return;
+        }
         self.app_state.handle_char(unicode, &self.ui_builder);
     }
 
@@ -431,10 +716,27 @@ where
         action: Action,
         modifiers: Modifiers,
     ) {
+        self.dirty = true;
         match button {
             MouseButton::Button1 => {
                 self.mouse_left_down =
                     action == glfw::Action::Press || action == glfw::Action::Repeat;
+                if action == glfw::Action::Press {
+                    let now = Instant::now();
+                    self.mouse_double_clicked = is_double_click(
+                        self.last_click,
+                        now,
+                        self.mouse_pos,
+                        self.double_click_threshold,
+                        self.double_click_tolerance,
+                    );
+                    // A third click should start a new pair rather than double click again
+                    self.last_click = if self.mouse_double_clicked {
+                        None
+                    } else {
+                        Some((now, self.mouse_pos))
+                    };
+                }
                 self.app_state
                     .handle_mouse_button(button, action, modifiers, &self.ui_builder);
             }
@@ -456,6 +758,7 @@ where
 
     /// Passes mouse position changes to the application state
     pub fn handle_mouse_position(&mut self, position: Vector<f32>) {
+        self.dirty = true;
         let delta = position - self.last_mouse_pos;
         self.last_mouse_pos = self.mouse_pos;
         self.mouse_pos = position;
@@ -464,19 +767,25 @@ where
 
     /// Passes mouse scroll events to the application state
     pub fn handle_mouse_scroll(&mut self, scroll_delta: Vector<f32>) {
+        self.dirty = true;
         self.scroll_delta = scroll_delta;
         self.app_state.handle_mouse_scroll(scroll_delta);
     }
 
     fn compute_layout(&mut self) {
-        let window_size = Vector::new(self.width as f32, self.height as f32);
+        let window_size = self.logical_size();
         let mut layers = self
             .app_state
             .generate_layout(window_size, &self.ui_builder);
+        // Stable, so equal layers keep their order. The layers added by the renderer go on top.
+        layers.sort_by_key(|layer| layer.z);
 
         if self.show_debug_layer {
             layers.push(self.debug_layer());
         }
+        if let Some(menu) = self.context_menu_layer() {
+            layers.push(menu);
+        }
 
         for (i, layer) in layers.iter_mut().enumerate().rev() {
             layer
@@ -495,17 +804,7 @@ where
                 )
                 .unwrap();
             let size: Vector<f32> = layer.tree.layout(layer.root).unwrap().size.into();
-            let pos = match layer.anchor {
-                Anchor::TopLeft => layer.root_pos,
-                Anchor::TopRight => {
-                    Vector::new(window_size.x - layer.root_pos.x - size.x, layer.root_pos.y)
-                }
-                Anchor::BottomLeft => {
-                    Vector::new(layer.root_pos.x, window_size.y - layer.root_pos.y - size.y)
-                }
-                Anchor::BottomRight => window_size - layer.root_pos - size,
-                Anchor::Center => (window_size - size).scaled(0.5) + layer.root_pos,
-            };
+            let pos = layer.anchor.position(layer.root_pos, size, window_size);
             for marker in &layer.delayed_markers {
                 match self.ui_builder.node_id(&marker.attached_to) {
                     Some(attached_to) => {
@@ -558,6 +857,7 @@ where
                                     abs_pos.y + attached_size.height / 2.0
                                         - marker_size.height / 2.0,
                                 ),
+                                Anchor::Custom(offset) => abs_pos + offset,
                             };
 
                             self.delayed_renders.push(DelayedRender {
@@ -595,36 +895,77 @@ where
             }
             let _ = self.collect_event_listeners(&layer.tree, layer.root, pos, i as i32);
         }
+
+        // A left press focuses the focusable node under it, or clears the focus if there is none
+        let focus_candidate = self.focus_candidate.take();
+        if self.mouse_left_down && !self.mouse_left_was_down && focus_candidate != self.focused {
+            self.set_focus(focus_candidate);
+        }
+
+        // Moving the mouse hides the tooltip and restarts the delay
+        self.hovered_tooltip = match (self.tooltip_candidate.take(), self.hovered_tooltip.take()) {
+            (Some((text, _)), Some((hovered, since, pos)))
+                if text == hovered && pos == self.mouse_pos =>
+            {
+                Some((hovered, since, pos))
+            }
+            (Some((text, _)), _) => Some((text, self.frame_start, self.mouse_pos)),
+            (None, _) => None,
+        };
+        if let Some((_, since, _)) = &self.hovered_tooltip
+            && self.frame_start.duration_since(*since) < self.tooltip_delay
+        {
+            // Nothing else may happen until the tooltip is due
+            self.dirty = true;
+        }
+        if let Some((text, since, pos)) = &self.hovered_tooltip
+            && self.frame_start.duration_since(*since) >= self.tooltip_delay
+        {
+            let mut layer = self.tooltip_layer(text, *pos);
+            layer
+                .tree
+                .compute_layout_with_measure(
+                    layer.root,
+                    layer.desired_size,
+                    |known_dimensions, available_space, _node_id, node_context, _style| {
+                        measure_function(
+                            known_dimensions,
+                            available_space,
+                            node_context,
+                            &mut self.text_r,
+                        )
+                    },
+                )
+                .unwrap();
+            // Keep the tooltip on screen
+            let size: Vector<f32> = layer.tree.layout(layer.root).unwrap().size.into();
+            layer.root_pos.x = layer.root_pos.x.min(window_size.x - size.x).max(0.0);
+            if layer.root_pos.y + size.y > window_size.y {
+                layer.root_pos.y = (pos.y - size.y - 4.0).max(0.0);
+            }
+            layers.push(layer);
+        }
         self.layers = layers.into();
     }
 
     /// Fetches a layout tree for each layer from the application state, draws them to the screen
     /// and checks if any event listeners should run (calls [Renderer::render]).
     pub fn render(&mut self) {
-        let window_size = Vector::new(self.width as f32, self.height as f32);
+        self.frame_counter.start_frame();
+        let window_size = self.logical_size();
         let layers = self.layers.clone();
         for layer in layers.iter() {
             let size: Vector<f32> = layer.tree.layout(layer.root).unwrap().size.into();
-            let pos = match layer.anchor {
-                Anchor::TopLeft => layer.root_pos,
-                Anchor::TopRight => {
-                    Vector::new(window_size.x - layer.root_pos.x - size.x, layer.root_pos.y)
-                }
-                Anchor::BottomLeft => {
-                    Vector::new(layer.root_pos.x, window_size.y - layer.root_pos.y - size.y)
-                }
-                Anchor::BottomRight => window_size - layer.root_pos - size,
-                Anchor::Center => (window_size - size).scaled(0.5) + layer.root_pos,
-            };
+            let pos = layer.anchor.position(layer.root_pos, size, window_size);
 
             if layer.scissor {
-                self.enable_scissor_for_layer(pos, size);
+                self.push_scissor(pos, size);
             }
 
             let _ = self.render_tree(&layer.tree, layer.root, pos);
 
             if layer.scissor {
-                self.disable_scissor();
+                self.pop_scissor();
             }
         }
 
@@ -645,12 +986,18 @@ where
                     delayed.pos.y - size.height,
                 ),
                 Anchor::Center => delayed.pos + Vector::new(size.width / 2.0, size.height / 2.0),
+                Anchor::Custom(offset) => delayed.pos + offset,
             };
             let _ = self.render_tree(&layer.tree, delayed.id, pos);
         }
         self.delayed_renders.clear();
     }
 
+    fn register_hit(&mut self, id: NodeId, layer_idx: i32) {
+        self.mouse_hit_layer = layer_idx;
+        self.last_hit_node = Some(id);
+    }
+
     fn collect_event_listeners(
         &mut self,
         tree: &TaffyTree<NodeContext<T>>,
@@ -666,29 +1013,7 @@ where
             let default_ctx = &NodeContext::default();
             let ctx = tree.get_node_context(id).unwrap_or(default_ctx);
 
-            let mut abs_pos = layout.location + parent_pos;
-
-            if ctx.flags & flags::SCROLL_BAR != 0 {
-                if let Some(parent_bbox) = tree.parent(id).map(|pid| tree.layout(pid).unwrap()) {
-                    abs_pos.y += lerp(
-                        0.0,
-                        parent_bbox.size.height - layout.size.height,
-                        ctx.offset.y,
-                    );
-                }
-            } else if ctx.flags & flags::SCROLL_CONTENT != 0 {
-                if let Some(Ok(parent_bbox)) = tree.parent(id).map(|pid| tree.layout(pid)) {
-                    if layout.content_size.height > parent_bbox.size.height {
-                        abs_pos.y -= lerp(
-                            0.0,
-                            layout.content_size.height - parent_bbox.size.height,
-                            ctx.offset.y,
-                        );
-                    }
-                }
-            } else {
-                abs_pos = abs_pos + ctx.offset.into();
-            }
+            let abs_pos = Self::node_position(tree, id, layout, ctx, parent_pos);
 
             // Traverse trail to find parent
             while trail.last().is_some() && tree.parent(id) != trail.last().map(|x| x.0) {
@@ -714,13 +1039,22 @@ where
                     x1: Into::<Vector<f32>>::into(abs_pos) + layout.size.into(),
                 };
                 if abs_bbox.contains(self.mouse_pos) {
+                    if let Some(tooltip) = &ctx.tooltip
+                        && layer_idx >= self.mouse_hit_layer
+                        && self
+                            .tooltip_candidate
+                            .as_ref()
+                            .is_none_or(|(_, layer)| layer_idx >= *layer)
+                    {
+                        self.tooltip_candidate = Some((tooltip.clone(), layer_idx));
+                    }
                     if let Some(on_mouse_down) = &ctx.on_left_mouse_down
                         && self.mouse_left_down
                         && !self.mouse_left_was_down
                         && layer_idx >= self.mouse_hit_layer
                     {
                         self.pending_event_listeners.push(on_mouse_down.clone());
-                        self.mouse_hit_layer = layer_idx;
+                        self.register_hit(id, layer_idx);
                     }
                     if let Some(on_mouse_up) = &ctx.on_left_mouse_up
                         && !self.mouse_left_down
@@ -728,6 +1062,7 @@ where
                         && layer_idx >= self.mouse_hit_layer
                     {
                         self.pending_event_listeners.push(on_mouse_up.clone());
+                        self.last_hit_node = Some(id);
                     }
                     if let Some(on_mouse_down) = &ctx.on_right_mouse_down
                         && self.mouse_right_down
@@ -735,7 +1070,7 @@ where
                         && layer_idx >= self.mouse_hit_layer
                     {
                         self.pending_event_listeners.push(on_mouse_down.clone());
-                        self.mouse_hit_layer = layer_idx;
+                        self.register_hit(id, layer_idx);
                     }
                     if let Some(on_mouse_up) = &ctx.on_right_mouse_up
                         && !self.mouse_right_down
@@ -743,6 +1078,7 @@ where
                         && layer_idx >= self.mouse_hit_layer
                     {
                         self.pending_event_listeners.push(on_mouse_up.clone());
+                        self.last_hit_node = Some(id);
                     }
                     if let Some(on_mouse_down) = &ctx.on_middle_mouse_down
                         && self.mouse_middle_down
@@ -750,7 +1086,7 @@ where
                         && layer_idx >= self.mouse_hit_layer
                     {
                         self.pending_event_listeners.push(on_mouse_down.clone());
-                        self.mouse_hit_layer = layer_idx;
+                        self.register_hit(id, layer_idx);
                     }
                     if let Some(on_mouse_up) = &ctx.on_middle_mouse_up
                         && !self.mouse_middle_down
@@ -758,6 +1094,23 @@ where
                         && layer_idx >= self.mouse_hit_layer
                     {
                         self.pending_event_listeners.push(on_mouse_up.clone());
+                        self.last_hit_node = Some(id);
+                    }
+                    if ctx.flags & flags::FOCUSABLE != 0
+                        && let Some(pid) = &ctx.persistent_id
+                        && self.mouse_left_down
+                        && !self.mouse_left_was_down
+                        && layer_idx >= self.mouse_hit_layer
+                    {
+                        self.focus_candidate = Some(pid.clone());
+                        self.register_hit(id, layer_idx);
+                    }
+                    if let Some(on_double_click) = &ctx.on_double_click
+                        && self.mouse_double_clicked
+                        && layer_idx >= self.mouse_hit_layer
+                    {
+                        self.pending_event_listeners.push(on_double_click.clone());
+                        self.register_hit(id, layer_idx);
                     }
                     if let Some(on_scroll) = &ctx.on_scroll
                         && (self.scroll_delta.x.abs() > 0.01 || self.scroll_delta.y.abs() > 0.01)
@@ -765,24 +1118,23 @@ where
                         && layer_idx >= self.mouse_hit_layer
                     {
                         self.pending_event_listeners.push(on_scroll.clone());
-                        self.mouse_hit_layer = layer_idx;
+                        self.register_hit(id, layer_idx);
                     }
                     if let Some(on_move) = &ctx.on_mouse_move
                         && self.mouse_pos != self.last_mouse_pos
                     {
                         self.pending_event_listeners.push(on_move.clone());
-                        self.mouse_hit_layer = layer_idx;
+                        self.register_hit(id, layer_idx);
                     }
 
-                    // Even if no event listener is registered, an element with text, an icon or a
-                    // background colour should occlude anything behind it. Eventually all this should
-                    // probably be replaced a proper rounded rectangle-drawing picker buffer similar to
-                    // how sketch objects are picked.
-                    if (ctx.bg_color.a != 0.0
-                        || (ctx.flags & (flags::TEXT | flags::SPRITE | flags::HOVER_BG) != 0))
+                    // Even if none of its event listeners fired, an element with text, an icon, a
+                    // background colour or any listener should occlude anything behind it. Eventually
+                    // all this should probably be replaced a proper rounded rectangle-drawing picker
+                    // buffer similar to how sketch objects are picked.
+                    if ctx.occludes(abs_bbox, current_scissor, self.mouse_pos)
                         && layer_idx >= self.mouse_hit_layer
                     {
-                        self.mouse_hit_layer = layer_idx;
+                        self.register_hit(id, layer_idx);
                     }
                 }
 
@@ -802,7 +1154,6 @@ where
                         if let Some(on_mouse_enter) = &ctx.on_mouse_enter {
                             self.pending_event_listeners.push(on_mouse_enter.clone());
                         }
-                        self.mouse_hit_layer = layer_idx;
                     } else if !is_hovered && was_hovered {
                         // Mouse exited
                         self.hover_states.insert(id, false);
@@ -830,58 +1181,42 @@ where
         root_node: NodeId,
         position: Vector<f32>,
     ) -> taffy::TaffyResult<()> {
-        let mut to_render: Vec<(NodeId, taffy::Point<f32>)> = vec![(root_node, position.into())];
-        // The trail acts as a scissor stack. It allows the program to restore an outer scissor
-        // state before delving into the rendering of additional descendant nodes
-        let mut trail: Vec<(NodeId, Option<(Vector<f32>, Vector<f32>)>)> = vec![];
-
-        while let Some((id, parent_pos)) = to_render.pop() {
+        // Each node is paired with the opacity inherited from its ancestors
+        let mut to_render: Vec<(NodeId, taffy::Point<f32>, f32)> =
+            vec![(root_node, position.into(), 1.0)];
+        // The trail holds the ancestors of the current node and the scissor region each of them
+        // pushed, if any. Leaving a node pops its region, restoring the clip of its parent.
+        let mut trail: Vec<(NodeId, Option<crate::geometry::Rect<f32>>)> = vec![];
+        // Outlines are drawn once the whole tree is done so that later siblings can't cover them.
+        // Each one is clipped by the regions of its ancestors, but not by the node's own region.
+        let mut outlines = vec![];
+        // Backgrounds are collected into as few draw calls as possible. Anything else drawn on top
+        // of them has to break the batch first.
+        self.rect_r.begin_batch();
+
+        while let Some((id, parent_pos, parent_opacity)) = to_render.pop() {
             let layout = tree.layout(id)?;
-            let mut abs_pos = layout.location + parent_pos;
             let default_ctx = &NodeContext::default();
             let ctx = tree.get_node_context(id).unwrap_or(default_ctx);
+            // Use hover_states hashmap to determine hover, respecting layer occlusion
+            let is_hovered = self.hover_states.get(&id).copied().unwrap_or(false);
+            let background = if (ctx.flags & flags::HOVER_BG != 0) && is_hovered {
+                Background::Solid(ctx.bg_color_hover)
+            } else {
+                ctx.background
+            };
+            let (background, node_opacity) = self.apply_transition(ctx, background, ctx.opacity);
+            let opacity = parent_opacity * node_opacity;
             // If the last node of the trail isn't our parent, we should traverse the trail upwards
             // until we find our parent. This ensures that scissoring is applied to all children of
             // a node, while not affecting any other nodes in other places of the tree.
             while trail.last().is_some() && tree.parent(id) != trail.last().map(|x| x.0) {
-                trail.pop();
-            }
-            self.disable_scissor();
-            // After ensuring that the last node is my parent, find the nearest scissor region if
-            // there are any
-            if ctx.scissor {
-                trail.push((id, Some((abs_pos.into(), layout.size.into()))));
-                self.enable_scissor_for_layer(abs_pos.into(), layout.size.into());
-            } else {
-                if let Some((_, Some(scissor))) =
-                    trail.iter().rev().find(|(_, scissor)| scissor.is_some())
-                {
-                    self.enable_scissor_for_layer(scissor.0, scissor.1);
+                if let Some((_, Some(_))) = trail.pop() {
+                    self.pop_scissor();
                 }
-                trail.push((id, None));
-            }
-
-            if ctx.flags & flags::SCROLL_BAR != 0 {
-                if let Some(parent_bbox) = tree.parent(id).map(|pid| tree.layout(pid).unwrap()) {
-                    abs_pos.y += lerp(
-                        0.0,
-                        parent_bbox.size.height - layout.size.height,
-                        ctx.offset.y,
-                    );
-                }
-            } else if ctx.flags & flags::SCROLL_CONTENT != 0 {
-                if let Some(Ok(parent_bbox)) = tree.parent(id).map(|pid| tree.layout(pid)) {
-                    if layout.content_size.height > parent_bbox.size.height {
-                        abs_pos.y -= lerp(
-                            0.0,
-                            layout.content_size.height - parent_bbox.size.height,
-                            ctx.offset.y,
-                        );
-                    }
-                }
-            } else {
-                abs_pos = abs_pos + ctx.offset.into();
             }
+            // Clip to where the node is drawn, after scrolling and offsets are applied
+            let abs_pos = Self::node_position(tree, id, layout, ctx, parent_pos);
             let bbox = crate::geometry::Rect {
                 x0: Vector::new(abs_pos.x, abs_pos.y),
                 x1: Vector::new(
@@ -889,29 +1224,49 @@ where
                     abs_pos.y + layout.size.height,
                 ),
             };
-            // Use hover_states hashmap to determine hover, respecting layer occlusion
-            let is_hovered = self.hover_states.get(&id).copied().unwrap_or(false);
-            let bg_color = if (ctx.flags & flags::HOVER_BG != 0) && is_hovered {
-                ctx.bg_color_hover
-            } else {
-                ctx.bg_color
-            };
-
+            if let Some(outline) = ctx.outline {
+                let outline = Outline {
+                    color: outline.color.faded(opacity),
+                    ..outline
+                };
+                let clips: Vec<_> = trail.iter().filter_map(|(_, clip)| *clip).collect();
+                outlines.push((bbox, outline, ctx.border.radius, clips));
+            }
+            // The shadow lies outside the node, so it is drawn before the node's own clip is pushed
+            if let Some(shadow) = ctx.shadow {
+                let shadow = Shadow {
+                    color: shadow.color.faded(opacity),
+                    ..shadow
+                };
+                self.rect_r.break_batch();
+                self.rect_r.draw_shadow(bbox, shadow, ctx.border.radius);
+            }
             if ctx.scissor {
-                self.enable_scissor_for_layer(abs_pos.into(), layout.size.into());
+                self.push_scissor(abs_pos.into(), layout.size.into());
             }
+            trail.push((id, ctx.scissor.then_some(bbox)));
+
+            let background = background.faded(opacity);
+            let border = Border {
+                color: ctx.border.color.faded(opacity),
+                ..ctx.border
+            };
 
             // Drawing
-            self.rect_r.draw(bbox, bg_color, ctx.border, 1.0);
+            self.rect_r.push(bbox, background, border, 1.0);
 
             if let Some(pid) = &ctx.persistent_id
                 && let Some(pstate) = self.ui_builder.accessing_state(pid)
             {
+                self.rect_r.break_batch();
                 let data = pstate.data.lock().unwrap();
                 data.custom_render(&id, ctx, layout, self, bbox);
             }
 
             if ctx.flags & flags::TEXT != 0 {
+                self.rect_r.break_batch();
+                let mut text = ctx.text.clone();
+                text.color = text.color.faded(opacity);
                 let mut text_pos = Vector::new(
                     abs_pos.x + layout.padding.left,
                     abs_pos.y + layout.padding.top,
@@ -920,13 +1275,17 @@ where
                     width: layout.size.width - layout.padding.left - layout.padding.right,
                     height: layout.size.height - layout.padding.top - layout.padding.bottom,
                 };
+                let mut text_scissor = false;
                 if ctx.flags & flags::TEXT_SCROLL != 0 {
                     if let Some(Ok(parent_layout)) = tree.parent(id).map(|pid| tree.layout(pid)) {
-                        self.enable_scissor_for_layer(parent_pos.into(), parent_layout.size.into());
+                        self.push_scissor(parent_pos.into(), parent_layout.size.into());
+                        text_scissor = true;
                         if let Some(cursor_idx) = ctx.cursor_idx {
+                            let font_id = self.text_r.font_id(ctx.text.font.as_ref());
                             let cursor_pos = self.text_r.cursor_pos(
                                 &ctx.text.text,
                                 Vector::zero(),
+                                font_id,
                                 ctx.text.font_size,
                                 cursor_idx,
                             );
@@ -937,59 +1296,207 @@ where
                     }
                 }
                 if ctx.flags & flags::EXPLICIT_TEXT_LAYOUT != 0 {
-                    self.text_r.draw_in_box_explicit(
-                        ctx.text.clone(),
-                        text_pos,
-                        text_size,
-                        ctx.cursor_idx,
-                    );
+                    self.text_r
+                        .draw_in_box_explicit(text, text_pos, text_size, ctx.cursor_idx);
                 } else {
                     if ctx.flags & flags::TEXT_SINGLE_LINE != 0 {
-                        self.text_r.draw_on_line(
-                            ctx.text.clone(),
-                            text_pos,
-                            text_size,
-                            ctx.cursor_idx,
-                        );
+                        self.text_r
+                            .draw_on_line(text, text_pos, text_size, ctx.cursor_idx);
                     } else {
-                        self.text_r.draw_in_box(
-                            ctx.text.clone(),
-                            text_pos,
-                            text_size,
-                            ctx.cursor_idx,
-                        );
+                        self.text_r
+                            .draw_in_box(text, text_pos, text_size, ctx.cursor_idx);
                     }
                 }
+                if text_scissor {
+                    self.pop_scissor();
+                }
             }
             if ctx.flags & flags::SPRITE != 0 {
-                self.sprite_r.draw(
-                    &ctx.sprite_key,
-                    crate::geometry::Rect {
-                        x0: Vector::new(
-                            abs_pos.x + layout.padding.left,
-                            abs_pos.y + layout.padding.top,
-                        ),
-                        x1: Vector::new(
-                            abs_pos.x - layout.padding.left + layout.size.width,
-                            abs_pos.y - layout.padding.top + layout.size.height,
-                        ),
-                    },
-                );
+                self.rect_r.break_batch();
+                let location = crate::geometry::Rect {
+                    x0: Vector::new(
+                        abs_pos.x + layout.padding.left,
+                        abs_pos.y + layout.padding.top,
+                    ),
+                    x1: Vector::new(
+                        abs_pos.x - layout.padding.left + layout.size.width,
+                        abs_pos.y - layout.padding.top + layout.size.height,
+                    ),
+                };
+                let tint = ctx.sprite_tint.faded(opacity);
+                // Sprites with insets in the atlas legend are skinned panels that should stretch
+                // without distorting their corners
+                match self.sprite_r.insets(&ctx.sprite_key) {
+                    Some(insets) => {
+                        self.sprite_r
+                            .draw_nine_patch(&ctx.sprite_key, location, insets, tint)
+                    }
+                    None => self.sprite_r.draw_tinted(&ctx.sprite_key, location, tint),
+                }
             }
 
             for child in tree.children(id)?.iter().rev() {
-                to_render.push((*child, abs_pos));
+                to_render.push((*child, abs_pos, opacity));
+            }
+        }
+
+        for (_, clip) in trail {
+            if clip.is_some() {
+                self.pop_scissor();
             }
         }
+        self.rect_r.flush();
 
-        self.disable_scissor();
+        for (bbox, outline, radius, clips) in outlines {
+            for clip in &clips {
+                self.push_scissor(clip.x0, clip.size());
+            }
+            self.rect_r.draw_outline(bbox, outline, radius);
+            for _ in &clips {
+                self.pop_scissor();
+            }
+        }
 
         Ok(())
     }
 
+    /// Eases the background and opacity of a node with a [Transition] towards their targets for
+    /// this frame. The progress is kept in the [UiBuilder] state map under the node's persistent id
+    fn apply_transition(
+        &mut self,
+        ctx: &NodeContext<T>,
+        background: Background,
+        opacity: f32,
+    ) -> (Background, f32) {
+        let Some(pid) = &ctx.persistent_id else {
+            return (background, opacity);
+        };
+        if ctx.transition.property.is_none() {
+            return (background, opacity);
+        }
+        let key = DefaultAtom::from(format!("{pid}#transition"));
+        let state = match self.ui_builder.accessing_state(&key) {
+            Some(state) => state,
+            None => self
+                .ui_builder
+                .insert_state(key, TransitionState::default()),
+        };
+        let mut data = state.data.lock().unwrap();
+        let Some(transition_state) = data.as_mut().downcast_mut::<TransitionState>() else {
+            return (background, opacity);
+        };
+        let transition = &ctx.transition;
+        let background = if transition.animates_colors() {
+            transition_state.background(background, self.frame_start, transition)
+        } else {
+            background
+        };
+        let opacity = if transition.animates_opacity() {
+            transition_state.opacity(opacity, self.frame_start, transition)
+        } else {
+            opacity
+        };
+        if transition_state.is_animating(self.frame_start, transition) {
+            self.dirty = true;
+        }
+        (background, opacity)
+    }
+
+    /// Draws the current layers into a new RGBA texture of the renderer's size instead of the
+    /// window. The caller owns the returned texture and is responsible for deleting it.
+    pub fn render_to_texture(&mut self) -> GLuint {
+        let (texture, framebuffer) = self.render_offscreen();
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::DeleteFramebuffers(1, &framebuffer);
+        }
+        texture
+    }
+
+    /// Renders offscreen like [Renderer::render_to_texture] and reads the result back as tightly
+    /// packed RGBA bytes, top row first.
+    pub fn render_to_rgba(&mut self) -> Vec<u8> {
+        // GLES 3.0 has no glGetTexImage, so the texture is read through its framebuffer instead
+        let (texture, framebuffer) = self.render_offscreen();
+        let image = self.capture_frame();
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::DeleteFramebuffers(1, &framebuffer);
+            gl::DeleteTextures(1, &texture);
+        }
+        image.into_raw()
+    }
+
+    /// Draws the current layers into a new texture attached to a new framebuffer. Returns both,
+    /// with the framebuffer still bound.
+    fn render_offscreen(&mut self) -> (GLuint, GLuint) {
+        let mut texture: GLuint = 0;
+        let mut framebuffer: GLuint = 0;
+        unsafe {
+            gl::GenTextures(1, &mut texture);
+            gl::BindTexture(gl::TEXTURE_2D, texture);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA8 as i32,
+                self.width as i32,
+                self.height as i32,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                std::ptr::null(),
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+
+            gl::GenFramebuffers(1, &mut framebuffer);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                texture,
+                0,
+            );
+            if gl::CheckFramebufferStatus(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
+                error!("Offscreen framebuffer is incomplete");
+            }
+            gl::Viewport(0, 0, self.width as i32, self.height as i32);
+            gl::ClearColor(0.0, 0.0, 0.0, 0.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+        }
+
+        self.render();
+        (texture, framebuffer)
+    }
+
+    /// Reads back the currently bound framebuffer. Call it after [Renderer::render] but before
+    /// swapping buffers to capture what is about to be presented.
+    pub fn capture_frame(&self) -> RgbaImage {
+        let mut pixels = vec![0u8; self.width as usize * self.height as usize * 4];
+        unsafe {
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(
+                0,
+                0,
+                self.width as i32,
+                self.height as i32,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_mut_ptr() as *mut _,
+            );
+        }
+        let mut image = RgbaImage::from_raw(self.width, self.height, pixels)
+            .expect("Pixel buffer matches the frame size");
+        // OpenGL stores the bottom row first
+        image::imageops::flip_vertical_in_place(&mut image);
+        image
+    }
+
     pub fn window_size(&mut self, size: (i32, i32)) {
         self.width = size.0 as u32;
         self.height = size.1 as u32;
+        self.dirty = true;
     }
 
     fn debug_layer(&self) -> RenderLayout<T> {
@@ -1035,10 +1542,9 @@ where
             ])
         }
 
-        let root = b.div(
-            "rounded-8 bg-black opacity-40 w-full h-full p-8 flex-col",
-            &children,
-        );
+        let root = b.div("rounded-8 bg-black w-full h-full p-8 flex-col", &children);
+        // Only the background should be see-through, the text has to stay readable
+        b.mutate_context(root, |ctx| ctx.background.set_alpha(0.4));
 
         RenderLayout {
             tree: b.tree(),
@@ -1055,11 +1561,133 @@ where
             root_pos: self.debug_position.into(),
             anchor: Anchor::TopRight,
             scissor: true,
+            z: i32::MAX,
+        }
+    }
+
+    /// Opens a menu with its top left corner at `position`, replacing any open menu
+    pub fn open_context_menu(
+        &mut self,
+        position: Vector<f32>,
+        items: Vec<(String, EventListener<T>)>,
+    ) {
+        self.context_menu = Some(ContextMenu::new(position, items));
+        self.dirty = true;
+    }
+
+    pub fn close_context_menu(&mut self) {
+        self.context_menu = None;
+        self.dirty = true;
+    }
+
+    /// Lays out the open context menu, moved to fit on screen. Pressing a mouse button outside
+    /// of the menu closes it and lets the press through to whatever is below.
+    fn context_menu_layer(&mut self) -> Option<RenderLayout<T>> {
+        let menu = self.context_menu.as_ref()?;
+        let b = &self.ui_builder;
+        let root = menu.build(b);
+        let mut layer = RenderLayout {
+            tree: b.tree(),
+            delayed_markers: b.delayed_ids(),
+            root,
+            desired_size: Size {
+                width: AvailableSpace::MaxContent,
+                height: AvailableSpace::MaxContent,
+            },
+            root_pos: menu.position,
+            anchor: Anchor::TopLeft,
+            scissor: false,
+            z: i32::MAX,
+        };
+        layer
+            .tree
+            .compute_layout_with_measure(
+                layer.root,
+                layer.desired_size,
+                |known_dimensions, available_space, _node_id, node_context, _style| {
+                    measure_function(
+                        known_dimensions,
+                        available_space,
+                        node_context,
+                        &mut self.text_r,
+                    )
+                },
+            )
+            .unwrap();
+        let size: Vector<f32> = layer.tree.layout(layer.root).unwrap().size.into();
+        let window_size = self.logical_size();
+        layer.root_pos.x = layer.root_pos.x.min(window_size.x - size.x).max(0.0);
+        layer.root_pos.y = layer.root_pos.y.min(window_size.y - size.y).max(0.0);
+
+        let bbox = crate::geometry::Rect {
+            x0: layer.root_pos,
+            x1: layer.root_pos + size,
+        };
+        let pressed = (self.mouse_left_down && !self.mouse_left_was_down)
+            || (self.mouse_right_down && !self.mouse_right_was_down)
+            || (self.mouse_middle_down && !self.mouse_middle_was_down);
+        if pressed && !bbox.contains(self.mouse_pos) {
+            self.close_context_menu();
+            return None;
+        }
+        Some(layer)
+    }
+
+    /// A small box with `text` just below and to the right of `position`
+    fn tooltip_layer(&self, text: &str, position: Vector<f32>) -> RenderLayout<T> {
+        let b = &self.ui_builder;
+        let root = b.text(
+            "rounded-4 bg-zinc-800 px-6 py-4",
+            Text::new(text, 12, COLOR_LIGHT),
+        );
+        RenderLayout {
+            tree: b.tree(),
+            delayed_markers: b.delayed_ids(),
+            root,
+            desired_size: Size {
+                width: AvailableSpace::MaxContent,
+                height: AvailableSpace::MaxContent,
+            },
+            root_pos: position + Vector::new(12.0, 16.0),
+            anchor: Anchor::TopLeft,
+            scissor: false,
+            z: i32::MAX,
         }
     }
 
     pub fn set_focus(&mut self, focus: Option<DefaultAtom>) {
+        self.focused = focus.clone();
         self.app_state.set_focus(focus);
+        self.dirty = true;
+    }
+
+    /// Moves focus to the next focusable node in document order, or the previous one if
+    /// `backwards` is set. Returns false if there is nothing to focus.
+    pub fn focus_next(&mut self, backwards: bool) -> bool {
+        let focusable: Vec<DefaultAtom> = self
+            .layers
+            .iter()
+            .flat_map(|layer| focusable_nodes(&layer.tree, layer.root))
+            .collect();
+        match next_focus(&focusable, self.focused.as_ref(), backwards) {
+            Some(next) => {
+                self.set_focus(Some(next));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Finds the context of the focused node in the most recently computed layout
+    fn focused_context(&self) -> Option<&NodeContext<T>> {
+        let focused = self.focused.as_ref()?;
+        let node_id = self.ui_builder.node_id(focused)?;
+        self.layers.iter().find_map(|layer| {
+            layer
+                .tree
+                .get_node_context(node_id)
+                .filter(|ctx| ctx.persistent_id.as_ref() == Some(focused))
+        })
     }
 
     /// Get the computed layout for a node by searching through all layers
@@ -1072,6 +1700,87 @@ where
         None
     }
 
+    /// Position of a node on screen given the position of its parent, following scroll bars,
+    /// scrolled content and [NodeContext::offset]
+    fn node_position(
+        tree: &TaffyTree<NodeContext<T>>,
+        id: NodeId,
+        layout: &Layout,
+        ctx: &NodeContext<T>,
+        parent_pos: taffy::Point<f32>,
+    ) -> taffy::Point<f32> {
+        let mut abs_pos = layout.location + parent_pos;
+        if ctx.flags & flags::SCROLL_BAR != 0 {
+            if let Some(parent_bbox) = tree.parent(id).map(|pid| tree.layout(pid).unwrap()) {
+                abs_pos.y += lerp(
+                    0.0,
+                    parent_bbox.size.height - layout.size.height,
+                    ctx.offset.y,
+                );
+            }
+        } else if ctx.flags & flags::SCROLL_CONTENT != 0 {
+            if let Some(Ok(parent_bbox)) = tree.parent(id).map(|pid| tree.layout(pid)) {
+                if layout.content_size.height > parent_bbox.size.height {
+                    abs_pos.y -= lerp(
+                        0.0,
+                        layout.content_size.height - parent_bbox.size.height,
+                        ctx.offset.y,
+                    );
+                }
+            }
+        } else {
+            abs_pos = abs_pos + ctx.offset.into();
+        }
+        abs_pos
+    }
+
+    /// The node that captured the mouse during the last update, in the layer given by
+    /// [Self::mouse_hit_layer]
+    pub fn last_hit_node(&self) -> Option<NodeId> {
+        self.last_hit_node
+    }
+
+    /// Finds the node that would capture the mouse at `point` using the layout of the last
+    /// frame, checking layers from the top down. Every layer has its own tree, so the node
+    /// belongs to the topmost layer with anything under `point`. Popups attached with delayed
+    /// markers are not considered.
+    pub fn hit_test(&self, point: Vector<f32>) -> Option<NodeId> {
+        let window_size = self.logical_size();
+        for layer in self.layers.iter().rev() {
+            let Ok(root_layout) = layer.tree.layout(layer.root) else {
+                continue;
+            };
+            let pos = layer
+                .anchor
+                .position(layer.root_pos, root_layout.size.into(), window_size);
+            let layer_rect = crate::geometry::Rect::from_pos_size(pos, root_layout.size.into());
+            let mut hit = None;
+            // Each node is paired with its parents position and the nearest clipping rectangle
+            let mut to_visit = vec![(layer.root, pos.into(), layer.scissor.then_some(layer_rect))];
+            while let Some((id, parent_pos, clip)) = to_visit.pop() {
+                let Ok(layout) = layer.tree.layout(id) else {
+                    continue;
+                };
+                let default_ctx = &NodeContext::default();
+                let ctx = layer.tree.get_node_context(id).unwrap_or(default_ctx);
+                let abs_pos = Self::node_position(&layer.tree, id, layout, ctx, parent_pos);
+                let bbox = crate::geometry::Rect::from_pos_size(abs_pos.into(), layout.size.into());
+                let clip = if ctx.scissor { Some(bbox) } else { clip };
+
+                if ctx.occludes(bbox, clip, point) {
+                    hit = Some(id);
+                }
+                for child in layer.tree.children(id).into_iter().flatten().rev() {
+                    to_visit.push((child, abs_pos, clip));
+                }
+            }
+            if hit.is_some() {
+                return hit;
+            }
+        }
+        None
+    }
+
     /// Compute the absolute position for a node in a layer by walking up the parent chain.
     /// Returns None if the node is not found in the layer.
     fn compute_absolute_position(
@@ -1115,7 +1824,8 @@ where
     }
 }
 
-/// Helps taffy decide how big nodes containing text need to be.
+/// Helps taffy decide how big nodes containing text need to be. Text nodes without an explicit
+/// size report the size of their laid out text, wrapped to a known width if taffy has one.
 pub fn measure_function<T>(
     known_dimensions: taffy::geometry::Size<Option<f32>>,
     available_space: taffy::geometry::Size<taffy::style::AvailableSpace>,
@@ -1133,30 +1843,110 @@ where
         return Size { width, height };
     }
 
+    // A known width (e.g. from a stretched parent) has to be respected when wrapping
+    let available_space = Size {
+        width: known_dimensions
+            .width
+            .map_or(available_space.width, AvailableSpace::Definite),
+        height: available_space.height,
+    };
+
     if let Some(ctx) = node_context
-        && ctx.flags & flags::TEXT == 1
+        && ctx.flags & flags::TEXT != 0
     {
+        // Alignment only shifts lines within the available width, so measuring left aligned text
+        // avoids aligned text claiming all of it
+        let font_id = text_renderer.font_id(ctx.text.font.as_ref());
         if ctx.flags & flags::EXPLICIT_TEXT_LAYOUT == 0 {
             let lines = text_renderer.layout_text(
                 available_space,
                 ctx.text.text.clone(),
+                font_id,
                 ctx.text.font_size,
-                true,
+                ctx.text.line_height,
+                ctx.text.overflow,
+                TextAlignment::Left,
             );
-            total_size(&lines).into()
+            known_dimensions.unwrap_or(total_size(&lines).into())
         } else {
             let lines = text_renderer.layout_text_explicit(
                 available_space,
                 ctx.text.text.clone(),
+                font_id,
                 ctx.text.font_size,
+                ctx.text.line_height,
+                TextAlignment::Left,
             );
-            total_size(&lines).into()
+            known_dimensions.unwrap_or(total_size(&lines).into())
         }
     } else {
         Size::ZERO
     }
 }
 
+/// Whether Tab moves focus away from the focused node with the context `focused`. A node keeps
+/// Tab to itself by setting [flags::CAPTURES_TAB] and listening for keys.
+fn tab_moves_focus<T>(focused: Option<&NodeContext<T>>) -> bool
+where
+    T: AppState,
+{
+    focused.is_none_or(|ctx| ctx.flags & flags::CAPTURES_TAB == 0 || ctx.on_key.is_none())
+}
+
+/// Collects the persistent ids of all [flags::FOCUSABLE] nodes below `root` in document order
+pub fn focusable_nodes<T>(tree: &TaffyTree<NodeContext<T>>, root: NodeId) -> Vec<DefaultAtom>
+where
+    T: AppState,
+{
+    let mut out = vec![];
+    let mut to_visit = vec![root];
+    while let Some(id) = to_visit.pop() {
+        if let Some(ctx) = tree.get_node_context(id)
+            && ctx.flags & flags::FOCUSABLE != 0
+            && let Some(pid) = &ctx.persistent_id
+        {
+            out.push(pid.clone());
+        }
+        if let Ok(children) = tree.children(id) {
+            to_visit.extend(children.into_iter().rev());
+        }
+    }
+    out
+}
+
+/// Picks the node after (or before) `current` in `focusable`, wrapping around at the ends.
+/// Starts from the first (or last) node if nothing is focused.
+fn next_focus(
+    focusable: &[DefaultAtom],
+    current: Option<&DefaultAtom>,
+    backwards: bool,
+) -> Option<DefaultAtom> {
+    if focusable.is_empty() {
+        return None;
+    }
+    let len = focusable.len();
+    let idx = match current.and_then(|c| focusable.iter().position(|f| f == c)) {
+        Some(idx) if backwards => (idx + len - 1) % len,
+        Some(idx) => (idx + 1) % len,
+        None if backwards => len - 1,
+        None => 0,
+    };
+    Some(focusable[idx].clone())
+}
+
+/// Checks if a press at `pos` and `now` forms a double click together with the `last_click`
+fn is_double_click(
+    last_click: Option<(Instant, Vector<f32>)>,
+    now: Instant,
+    pos: Vector<f32>,
+    threshold: Duration,
+    tolerance: f32,
+) -> bool {
+    last_click.is_some_and(|(at, last_pos)| {
+        now.duration_since(at) <= threshold && (pos - last_pos).length() <= tolerance
+    })
+}
+
 /// Chooses a corner of the window or its center as the origin for a layer. Any offset provided
 /// from the anchor will be towards the middle of the screen, or towards the bottom right corner if
 /// anchored to the center.
@@ -1168,6 +1958,29 @@ pub enum Anchor {
     BottomLeft,
     BottomRight,
     Center,
+    /// Places the top left corner at a pixel coordinate. For layers this is relative to the
+    /// window, for delayed markers to the top left corner of the node they are attached to.
+    Custom(Vector<f32>),
+}
+
+impl Anchor {
+    /// Top left corner of a layer of `size` in a window of `window_size`. `root_pos` is an extra
+    /// offset from the anchor, pointing inwards from the corner the layer is anchored to.
+    pub fn position(
+        &self,
+        root_pos: Vector<f32>,
+        size: Vector<f32>,
+        window_size: Vector<f32>,
+    ) -> Vector<f32> {
+        match self {
+            Anchor::TopLeft => root_pos,
+            Anchor::TopRight => Vector::new(window_size.x - root_pos.x - size.x, root_pos.y),
+            Anchor::BottomLeft => Vector::new(root_pos.x, window_size.y - root_pos.y - size.y),
+            Anchor::BottomRight => window_size - root_pos - size,
+            Anchor::Center => (window_size - size).scaled(0.5) + root_pos,
+            Anchor::Custom(point) => *point + root_pos,
+        }
+    }
 }
 
 pub struct RenderLayout<T>
@@ -1181,6 +1994,9 @@ where
     pub anchor: Anchor,
     pub scissor: bool,
     pub delayed_markers: Vec<DelayedMarker>,
+    /// Layers are drawn from lowest to highest `z`, in the order they were returned from
+    /// [AppState::generate_layout] when equal
+    pub z: i32,
 }
 
 impl<T> Default for RenderLayout<T>
@@ -1196,6 +2012,7 @@ where
             anchor: Anchor::default(),
             scissor: false,
             delayed_markers: vec![],
+            z: 0,
         }
     }
 }
@@ -1239,10 +2056,15 @@ pub fn lerp(start: f32, end: f32, normalized: f32) -> f32 {
 #[cfg(test)]
 pub mod tests {
     use super::*;
-    use crate::render::{renderer::AppState, widgets::UiBuilder};
+    use crate::render::{renderer::AppState, sprite::SpriteAtlas, widgets::UiBuilder};
+    use crate::shader::Shader;
 
+    /// App state for tests that never lay out a frame. Shared with the other modules' tests
     #[derive(Default)]
-    struct DummyState {}
+    pub(crate) struct DummyState {
+        /// Lets tests record which of their callbacks ran
+        pub(crate) log: Vec<String>,
+    }
 
     impl AppState for DummyState {
         type SpriteKey = String;
@@ -1260,5 +2082,428 @@ pub mod tests {
     pub fn ui_shorthand_doesnt_deadlock() {
         let b = UiBuilder::<DummyState>::new();
         b.ui("", Listeners::default(), &[b.div("", &[]), b.div("", &[])]);
+
+        let node = b.ui("bg-red-500 p-8", Listeners::default(), &[] as &[NodeId]);
+        let tree = b.borrow_tree();
+        let ctx = tree.get_node_context(node).unwrap();
+        let red_500 = Color::new(239.0 / 255.0, 68.0 / 255.0, 68.0 / 255.0, 1.0);
+        assert_eq!(
+            ctx.background,
+            Background::Solid(red_500),
+            "The background color should be red-500"
+        );
+        assert_eq!(
+            tree.style(node).unwrap().padding,
+            taffy::Rect::length(8.0),
+            "The padding should be 8 on all sides"
+        );
+    }
+
+    #[test]
+    fn mutate_style_after_creation() {
+        let b = UiBuilder::<DummyState>::new();
+        let node = b.div("flex-row", &[]);
+        b.mutate_style(node, |style| style.flex_direction = FlexDirection::Column);
+        assert_eq!(
+            b.borrow_tree().style(node).unwrap().flex_direction,
+            FlexDirection::Column
+        );
+    }
+
+    #[test]
+    fn only_visible_or_interactive_nodes_capture_the_mouse() {
+        let b = UiBuilder::<DummyState>::new();
+        let empty = b.div("p-8", &[]);
+        let filled = b.div("bg-red-500", &[]);
+        let clickable = b.ui(
+            "",
+            Listeners {
+                on_left_mouse_up: Some(Arc::new(|_: &mut Renderer<DummyState>| {})),
+                ..Default::default()
+            },
+            &[] as &[NodeId],
+        );
+        let tree = b.borrow_tree();
+        assert!(!tree.get_node_context(empty).unwrap().captures_mouse());
+        assert!(tree.get_node_context(filled).unwrap().captures_mouse());
+        assert!(tree.get_node_context(clickable).unwrap().captures_mouse());
+    }
+
+    #[test]
+    fn custom_anchor_is_relative_to_the_window_origin() {
+        let size = Vector::new(100.0, 50.0);
+        let window = Vector::new(800.0, 600.0);
+        let offset = Vector::new(5.0, 5.0);
+        assert_eq!(
+            Anchor::Custom(Vector::new(300.0, 200.0)).position(offset, size, window),
+            Vector::new(305.0, 205.0)
+        );
+        assert_eq!(
+            Anchor::BottomRight.position(offset, size, window),
+            Vector::new(695.0, 545.0)
+        );
+    }
+
+    #[test]
+    fn context_menu_has_a_row_per_item() {
+        let b = UiBuilder::<DummyState>::new();
+        let noop: EventListener<DummyState> = Arc::new(|_| {});
+        let menu = ContextMenu::new(
+            Vector::new(10.0, 10.0),
+            vec![("Copy".into(), noop.clone()), ("Paste".into(), noop)],
+        );
+        let root = menu.build(&b);
+        let tree = b.borrow_tree();
+        assert_eq!(tree.children(root).unwrap().len(), 2);
+        assert!(tree.children(root).unwrap().iter().all(|row| {
+            tree.get_node_context(*row)
+                .is_some_and(|ctx| ctx.on_left_mouse_up.is_some())
+        }));
+    }
+
+    #[test]
+    fn double_click_requires_quick_nearby_presses() {
+        let threshold = Duration::from_millis(400);
+        let start = Instant::now();
+        let first = Some((start, Vector::new(10.0, 10.0)));
+
+        assert!(is_double_click(
+            first,
+            start + Duration::from_millis(200),
+            Vector::new(12.0, 11.0),
+            threshold,
+            4.0
+        ));
+        assert!(!is_double_click(
+            first,
+            start + Duration::from_millis(500),
+            Vector::new(10.0, 10.0),
+            threshold,
+            4.0
+        ));
+        assert!(!is_double_click(
+            first,
+            start + Duration::from_millis(200),
+            Vector::new(30.0, 10.0),
+            threshold,
+            4.0
+        ));
+        assert!(!is_double_click(
+            None,
+            start,
+            Vector::new(10.0, 10.0),
+            threshold,
+            4.0
+        ));
+    }
+
+    fn focusable(tree: &mut TaffyTree<NodeContext<DummyState>>, id: &str) -> NodeId {
+        let ctx = NodeContext {
+            flags: flags::FOCUSABLE,
+            persistent_id: Some(DefaultAtom::from(id)),
+            ..Default::default()
+        };
+        tree.new_leaf_with_context(Style::default(), ctx).unwrap()
+    }
+
+    #[test]
+    fn focusable_nodes_are_in_document_order() {
+        let mut tree = TaffyTree::<NodeContext<DummyState>>::new();
+        let a = focusable(&mut tree, "a");
+        let b = focusable(&mut tree, "b");
+        let c = focusable(&mut tree, "c");
+        let plain = tree.new_leaf(Style::default()).unwrap();
+        let inner = tree
+            .new_with_children(Style::default(), &[b, plain])
+            .unwrap();
+        let root = tree
+            .new_with_children(Style::default(), &[a, inner, c])
+            .unwrap();
+
+        let ids: Vec<DefaultAtom> = ["a", "b", "c"].into_iter().map(DefaultAtom::from).collect();
+        assert_eq!(focusable_nodes(&tree, root), ids);
+    }
+
+    #[test]
+    fn tab_is_kept_by_nodes_capturing_it() {
+        let listener: KeyListener<DummyState> = Arc::new(|_, _, _, _| true);
+        let plain = NodeContext::<DummyState> {
+            on_key: Some(listener.clone()),
+            ..Default::default()
+        };
+        let capturing = NodeContext::<DummyState> {
+            flags: flags::CAPTURES_TAB,
+            on_key: Some(listener),
+            ..Default::default()
+        };
+        let deaf = NodeContext::<DummyState> {
+            flags: flags::CAPTURES_TAB,
+            ..Default::default()
+        };
+
+        assert!(tab_moves_focus::<DummyState>(None));
+        assert!(tab_moves_focus(Some(&plain)));
+        assert!(!tab_moves_focus(Some(&capturing)));
+        assert!(
+            tab_moves_focus(Some(&deaf)),
+            "Tab can't be kept without a listener to receive it"
+        );
+    }
+
+    #[test]
+    fn next_focus_wraps_around() {
+        let ids: Vec<DefaultAtom> = ["a", "b", "c"].into_iter().map(DefaultAtom::from).collect();
+
+        assert_eq!(next_focus(&ids, None, false), Some(ids[0].clone()));
+        assert_eq!(next_focus(&ids, None, true), Some(ids[2].clone()));
+        assert_eq!(next_focus(&ids, Some(&ids[0]), false), Some(ids[1].clone()));
+        assert_eq!(next_focus(&ids, Some(&ids[2]), false), Some(ids[0].clone()));
+        assert_eq!(next_focus(&ids, Some(&ids[0]), true), Some(ids[2].clone()));
+        assert_eq!(next_focus(&[], None, false), None);
+    }
+
+    #[derive(Default)]
+    struct StaticState {}
+
+    impl AppState for StaticState {
+        type SpriteKey = String;
+
+        fn generate_layout(
+            &mut self,
+            window_size: crate::geometry::Vector<f32>,
+            ui: &UiBuilder<Self>,
+        ) -> Vec<RenderLayout<Self>> {
+            let root = ui.div("w-full h-full bg-zinc-800", &[] as &[NodeId]);
+            vec![RenderLayout {
+                tree: ui.tree(),
+                root,
+                desired_size: Size {
+                    width: AvailableSpace::Definite(window_size.x),
+                    height: AvailableSpace::Definite(window_size.y),
+                },
+                ..Default::default()
+            }]
+        }
+    }
+
+    #[derive(Default)]
+    struct BlocksState {}
+
+    impl AppState for BlocksState {
+        type SpriteKey = String;
+
+        fn generate_layout(
+            &mut self,
+            window_size: crate::geometry::Vector<f32>,
+            ui: &UiBuilder<Self>,
+        ) -> Vec<RenderLayout<Self>> {
+            let blocks: Vec<NodeId> = (0..20)
+                .map(|_| ui.div("w-4 h-4 bg-red-500", &[] as &[NodeId]))
+                .collect();
+            let root = ui.div("w-full h-full flex-row flex-wrap bg-zinc-800", &blocks);
+            vec![RenderLayout {
+                tree: ui.tree(),
+                root,
+                desired_size: Size {
+                    width: AvailableSpace::Definite(window_size.x),
+                    height: AvailableSpace::Definite(window_size.y),
+                },
+                ..Default::default()
+            }]
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a display to create an OpenGL context"]
+    fn backgrounds_are_batched() {
+        let (_glfw, _window) = crate::init_headless(200, 100);
+        let font = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../assets/fonts/LiberationMono.ttf"
+        );
+        let mut renderer = Renderer::new(
+            RectRenderer::new(Shader::empty()).with_batch_shader(Shader::empty()),
+            TextRenderer::new(Shader::empty(), std::path::Path::new(font)).unwrap(),
+            LineRenderer::new(Shader::empty()),
+            SpriteRenderer::new(Shader::empty(), SpriteAtlas::empty()),
+            GraphRenderer::new(Shader::empty(), Vector::new(200, 100)),
+            BlocksState::default(),
+        );
+
+        assert!(renderer.render_if_dirty());
+        let stats = renderer.frame_stats();
+        assert_eq!(stats.instances, 21, "The root and every block");
+        assert_eq!(stats.draw_calls, 1);
+    }
+
+    #[test]
+    #[ignore = "needs a display to create an OpenGL context"]
+    fn idle_frames_stay_clean() {
+        let (_glfw, _window) = crate::init_headless(200, 100);
+        let font = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../assets/fonts/LiberationMono.ttf"
+        );
+        let mut renderer = Renderer::new(
+            RectRenderer::new(Shader::empty()),
+            TextRenderer::new(Shader::empty(), std::path::Path::new(font)).unwrap(),
+            LineRenderer::new(Shader::empty()),
+            SpriteRenderer::new(Shader::empty(), SpriteAtlas::empty()),
+            GraphRenderer::new(Shader::empty(), Vector::new(200, 100)),
+            StaticState::default(),
+        );
+
+        assert!(
+            renderer.render_if_dirty(),
+            "The first frame is always drawn"
+        );
+        renderer.pre_update();
+        assert!(!renderer.render_if_dirty(), "Nothing changed without input");
+        assert!(!renderer.is_dirty());
+
+        renderer.pre_update();
+        renderer.handle_mouse_position(Vector::new(10.0, 10.0));
+        assert!(renderer.render_if_dirty());
+        renderer.pre_update();
+        assert!(!renderer.render_if_dirty());
+
+        renderer.mark_dirty();
+        assert!(renderer.render_if_dirty());
+    }
+
+    /// A small focusable field in the corner of the window which records every focus change
+    #[derive(Default)]
+    struct FieldState {
+        focus_changes: Vec<Option<DefaultAtom>>,
+    }
+
+    impl AppState for FieldState {
+        type SpriteKey = String;
+
+        fn generate_layout(
+            &mut self,
+            window_size: crate::geometry::Vector<f32>,
+            ui: &UiBuilder<Self>,
+        ) -> Vec<RenderLayout<Self>> {
+            let field = ui.div("w-8 h-8", &[] as &[NodeId]);
+            ui.mutate_context(field, |ctx| {
+                ctx.flags |= flags::FOCUSABLE;
+                ctx.persistent_id = Some(DefaultAtom::from("field"));
+            });
+            let root = ui.div("w-full h-full", [field]);
+            vec![RenderLayout {
+                tree: ui.tree(),
+                root,
+                desired_size: Size {
+                    width: AvailableSpace::Definite(window_size.x),
+                    height: AvailableSpace::Definite(window_size.y),
+                },
+                ..Default::default()
+            }]
+        }
+
+        fn set_focus(&mut self, focus: Option<DefaultAtom>) {
+            self.focus_changes.push(focus);
+        }
+    }
+
+    fn click<S: AppState>(renderer: &mut Renderer<S>, pos: Vector<f32>) {
+        renderer.pre_update();
+        renderer.handle_mouse_position(pos);
+        renderer.handle_mouse_button(MouseButton::Button1, Action::Press, Modifiers::empty());
+        renderer.update();
+        renderer.pre_update();
+        renderer.handle_mouse_button(MouseButton::Button1, Action::Release, Modifiers::empty());
+        renderer.update();
+    }
+
+    #[test]
+    #[ignore = "needs a display to create an OpenGL context"]
+    fn clicks_only_report_focus_changes() {
+        let (_glfw, _window) = crate::init_headless(200, 100);
+        let font = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../assets/fonts/LiberationMono.ttf"
+        );
+        let mut renderer = Renderer::new(
+            RectRenderer::new(Shader::empty()),
+            TextRenderer::new(Shader::empty(), std::path::Path::new(font)).unwrap(),
+            LineRenderer::new(Shader::empty()),
+            SpriteRenderer::new(Shader::empty(), SpriteAtlas::empty()),
+            GraphRenderer::new(Shader::empty(), Vector::new(200, 100)),
+            FieldState::default(),
+        );
+
+        let field = Some(DefaultAtom::from("field"));
+        click(&mut renderer, Vector::new(10.0, 10.0));
+        click(&mut renderer, Vector::new(10.0, 10.0));
+        assert_eq!(renderer.focused, field);
+        assert_eq!(
+            renderer.app_state.focus_changes,
+            vec![field.clone()],
+            "Clicking the focused field again doesn't change the focus"
+        );
+
+        click(&mut renderer, Vector::new(150.0, 50.0));
+        assert_eq!(renderer.focused, None);
+        assert_eq!(renderer.app_state.focus_changes, vec![field, None]);
+    }
+
+    /// A node that only listens for right clicks on top of a filled background
+    #[derive(Default)]
+    struct RightClickState {}
+
+    impl AppState for RightClickState {
+        type SpriteKey = String;
+
+        fn generate_layout(
+            &mut self,
+            window_size: crate::geometry::Vector<f32>,
+            ui: &UiBuilder<Self>,
+        ) -> Vec<RenderLayout<Self>> {
+            let target = ui.ui(
+                "w-8 h-8",
+                Listeners {
+                    on_right_mouse_up: Some(Arc::new(|_: &mut Renderer<Self>| {})),
+                    ..Default::default()
+                },
+                &[] as &[NodeId],
+            );
+            let root = ui.div("w-full h-full bg-zinc-800", [target]);
+            vec![RenderLayout {
+                tree: ui.tree(),
+                root,
+                desired_size: Size {
+                    width: AvailableSpace::Definite(window_size.x),
+                    height: AvailableSpace::Definite(window_size.y),
+                },
+                ..Default::default()
+            }]
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a display to create an OpenGL context"]
+    fn hit_test_agrees_with_event_dispatch() {
+        let (_glfw, _window) = crate::init_headless(200, 100);
+        let font = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../assets/fonts/LiberationMono.ttf"
+        );
+        let mut renderer = Renderer::new(
+            RectRenderer::new(Shader::empty()),
+            TextRenderer::new(Shader::empty(), std::path::Path::new(font)).unwrap(),
+            LineRenderer::new(Shader::empty()),
+            SpriteRenderer::new(Shader::empty(), SpriteAtlas::empty()),
+            GraphRenderer::new(Shader::empty(), Vector::new(200, 100)),
+            RightClickState::default(),
+        );
+
+        // A left click used to reach the background even though the hit test found the target
+        let pos = Vector::new(10.0, 10.0);
+        click(&mut renderer, pos);
+        let hit = renderer.hit_test(pos);
+        assert!(hit.is_some());
+        assert_eq!(renderer.last_hit_node(), hit);
     }
 }
@@ -5,11 +5,12 @@ use std::{
 use gl::types::GLuint;
 
 use anyhow::{Result, anyhow};
-use image::ImageReader;
+use image::{DynamicImage, ImageReader, RgbaImage};
 use tracing::error;
 
 use crate::{
     geometry::{Rect, Vector},
+    render::{Color, FrameCounter},
     shader::Shader,
 };
 
@@ -25,6 +26,54 @@ pub struct SpriteInstance {
     atlas_coords: [f32; 2],
     /// How big is it in the atlas
     atlas_size: [f32; 2],
+    /// Radians about the center of the sprite
+    rotation: f32,
+    /// -1 on an axis mirrors the sprite along it
+    flip: [f32; 2],
+}
+
+/// Rotation and mirroring applied to a sprite where it is drawn. Lets a single sprite be drawn
+/// facing several directions.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SpriteTransform {
+    /// Radians about the center of the sprite. Positive angles turn clockwise since the y-axis
+    /// points down
+    pub rotation: f32,
+    /// Mirrors the sprite left to right
+    pub flip_x: bool,
+    /// Mirrors the sprite top to bottom
+    pub flip_y: bool,
+}
+
+impl SpriteTransform {
+    pub fn rotated(rotation: f32) -> Self {
+        Self {
+            rotation,
+            ..Default::default()
+        }
+    }
+
+    /// Where a point of the sprite ends up on the screen when it is drawn into `location`. The
+    /// point is given relative to the sprite, from (0, 0) in the top left to (1, 1) in the bottom
+    /// right corner. Mirrors the vertex shader, flipping only changes which texels are sampled so
+    /// it doesn't move any points.
+    pub fn apply(&self, location: Rect<f32>, point: Vector<f32>) -> Vector<f32> {
+        let size = location.size();
+        let centered = Vector::new((point.x - 0.5) * size.x, (point.y - 0.5) * size.y);
+        let (s, c) = self.rotation.sin_cos();
+        let rotated = Vector::new(
+            c * centered.x - s * centered.y,
+            s * centered.x + c * centered.y,
+        );
+        location.center() + rotated
+    }
+
+    fn flip_scale(&self) -> [f32; 2] {
+        [
+            if self.flip_x { -1.0 } else { 1.0 },
+            if self.flip_y { -1.0 } else { 1.0 },
+        ]
+    }
 }
 
 pub trait SpriteKey:
@@ -32,17 +81,51 @@ pub trait SpriteKey:
 {
 }
 
+/// How an atlas is sampled when sprites are drawn at a different size than they have in it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TextureFilter {
+    /// Blends pixels when shrinking but not when enlarging, which keeps pixel art sharp
+    #[default]
+    PixelArt,
+    /// Never blends pixels
+    Nearest,
+    /// Blends pixels both ways so that high resolution icons scale smoothly
+    Linear,
+}
+
+impl TextureFilter {
+    /// The `(min, mag)` filters to pass to OpenGL
+    fn gl_filters(self) -> (i32, i32) {
+        match self {
+            TextureFilter::PixelArt => (gl::LINEAR as i32, gl::NEAREST as i32),
+            TextureFilter::Nearest => (gl::NEAREST as i32, gl::NEAREST as i32),
+            TextureFilter::Linear => (gl::LINEAR as i32, gl::LINEAR as i32),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct SpriteAtlas<K>
 where
     K: SpriteKey,
 {
     texture_id: GLuint,
+    /// Size of the atlas texture in pixels
+    size: Vector<f32>,
     map: HashMap<K, Rect<f32>>,
+    /// Pixel insets of sprites that should be drawn as nine-patches. See
+    /// [SpriteRenderer::draw_nine_patch]
+    insets: HashMap<K, Rect<f32>>,
+    filter: TextureFilter,
 }
 
+/// A sprite's name, its bounding box in the atlas and optional nine-patch insets
+type LegendEntry<K> = (K, Rect<f32>, Option<Rect<f32>>);
+
 impl<K: SpriteKey> SpriteAtlas<K> {
-    fn parse_legend(contents: &str) -> Result<Vec<(K, Rect<f32>)>> {
+    /// Each line holds a name followed by x, y, width and height. Four optional columns give the
+    /// left, top, right and bottom nine-patch insets of the sprite.
+    fn parse_legend(contents: &str) -> Result<Vec<LegendEntry<K>>> {
         let mut out = vec![];
         // Skip csv header, we know the layout
         for (i, l) in contents.lines().skip(1).enumerate() {
@@ -53,23 +136,28 @@ impl<K: SpriteKey> SpriteAtlas<K> {
                 parts[3].parse::<f32>()? + x0.x,
                 parts[4].parse::<f32>()? + x0.y,
             );
+            let insets = if parts.len() >= 9 {
+                Some(Rect {
+                    x0: Vector::new(parts[5].parse()?, parts[6].parse()?),
+                    x1: Vector::new(parts[7].parse()?, parts[8].parse()?),
+                })
+            } else {
+                None
+            };
 
             out.push((
                 K::from_str(parts[0])
                     .map_err(|_| anyhow!("Couldn't parse icon name on line {}: {}", i + 1, l))?,
                 Rect { x0, x1 },
+                insets,
             ));
         }
 
         Ok(out)
     }
 
-    /// The legend is a csv file containing the names and bounding boxes of the different textures
-    pub fn from_path(img_path: &Path, legend_path: &Path) -> Result<Self> {
-        let img = match ImageReader::open(img_path)?.decode()? {
-            image::DynamicImage::ImageRgba8(image_buffer) => image_buffer,
-            img => img.to_rgba8(),
-        };
+    /// Uploads an image to a new texture that sprites can be sampled from
+    fn create_texture(img: RgbaImage) -> GLuint {
         let atlas_size = Vector::new(img.dimensions().0, img.dimensions().1);
         let mut texture_id: GLuint = 0;
 
@@ -91,14 +179,51 @@ impl<K: SpriteKey> SpriteAtlas<K> {
                 img_ptr,
             );
 
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
             gl::BindTexture(gl::TEXTURE_2D, 0);
         }
+        Self::apply_filter(texture_id, TextureFilter::default());
+
+        texture_id
+    }
+
+    fn apply_filter(texture_id: GLuint, filter: TextureFilter) {
+        let (min, mag) = filter.gl_filters();
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, texture_id);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, min);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, mag);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+    }
+
+    /// Samples the atlas with `filter` instead of [TextureFilter::PixelArt]
+    pub fn with_filter(mut self, filter: TextureFilter) -> Self {
+        if self.texture_id != u32::MAX {
+            Self::apply_filter(self.texture_id, filter);
+        }
+        self.filter = filter;
+        self
+    }
+
+    pub fn filter(&self) -> TextureFilter {
+        self.filter
+    }
+
+    /// The legend is a csv file containing the names and bounding boxes of the different textures
+    pub fn from_path(img_path: &Path, legend_path: &Path) -> Result<Self> {
+        let img = match ImageReader::open(img_path)?.decode()? {
+            image::DynamicImage::ImageRgba8(image_buffer) => image_buffer,
+            img => img.to_rgba8(),
+        };
+        let atlas_size = Vector::new(img.dimensions().0 as f32, img.dimensions().1 as f32);
+        let texture_id = Self::create_texture(img);
 
-        let atlas_size = Vector::new(atlas_size.x as f32, atlas_size.y as f32);
         let mut map = HashMap::new();
-        for (key, location) in Self::parse_legend(&fs::read_to_string(legend_path)?)? {
+        let mut insets = HashMap::new();
+        for (key, location, key_insets) in Self::parse_legend(&fs::read_to_string(legend_path)?)? {
+            if let Some(key_insets) = key_insets {
+                insets.insert(key.clone(), key_insets);
+            }
             let normalized_rect = Rect {
                 x0: Vector::new(location.x0.x / atlas_size.x, location.x0.y / atlas_size.y),
                 x1: Vector::new(location.x1.x / atlas_size.x, location.x1.y / atlas_size.y),
@@ -106,13 +231,59 @@ impl<K: SpriteKey> SpriteAtlas<K> {
             map.insert(key, normalized_rect);
         }
 
-        Ok(Self { texture_id, map })
+        Ok(Self {
+            texture_id,
+            size: atlas_size,
+            map,
+            insets,
+            filter: TextureFilter::default(),
+        })
+    }
+
+    /// Packs separate images into a single atlas at runtime. Each image can then be drawn using
+    /// the key it is paired with.
+    pub fn from_images(images: &[(String, DynamicImage)]) -> Result<Self> {
+        if images.is_empty() {
+            return Err(anyhow!("Can't build a sprite atlas without any images"));
+        }
+        let sizes: Vec<(u32, u32)> = images
+            .iter()
+            .map(|(_, img)| (img.width(), img.height()))
+            .collect();
+        let (positions, atlas_size) = pack_shelves(&sizes);
+
+        let mut atlas = RgbaImage::new(atlas_size.x, atlas_size.y);
+        for ((_, img), (x, y)) in images.iter().zip(&positions) {
+            image::imageops::replace(&mut atlas, &img.to_rgba8(), *x as i64, *y as i64);
+        }
+        let texture_id = Self::create_texture(atlas);
+
+        let atlas_size = Vector::new(atlas_size.x as f32, atlas_size.y as f32);
+        let mut map = HashMap::new();
+        for (((key, _), (x, y)), (w, h)) in images.iter().zip(positions).zip(sizes) {
+            let normalized_rect = Rect {
+                x0: Vector::new(x as f32 / atlas_size.x, y as f32 / atlas_size.y),
+                x1: Vector::new((x + w) as f32 / atlas_size.x, (y + h) as f32 / atlas_size.y),
+            };
+            map.insert(K::from(key.as_str()), normalized_rect);
+        }
+
+        Ok(Self {
+            texture_id,
+            size: atlas_size,
+            map,
+            insets: HashMap::new(),
+            filter: TextureFilter::default(),
+        })
     }
 
     pub fn empty() -> Self {
         Self {
             texture_id: u32::MAX,
+            size: Vector::zero(),
             map: HashMap::new(),
+            insets: HashMap::new(),
+            filter: TextureFilter::default(),
         }
     }
 }
@@ -133,12 +304,14 @@ pub struct SpriteRenderer<K>
 where
     K: SpriteKey,
 {
-    shader: Shader,
+    pub(crate) shader: Shader,
     quad_vao: GLuint,
     quad_vbo: GLuint,
     /// Will eventually be used to draw all possible icons at once
     instance_vbo: GLuint,
     pub atlas: SpriteAtlas<K>,
+    /// Counts what is drawn, see [crate::render::renderer::Renderer::frame_stats]
+    pub(crate) frame_counter: FrameCounter,
 }
 
 impl<K: SpriteKey> SpriteRenderer<K> {
@@ -227,6 +400,26 @@ impl<K: SpriteKey> SpriteRenderer<K> {
                 (6 * std::mem::size_of::<f32>()) as *const c_void,
             );
             gl::VertexAttribDivisor(4, 1);
+            gl::EnableVertexAttribArray(5);
+            gl::VertexAttribPointer(
+                5,
+                1,
+                gl::FLOAT,
+                gl::FALSE,
+                std::mem::size_of::<SpriteInstance>() as i32,
+                (8 * std::mem::size_of::<f32>()) as *const c_void,
+            );
+            gl::VertexAttribDivisor(5, 1);
+            gl::EnableVertexAttribArray(6);
+            gl::VertexAttribPointer(
+                6,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                std::mem::size_of::<SpriteInstance>() as i32,
+                (9 * std::mem::size_of::<f32>()) as *const c_void,
+            );
+            gl::VertexAttribDivisor(6, 1);
             gl::BindBuffer(gl::ARRAY_BUFFER, 0);
             gl::BindVertexArray(0);
         }
@@ -237,10 +430,30 @@ impl<K: SpriteKey> SpriteRenderer<K> {
             quad_vbo,
             instance_vbo,
             atlas,
+            frame_counter: FrameCounter::default(),
         }
     }
 
     pub fn draw(&self, key: &K, location: Rect<f32>) {
+        self.draw_tinted(key, location, Color::new(1.0, 1.0, 1.0, 1.0));
+    }
+
+    /// Draws a sprite with each texel multiplied by `tint`, which lets one monochrome sprite be
+    /// drawn in any color
+    pub fn draw_tinted(&self, key: &K, location: Rect<f32>, tint: Color) {
+        self.draw_transformed(key, location, tint, SpriteTransform::default());
+    }
+
+    /// Same as [SpriteRenderer::draw_tinted] but rotates the sprite about the center of `location`
+    /// and mirrors it. The sprite keeps the size of `location`, so unless it is square a rotation
+    /// by anything but a half turn makes the sprite reach outside of `location`.
+    pub fn draw_transformed(
+        &self,
+        key: &K,
+        location: Rect<f32>,
+        tint: Color,
+        transform: SpriteTransform,
+    ) {
         if let Some(bbox) = self.atlas.map.get(key) {
             let instances = [SpriteInstance {
                 position: [(location.x0.x + 0.5).floor(), (location.x0.y + 0.5).floor()],
@@ -250,34 +463,74 @@ impl<K: SpriteKey> SpriteRenderer<K> {
                 ],
                 atlas_coords: [bbox.x0.x, bbox.x0.y],
                 atlas_size: [bbox.width(), bbox.height()],
+                rotation: transform.rotation,
+                flip: transform.flip_scale(),
             }];
+            self.draw_instances(&instances, tint);
+        } else {
+            self.unknown_key(key);
+        }
+    }
 
-            self.shader.use_shader();
-            self.shader.set_uniform("text", &0);
-            unsafe {
-                gl::ActiveTexture(gl::TEXTURE0);
-                gl::BindTexture(gl::TEXTURE_2D, self.atlas.texture_id);
-                gl::BindVertexArray(self.quad_vao);
-
-                gl::BindBuffer(gl::ARRAY_BUFFER, self.instance_vbo);
-                gl::BufferData(
-                    gl::ARRAY_BUFFER,
-                    (std::mem::size_of::<SpriteInstance>() * instances.len()) as isize,
-                    instances.as_ptr() as *const c_void,
-                    gl::DYNAMIC_DRAW,
-                );
-
-                gl::DrawArraysInstanced(gl::TRIANGLES, 0, 6, instances.len() as i32);
-
-                gl::BindVertexArray(0);
-                gl::BindTexture(gl::TEXTURE_2D, 0);
-            }
+    /// The nine-patch insets of a sprite if its atlas legend defined any
+    pub fn insets(&self, key: &K) -> Option<Rect<f32>> {
+        self.atlas.insets.get(key).copied()
+    }
+
+    /// Draws a sprite sliced into a 3x3 grid. The corners keep their size, the edges stretch along
+    /// one axis and the center stretches along both. `insets` holds the left and top insets in
+    /// `x0` and the right and bottom insets in `x1`, all in pixels of the atlas.
+    pub fn draw_nine_patch(&self, key: &K, location: Rect<f32>, insets: Rect<f32>, tint: Color) {
+        if let Some(bbox) = self.atlas.map.get(key) {
+            let instances: Vec<SpriteInstance> =
+                nine_patch_slices(location, *bbox, insets, self.atlas.size)
+                    .into_iter()
+                    .map(|(dest, uv)| SpriteInstance {
+                        position: [dest.x0.x, dest.x0.y],
+                        size: [dest.width(), dest.height()],
+                        atlas_coords: [uv.x0.x, uv.x0.y],
+                        atlas_size: [uv.width(), uv.height()],
+                        rotation: 0.0,
+                        flip: [1.0, 1.0],
+                    })
+                    .collect();
+            self.draw_instances(&instances, tint);
         } else {
-            error!(
-                "Unknown sprite key: {:?}, available keys are {:?}",
-                key,
-                self.atlas.map.keys().collect::<Vec<_>>()
+            self.unknown_key(key);
+        }
+    }
+
+    fn unknown_key(&self, key: &K) {
+        error!(
+            "Unknown sprite key: {:?}, available keys are {:?}",
+            key,
+            self.atlas.map.keys().collect::<Vec<_>>()
+        );
+    }
+
+    fn draw_instances(&self, instances: &[SpriteInstance], tint: Color) {
+        self.shader.use_shader();
+        self.shader.set_uniform("text", &0);
+        let tint_vec = glm::make_vec4(&[tint.r, tint.g, tint.b, tint.a]);
+        self.shader.set_uniform("tint", &tint_vec);
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.atlas.texture_id);
+            gl::BindVertexArray(self.quad_vao);
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.instance_vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                std::mem::size_of_val(instances) as isize,
+                instances.as_ptr() as *const c_void,
+                gl::DYNAMIC_DRAW,
             );
+
+            self.frame_counter.count_draw_call(2, instances.len());
+            gl::DrawArraysInstanced(gl::TRIANGLES, 0, 6, instances.len() as i32);
+
+            gl::BindVertexArray(0);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
         }
     }
 }
@@ -291,3 +544,168 @@ impl<K: SpriteKey> Drop for SpriteRenderer<K> {
         }
     }
 }
+
+/// Splits `dest` and the sprite's `uv` rect into the nine pieces of a nine-patch, skipping empty
+/// pieces. Corners are scaled down uniformly when `dest` is too small to fit them. Screen edges are
+/// rounded to whole pixels so that neighbouring pieces line up without gaps.
+fn nine_patch_slices(
+    dest: Rect<f32>,
+    uv: Rect<f32>,
+    insets: Rect<f32>,
+    atlas_size: Vector<f32>,
+) -> Vec<(Rect<f32>, Rect<f32>)> {
+    let fit = (dest.width() / (insets.x0.x + insets.x1.x))
+        .min(dest.height() / (insets.x0.y + insets.x1.y))
+        .min(1.0);
+    let xs = [
+        dest.x0.x,
+        dest.x0.x + insets.x0.x * fit,
+        dest.x1.x - insets.x1.x * fit,
+        dest.x1.x,
+    ]
+    .map(|x| (x + 0.5).floor());
+    let ys = [
+        dest.x0.y,
+        dest.x0.y + insets.x0.y * fit,
+        dest.x1.y - insets.x1.y * fit,
+        dest.x1.y,
+    ]
+    .map(|y| (y + 0.5).floor());
+    let us = [
+        uv.x0.x,
+        uv.x0.x + insets.x0.x / atlas_size.x,
+        uv.x1.x - insets.x1.x / atlas_size.x,
+        uv.x1.x,
+    ];
+    let vs = [
+        uv.x0.y,
+        uv.x0.y + insets.x0.y / atlas_size.y,
+        uv.x1.y - insets.x1.y / atlas_size.y,
+        uv.x1.y,
+    ];
+
+    let mut out = Vec::with_capacity(9);
+    for row in 0..3 {
+        for col in 0..3 {
+            if xs[col + 1] <= xs[col] || ys[row + 1] <= ys[row] {
+                continue;
+            }
+            out.push((
+                Rect::from_points(
+                    Vector::new(xs[col], ys[row]),
+                    Vector::new(xs[col + 1], ys[row + 1]),
+                ),
+                Rect::from_points(
+                    Vector::new(us[col], vs[row]),
+                    Vector::new(us[col + 1], vs[row + 1]),
+                ),
+            ));
+        }
+    }
+    out
+}
+
+/// Gap in pixels between packed sprites which stops neighbours from bleeding into each other when
+/// sampled with linear filtering
+const SPRITE_PADDING: u32 = 1;
+
+/// Places rectangles of the given sizes in rows ("shelves"), tallest first, and returns the
+/// top-left corner of each rectangle in the same order as `sizes` along with the total size. The
+/// atlas is roughly square unless a single rectangle is wider than that.
+fn pack_shelves(sizes: &[(u32, u32)]) -> (Vec<(u32, u32)>, Vector<u32>) {
+    let area: u32 = sizes
+        .iter()
+        .map(|(w, h)| (w + SPRITE_PADDING) * (h + SPRITE_PADDING))
+        .sum();
+    let widest = sizes.iter().map(|(w, _)| *w).max().unwrap_or(0);
+    let width = widest.max((area as f32).sqrt().ceil() as u32);
+
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by(|a, b| sizes[*b].1.cmp(&sizes[*a].1));
+
+    let mut positions = vec![(0, 0); sizes.len()];
+    let (mut x, mut y, mut shelf_height, mut used_width) = (0, 0, 0, 0);
+    for i in order {
+        let (w, h) = sizes[i];
+        if x > 0 && x + w > width {
+            x = 0;
+            y += shelf_height + SPRITE_PADDING;
+            shelf_height = 0;
+        }
+        positions[i] = (x, y);
+        used_width = used_width.max(x + w);
+        shelf_height = shelf_height.max(h);
+        x += w + SPRITE_PADDING;
+    }
+
+    (
+        positions,
+        Vector::new(used_width.max(1), (y + shelf_height).max(1)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SpriteTransform, nine_patch_slices, pack_shelves};
+    use crate::geometry::{Rect, Vector};
+
+    #[test]
+    fn packed_sprites_do_not_overlap() {
+        let sizes = [(16, 16), (32, 8), (8, 24), (16, 16), (4, 4)];
+        let (positions, size) = pack_shelves(&sizes);
+
+        for (i, (&(x, y), &(w, h))) in positions.iter().zip(&sizes).enumerate() {
+            assert!(
+                x + w <= size.x && y + h <= size.y,
+                "Sprite {i} is outside the atlas"
+            );
+            for (&(ox, oy), &(ow, oh)) in positions.iter().zip(&sizes).skip(i + 1) {
+                let apart = x + w <= ox || ox + ow <= x || y + h <= oy || oy + oh <= y;
+                assert!(apart, "Sprite {i} overlaps another sprite");
+            }
+        }
+    }
+
+    #[test]
+    fn nine_patch_keeps_corners_fixed() {
+        let dest = Rect::from_points(Vector::new(0.0, 0.0), Vector::new(100.0, 50.0));
+        let uv = Rect::from_points(Vector::new(0.0, 0.0), Vector::new(0.5, 0.5));
+        let insets = Rect::from_points(Vector::new(4.0, 4.0), Vector::new(4.0, 4.0));
+        let slices = nine_patch_slices(dest, uv, insets, Vector::new(64.0, 64.0));
+
+        assert_eq!(slices.len(), 9);
+        let (top_left, top_left_uv) = slices[0];
+        assert_eq!(top_left.size(), Vector::new(4.0, 4.0));
+        assert_eq!(top_left_uv.x1, Vector::new(4.0 / 64.0, 4.0 / 64.0));
+        let (center, _) = slices[4];
+        assert_eq!(center.size(), Vector::new(92.0, 42.0));
+        let (bottom_right, _) = slices[8];
+        assert_eq!(bottom_right.x1, Vector::new(100.0, 50.0));
+    }
+
+    #[test]
+    fn quarter_turn_moves_top_left_corner_to_top_right() {
+        let location = Rect {
+            x0: Vector::new(10.0, 20.0),
+            x1: Vector::new(30.0, 40.0),
+        };
+        let transform = SpriteTransform::rotated(std::f32::consts::FRAC_PI_2);
+
+        let corner = transform.apply(location, Vector::new(0.0, 0.0));
+        assert!((corner.x - 30.0).abs() < 1e-4 && (corner.y - 20.0).abs() < 1e-4);
+        assert_eq!(
+            SpriteTransform::default().apply(location, Vector::new(1.0, 1.0)),
+            location.x1
+        );
+    }
+
+    #[test]
+    fn flips_mirror_their_own_axis() {
+        assert_eq!(SpriteTransform::default().flip_scale(), [1.0, 1.0]);
+        let flipped = SpriteTransform {
+            flip_y: true,
+            ..SpriteTransform::rotated(std::f32::consts::FRAC_PI_2)
+        };
+        assert_eq!(flipped.flip_scale(), [1.0, -1.0]);
+    }
+}
@@ -4,11 +4,11 @@ use anyhow::{Result, anyhow};
 use freetype as ft;
 use gl::types::GLuint;
 use string_cache::DefaultAtom;
-use taffy::{AvailableSpace, TextAlign};
+use taffy::AvailableSpace;
 
 use crate::{
     geometry::Vector,
-    render::{Color, Text, TextAlignment},
+    render::{Color, FrameCounter, Text, TextAlignment, TextOverflow},
     shader::Shader,
 };
 
@@ -27,6 +27,8 @@ pub struct Character {
     advance: f32,
     ascent: f32,
     descent: f32,
+    /// Index of the atlas page holding the glyph bitmap
+    page: usize,
 }
 
 /// Used to draw characters with GPU instancing
@@ -39,20 +41,41 @@ pub struct CharacterInstance {
     atlas_size: [f32; 2],
 }
 
+/// Font size that glyphs are rasterized at in signed distance field mode
+const SDF_FONT_SIZE: u32 = 48;
+/// Distance in pixels around each glyph outline that a signed distance field covers
+const SDF_SPREAD: usize = 6;
+
+/// Instances, base positions and atlas pages for each glyph of a cached line
+type LineGlyphs = (Vec<CharacterInstance>, Vec<Vector<f32>>, Vec<usize>);
+
 #[derive(Debug)]
 pub struct FontAtlas {
-    texture_id: GLuint,
+    /// One texture per atlas page. Glyphs are always added to the last page and a new page is
+    /// allocated once it fills up.
+    texture_ids: Vec<GLuint>,
     size: Vector<i32>,
     characters: Vec<(char, Character)>,
     current_x: i32,
     current_y: i32,
     line_height: i32,
-    line_cache: Vec<(DefaultAtom, (Vec<CharacterInstance>, Vec<Vector<f32>>))>,
+    line_cache: Vec<(DefaultAtom, LineGlyphs)>,
     size_cache: HashMap<DefaultAtom, Vector<f32>>,
+    /// Kerning adjustments in pixels between pairs of consecutive characters
+    kerning_cache: HashMap<(char, char), f32>,
     max_ascent: f32,
     max_descent: f32,
 }
 
+/// Atlases are kept per font and font size since glyph bitmaps differ between both. Distance
+/// field glyphs are kept apart from regular ones since the bitmaps are not interchangeable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct AtlasKey {
+    font_id: usize,
+    font_size: u32,
+    sdf: bool,
+}
+
 #[derive(Debug)]
 pub struct TextLine {
     position: Vector<f32>,
@@ -60,17 +83,32 @@ pub struct TextLine {
     contents: String,
 }
 
+impl TextLine {
+    /// The part of the laid out text on this line
+    pub fn contents(&self) -> &str {
+        &self.contents
+    }
+}
+
 /// Renders text using caches for each character at each font size with OpenGL(ES, on Raspberry Pi)
 /// and Freetype
 pub struct TextRenderer {
-    shader: Shader,
+    pub(crate) shader: Shader,
     quad_vao: GLuint,
     quad_vbo: GLuint,
     instance_vbo: GLuint,
     #[allow(dead_code)] // This holds on to some important information until its dropped
     ft_library: ft::Library,
-    ft_face: ft::Face,
-    atlases: Vec<(u32, FontAtlas)>,
+    /// The first face is the default font used by any [Text] without an explicit font
+    ft_faces: Vec<(DefaultAtom, ft::Face)>,
+    atlases: Vec<(AtlasKey, FontAtlas)>,
+    /// Tab characters advance to the next multiple of this many space widths
+    tab_width: usize,
+    /// Rasterizes glyphs once as signed distance fields and scales them to each font size instead
+    /// of keeping an atlas per font size
+    sdf: bool,
+    /// Counts what is drawn, see [crate::render::renderer::Renderer::frame_stats]
+    pub(crate) frame_counter: FrameCounter,
 }
 
 impl std::fmt::Debug for TextRenderer {
@@ -80,7 +118,14 @@ impl std::fmt::Debug for TextRenderer {
             .field("quad_vao", &self.quad_vao)
             .field("quad_vbo", &self.quad_vbo)
             .field("instance_vbo", &self.instance_vbo)
+            .field(
+                "fonts",
+                &self.ft_faces.iter().map(|(n, _)| n).collect::<Vec<_>>(),
+            )
             .field("atlases", &self.atlases)
+            .field("tab_width", &self.tab_width)
+            .field("sdf", &self.sdf)
+            .field("frame_counter", &self.frame_counter)
             .finish()
     }
 }
@@ -194,49 +239,110 @@ impl TextRenderer {
             quad_vbo,
             instance_vbo,
             ft_library,
-            ft_face,
+            ft_faces: vec![(DefaultAtom::from("default"), ft_face)],
             atlases,
+            tab_width: 4,
+            sdf: false,
+            frame_counter: FrameCounter::default(),
         })
     }
 
-    fn get_or_create_atlas(&mut self, font_size: u32) -> Result<&mut FontAtlas> {
-        if let Some(idx) = self.atlases.iter().position(|(fs, _)| *fs == font_size) {
-            return Ok(&mut self.atlases[idx].1);
+    /// Switches between rasterizing glyphs for every font size and scaling signed distance field
+    /// glyphs. Distance fields use a single atlas per font which saves memory when many font
+    /// sizes are used, at the cost of slightly softer small text.
+    pub fn set_sdf(&mut self, enabled: bool) {
+        self.sdf = enabled;
+    }
+
+    /// The font size glyphs are rasterized at for a requested `font_size`, and the factor to scale
+    /// them by when drawing. Mapping an already mapped size gives the same size and a scale of 1.
+    fn atlas_font_size(&self, font_size: u32) -> (u32, f32) {
+        if self.sdf {
+            (SDF_FONT_SIZE, font_size as f32 / SDF_FONT_SIZE as f32)
+        } else {
+            (font_size, 1.0)
         }
-        let atlas_size = Vector::new(512, 512);
-        let mut texture_id: GLuint = 0;
+    }
 
-        unsafe {
-            gl::GenTextures(1, &mut texture_id);
-            gl::BindTexture(gl::TEXTURE_2D, texture_id);
-            gl::TexImage2D(
-                gl::TEXTURE_2D,
-                0,
-                gl::RED as i32,
-                atlas_size.x,
-                atlas_size.y,
-                0,
-                gl::RED,
-                gl::UNSIGNED_BYTE,
-                std::ptr::null(),
-            );
+    fn atlas_key(&self, font_id: usize, font_size: u32) -> AtlasKey {
+        AtlasKey {
+            font_id,
+            font_size,
+            sdf: self.sdf,
+        }
+    }
 
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
-            gl::BindTexture(gl::TEXTURE_2D, 0);
+    /// Sets how many spaces wide a tab stop is. Clears the cached line layouts since they depend
+    /// on it.
+    pub fn set_tab_width(&mut self, spaces: usize) {
+        self.tab_width = spaces;
+        for (_, atlas) in &mut self.atlases {
+            atlas.line_cache.clear();
+            atlas.size_cache.clear();
         }
+    }
 
-        self.ft_face.set_pixel_sizes(0, font_size)?;
-        let units_per_em = self.ft_face.em_size() as f32;
-        let ascender = self.ft_face.ascender() as f32;
-        let descender = self.ft_face.descender() as f32;
+    /// The distance between two tab stops for the given font
+    fn tab_size(&mut self, font_id: usize, font_size: u32) -> f32 {
+        let space = self
+            .load_character(' ', font_id, font_size)
+            .map_or(0.0, |ch| ch.advance);
+        space * self.tab_width as f32
+    }
+
+    /// Registers an additional font under `name` which [Text::font] can then refer to. Adding a
+    /// font with an existing name replaces the old face and clears its cached glyphs.
+    pub fn add_font(&mut self, name: &str, font_path: &Path) -> Result<()> {
+        let ft_face = self
+            .ft_library
+            .new_face(font_path, 0)
+            .map_err(|_| anyhow!("Failed to load font {}", font_path.display()))?;
+        let name = DefaultAtom::from(name);
+
+        if let Some(font_id) = self.ft_faces.iter().position(|(n, _)| *n == name) {
+            self.ft_faces[font_id].1 = ft_face;
+            self.atlases.retain(|(key, atlas)| {
+                if key.font_id == font_id {
+                    unsafe {
+                        gl::DeleteTextures(
+                            atlas.texture_ids.len() as i32,
+                            atlas.texture_ids.as_ptr(),
+                        );
+                    }
+                }
+                key.font_id != font_id
+            });
+        } else {
+            self.ft_faces.push((name, ft_face));
+        }
+        Ok(())
+    }
+
+    /// Resolves a font name to the id used by the drawing and layout functions. Unknown or missing
+    /// names fall back to the default font.
+    pub fn font_id(&self, font: Option<&DefaultAtom>) -> usize {
+        font.and_then(|font| self.ft_faces.iter().position(|(n, _)| n == font))
+            .unwrap_or(0)
+    }
+
+    fn get_or_create_atlas(&mut self, font_id: usize, font_size: u32) -> Result<&mut FontAtlas> {
+        let key = self.atlas_key(font_id, font_size);
+        if let Some(idx) = self.atlases.iter().position(|(k, _)| *k == key) {
+            return Ok(&mut self.atlases[idx].1);
+        }
+        let atlas_size = Vector::new(512, 512);
+        let texture_id = create_atlas_page(atlas_size, self.sdf);
+
+        let ft_face = &self.ft_faces[font_id].1;
+        ft_face.set_pixel_sizes(0, font_size)?;
+        let units_per_em = ft_face.em_size() as f32;
+        let ascender = ft_face.ascender() as f32;
+        let descender = ft_face.descender() as f32;
         let max_ascent = (ascender / units_per_em) * font_size as f32;
         let max_descent = (-descender / units_per_em) * font_size as f32;
 
         let new_atlas = FontAtlas {
-            texture_id,
+            texture_ids: vec![texture_id],
             size: atlas_size,
             characters: Vec::new(),
             current_x: 2,
@@ -244,15 +350,21 @@ impl TextRenderer {
             line_height: 0,
             line_cache: Vec::new(),
             size_cache: HashMap::new(),
+            kerning_cache: HashMap::new(),
             max_ascent,
             max_descent,
         };
-        self.atlases.push((font_size, new_atlas));
+        self.atlases.push((key, new_atlas));
         Ok(&mut self.atlases.last_mut().unwrap().1)
     }
 
-    fn load_character(&mut self, character: char, font_size: u32) -> Result<Character> {
-        let atlas = self.get_or_create_atlas(font_size)?;
+    fn load_character(
+        &mut self,
+        character: char,
+        font_id: usize,
+        font_size: u32,
+    ) -> Result<Character> {
+        let atlas = self.get_or_create_atlas(font_id, font_size)?;
 
         for (c, char_info) in &atlas.characters {
             if *c == character {
@@ -260,23 +372,43 @@ impl TextRenderer {
             }
         }
 
-        self.ft_face.set_pixel_sizes(0, font_size)?;
-        self.ft_face
-            .load_char(character as usize, ft::face::LoadFlag::DEFAULT)?;
+        let ft_face = &self.ft_faces[font_id].1;
+        ft_face.set_pixel_sizes(0, font_size)?;
+        ft_face.load_char(character as usize, ft::face::LoadFlag::DEFAULT)?;
 
-        let glyph = self.ft_face.glyph();
+        let glyph = ft_face.glyph();
         glyph.render_glyph(ft::render_mode::RenderMode::Normal)?;
 
         let bitmap = glyph.bitmap();
-        let glyph_width = bitmap.width();
-        let glyph_height = bitmap.rows();
-        let bitmap_left = glyph.bitmap_left();
-        let bitmap_top = glyph.bitmap_top();
+        let mut glyph_width = bitmap.width();
+        let mut glyph_height = bitmap.rows();
+        let mut bitmap_left = glyph.bitmap_left();
+        let mut bitmap_top = glyph.bitmap_top();
         let advance_x = (glyph.advance().x >> 6) as f32;
-        let buffer_ptr = bitmap.buffer().as_ptr();
         let buffer_empty = bitmap.buffer().is_empty();
+        let sdf = self.sdf;
+        let distance_field = (sdf && !buffer_empty).then(|| {
+            signed_distance_field(
+                bitmap.buffer(),
+                glyph_width as usize,
+                glyph_height as usize,
+                bitmap.pitch().unsigned_abs() as usize,
+                SDF_SPREAD,
+            )
+        });
+        if distance_field.is_some() {
+            // The field extends past the outline so the glyph grows on every side
+            let spread = SDF_SPREAD as i32;
+            glyph_width += 2 * spread;
+            glyph_height += 2 * spread;
+            bitmap_left -= spread;
+            bitmap_top += spread;
+        }
+        let buffer_ptr = distance_field
+            .as_ref()
+            .map_or(bitmap.buffer().as_ptr(), |field| field.as_ptr());
 
-        let atlas = self.get_or_create_atlas(font_size)?;
+        let atlas = self.get_or_create_atlas(font_id, font_size)?;
 
         // Check if we need to move to next line
         if atlas.current_x + glyph_width + 2 > atlas.size.x {
@@ -285,15 +417,26 @@ impl TextRenderer {
             atlas.line_height = 0;
         }
 
-        // Check if we've run out of space
+        // Start a new page if we've run out of space
         if atlas.current_y + glyph_height + 2 > atlas.size.y {
-            return Err(anyhow!("Atlas full for font size {}", font_size));
+            if glyph_width + 4 > atlas.size.x || glyph_height + 4 > atlas.size.y {
+                return Err(anyhow!(
+                    "Glyph '{}' is too large for an atlas page at font size {}",
+                    character,
+                    font_size
+                ));
+            }
+            atlas.texture_ids.push(create_atlas_page(atlas.size, sdf));
+            atlas.current_x = 2;
+            atlas.current_y = 2;
+            atlas.line_height = 0;
         }
+        let page = atlas.texture_ids.len() - 1;
 
         // Copy glyph bitmap to atlas
         if !buffer_empty {
             unsafe {
-                gl::BindTexture(gl::TEXTURE_2D, atlas.texture_id);
+                gl::BindTexture(gl::TEXTURE_2D, atlas.texture_ids[page]);
                 gl::TexSubImage2D(
                     gl::TEXTURE_2D,
                     0,
@@ -323,6 +466,7 @@ impl TextRenderer {
             advance: advance_x,
             ascent: bitmap_top as f32,
             descent: (glyph_height - bitmap_top) as f32,
+            page,
         };
 
         atlas.line_height = atlas.line_height.max(glyph_height);
@@ -332,24 +476,68 @@ impl TextRenderer {
         Ok(char_info)
     }
 
+    /// The horizontal adjustment in pixels to apply between `left` and `right` when `right`
+    /// directly follows `left`. Fonts without kerning information always give zero.
+    fn kerning(&mut self, left: char, right: char, font_id: usize, font_size: u32) -> f32 {
+        let Ok(atlas) = self.get_or_create_atlas(font_id, font_size) else {
+            return 0.0;
+        };
+        if let Some(&kern) = atlas.kerning_cache.get(&(left, right)) {
+            return kern;
+        }
+
+        let ft_face = &self.ft_faces[font_id].1;
+        let kern = if ft_face.has_kerning() && ft_face.set_pixel_sizes(0, font_size).is_ok() {
+            match (
+                ft_face.get_char_index(left as usize),
+                ft_face.get_char_index(right as usize),
+            ) {
+                (Some(l), Some(r)) => ft_face
+                    .get_kerning(l, r, ft::face::KerningMode::KerningDefault)
+                    .map_or(0.0, |v| v.x as f32 / 64.0),
+                _ => 0.0,
+            }
+        } else {
+            0.0
+        };
+
+        if let Ok(atlas) = self.get_or_create_atlas(font_id, font_size) {
+            atlas.kerning_cache.insert((left, right), kern);
+        }
+        kern
+    }
+
     fn compute_glyph_positions(
         &mut self,
         text: &str,
+        font_id: usize,
         font_size: u32,
-    ) -> (Vec<CharacterInstance>, Vec<Vector<f32>>) {
+    ) -> LineGlyphs {
         let scale = 1.0;
         let mut instances = Vec::new();
         let mut base_positions = Vec::new();
-        let size = self.measure_text_size(text, font_size);
+        let mut pages = Vec::new();
+        let size = self.measure_atlas_text(text, font_id, font_size);
         let position = Vector::zero();
         let mut x: f32 = position.x;
         let baseline_y = position.y + size.y * 0.8;
+        let tab_size = self.tab_size(font_id, font_size);
+        let mut previous: Option<char> = None;
 
         for c in text.chars() {
-            let ch = match self.load_character(c, font_size) {
+            // Tabs are drawn as a space to keep one instance per character, but advance to the
+            // next tab stop
+            let glyph = if c == '\t' { ' ' } else { c };
+            let ch = match self.load_character(glyph, font_id, font_size) {
                 Ok(ch) => ch,
                 Err(_) => continue,
             };
+            if let Some(previous) = previous
+                && c != '\t'
+            {
+                x += self.kerning(previous, c, font_id, font_size);
+            }
+            previous = (c != '\t').then_some(c);
 
             // Round to nearest pixel for crisp text rendering
             let xpos = f32::floor(x + ch.bearing.x as f32 * scale + 0.5);
@@ -368,60 +556,69 @@ impl TextRenderer {
 
             instances.push(instance);
             base_positions.push(Vector::new(xpos, ypos));
-            x += ch.advance * scale;
+            pages.push(ch.page);
+            if c == '\t' {
+                x = next_tab_stop(x, tab_size);
+            } else {
+                x += ch.advance * scale;
+            }
         }
-        (instances, base_positions)
+        (instances, base_positions, pages)
     }
 
-    fn compute_line(
-        &mut self,
-        text: &str,
-        font_size: u32,
-    ) -> &(Vec<CharacterInstance>, Vec<Vector<f32>>) {
-        self.get_or_create_atlas(font_size).ok();
+    fn compute_line(&mut self, text: &str, font_id: usize, font_size: u32) -> &LineGlyphs {
+        let key = self.atlas_key(font_id, font_size);
+        self.get_or_create_atlas(font_id, font_size).ok();
         if self
             .atlases
             .iter()
-            .find(|(fs, _)| *fs == font_size)
+            .find(|(k, _)| *k == key)
             .and_then(|(_, atlas)| atlas.line_cache.iter().find(|(k, _)| k == text))
             .is_none()
         {
-            let instances = self.compute_glyph_positions(text, font_size);
-            if let Ok(atlas) = self.get_or_create_atlas(font_size) {
+            let instances = self.compute_glyph_positions(text, font_id, font_size);
+            if let Ok(atlas) = self.get_or_create_atlas(font_id, font_size) {
                 atlas.line_cache.push((DefaultAtom::from(text), instances));
             }
         }
-        let atlas = self.get_or_create_atlas(font_size).unwrap();
+        let atlas = self.get_or_create_atlas(font_id, font_size).unwrap();
         &atlas.line_cache.iter().find(|(k, _)| k == text).unwrap().1
     }
 
-    /// Draws a single line of text
+    /// Draws a single line of text. `instances` holds one list of instances per atlas page.
     pub fn draw_line(
         &mut self,
         text: &str,
         position: Vector<f32>,
+        font_id: usize,
         font_size: u32,
-        instances: &mut Vec<CharacterInstance>,
+        instances: &mut Vec<Vec<CharacterInstance>>,
         cursor_idx: Option<usize>,
     ) {
-        let cached = self.compute_line(text, font_size);
+        let (atlas_size, scale) = self.atlas_font_size(font_size);
+        let cached = self.compute_line(text, font_id, atlas_size);
         // This can be avoided by changing cache from Vec<(CharacterInstance, [f32;2])> to
         // (Vec<CharacterInstance>, Vec<[f32;2]>). Or at least the extra allocation. Still the
         // bottleneck is probably the amount of draw calls
-        for (instance, base_position) in cached.0.iter().zip(cached.1.iter()) {
+        for ((instance, base_position), page) in
+            cached.0.iter().zip(cached.1.iter()).zip(cached.2.iter())
+        {
             let mut inst = *instance;
-            inst.position[0] = (base_position.x + position.x).floor();
-            inst.position[1] = (base_position.y + position.y).floor();
-            instances.push(inst);
+            inst.position[0] = (base_position.x * scale + position.x).floor();
+            inst.position[1] = (base_position.y * scale + position.y).floor();
+            inst.size = [inst.size[0] * scale, inst.size[1] * scale];
+            push_to_page(instances, *page, inst);
         }
 
         if let Some(cursor_idx) = cursor_idx {
-            let (cursor_inst, _) = self.compute_glyph_positions("|", font_size);
-            let cursor_pos = self.cursor_pos(text, position, font_size, cursor_idx);
+            let (cursor_inst, _, cursor_page) =
+                self.compute_glyph_positions("|", font_id, atlas_size);
+            let cursor_pos = self.cursor_pos(text, position, font_id, font_size, cursor_idx);
             let mut cursor_inst = cursor_inst[0];
             cursor_inst.position[0] = cursor_pos.x;
             cursor_inst.position[1] = cursor_pos.y;
-            instances.push(cursor_inst);
+            cursor_inst.size = [cursor_inst.size[0] * scale, cursor_inst.size[1] * scale];
+            push_to_page(instances, cursor_page[0], cursor_inst);
         }
     }
 
@@ -429,6 +626,7 @@ impl TextRenderer {
         &mut self,
         text: &str,
         position: Vector<f32>,
+        font_id: usize,
         font_size: u32,
         cursor_idx: usize,
     ) -> Vector<f32> {
@@ -436,54 +634,71 @@ impl TextRenderer {
             return Vector::zero();
         }
 
-        let cached = self.compute_line(text, font_size);
+        let (atlas_size, scale) = self.atlas_font_size(font_size);
+        let cached = self.compute_line(text, font_id, atlas_size);
         let cursor_pos = if cursor_idx == 0 {
             cached.1[0]
         } else if cursor_idx == text.len() {
-            let line_width: f32 = text
-                .chars()
-                .map(|c| {
-                    self.load_character(c, font_size)
-                        .map_or(0.0, |ch| ch.advance)
-                })
-                .sum();
-            Vector::new(line_width, 0.0)
+            Vector::new(self.measure_atlas_text(text, font_id, atlas_size).x, 0.0)
+        } else if text.chars().nth(cursor_idx - 1) == Some('\t') {
+            // Tabs are drawn as a space, the caret belongs at the tab stop they advance to instead
+            let y = cached.1[cursor_idx - 1].y;
+            let before: String = text.chars().take(cursor_idx).collect();
+            Vector::new(self.measure_atlas_text(&before, font_id, atlas_size).x, y)
         } else {
             cached.1[cursor_idx - 1] + Vector::new(cached.0[cursor_idx - 1].size[0], 0.0)
         };
-        Vector::new(cursor_pos.x + position.x, cursor_pos.y + position.y)
+        Vector::new(
+            cursor_pos.x * scale + position.x,
+            cursor_pos.y * scale + position.y,
+        )
     }
 
-    fn commit_drawing(&self, instances: &mut Vec<CharacterInstance>, font_size: u32, color: Color) {
-        let atlas_texture_id = self
+    /// Draws the instances of each atlas page with one instanced draw call per page
+    fn commit_drawing(
+        &self,
+        instances: &[Vec<CharacterInstance>],
+        font_id: usize,
+        font_size: u32,
+        color: Color,
+    ) {
+        let key = self.atlas_key(font_id, self.atlas_font_size(font_size).0);
+        let atlas_texture_ids = &self
             .atlases
             .iter()
-            .find(|(fs, _)| *fs == font_size)
+            .find(|(k, _)| *k == key)
             .unwrap()
             .1
-            .texture_id;
+            .texture_ids;
 
         self.shader.use_shader();
         let text_unit = 0;
         self.shader.set_uniform("text", &text_unit);
         let color_vec = glm::make_vec4(&[color.r, color.g, color.b, color.a]);
         self.shader.set_uniform("textColor", &color_vec);
+        self.shader.set_uniform("sdf", &(self.sdf as i32));
 
         unsafe {
             gl::ActiveTexture(gl::TEXTURE0);
-            gl::BindTexture(gl::TEXTURE_2D, atlas_texture_id);
             gl::BindVertexArray(self.quad_vao);
-
             gl::BindBuffer(gl::ARRAY_BUFFER, self.instance_vbo);
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                (std::mem::size_of::<CharacterInstance>() * instances.len()) as isize,
-                instances.as_ptr() as *const c_void,
-                gl::DYNAMIC_DRAW,
-            );
 
-            // Draw all characters in one call
-            gl::DrawArraysInstanced(gl::TRIANGLES, 0, 6, instances.len() as i32);
+            for (page_instances, texture_id) in instances.iter().zip(atlas_texture_ids) {
+                if page_instances.is_empty() {
+                    continue;
+                }
+                gl::BindTexture(gl::TEXTURE_2D, *texture_id);
+                gl::BufferData(
+                    gl::ARRAY_BUFFER,
+                    (std::mem::size_of::<CharacterInstance>() * page_instances.len()) as isize,
+                    page_instances.as_ptr() as *const c_void,
+                    gl::DYNAMIC_DRAW,
+                );
+
+                // Draw all characters on the page in one call
+                self.frame_counter.count_draw_call(2, page_instances.len());
+                gl::DrawArraysInstanced(gl::TRIANGLES, 0, 6, page_instances.len() as i32);
+            }
             gl::BindVertexArray(0);
             gl::BindTexture(gl::TEXTURE_2D, 0);
         }
@@ -498,6 +713,7 @@ impl TextRenderer {
         size: taffy::geometry::Size<f32>,
         cursor_idx: Option<usize>,
     ) {
+        let font_id = self.font_id(text.font.as_ref());
         let mut instances = vec![];
         let mut line_start = 0;
         let text_str = text.text.clone();
@@ -508,8 +724,11 @@ impl TextRenderer {
                 height: AvailableSpace::Definite(size.height),
             },
             text_str.clone(),
+            font_id,
             text.font_size,
-            true,
+            text.line_height,
+            editable_overflow(text.overflow, cursor_idx),
+            text.alignment,
         ) {
             let cursor_idx = cursor_idx
                 .filter(|&idx| {
@@ -532,12 +751,13 @@ impl TextRenderer {
             self.draw_line(
                 draw_text,
                 position + line.position,
+                font_id,
                 text.font_size,
                 &mut instances,
                 cursor_idx,
             );
         }
-        self.commit_drawing(&mut instances, text.font_size, text.color);
+        self.commit_drawing(&instances, font_id, text.font_size, text.color);
     }
 
     /// Wraps text inside `size` with explicit newline handling and draws the layed out lines at `position`
@@ -549,6 +769,7 @@ impl TextRenderer {
         size: taffy::geometry::Size<f32>,
         cursor_idx: Option<usize>,
     ) {
+        let font_id = self.font_id(text.font.as_ref());
         let mut instances = vec![];
         let mut line_start = 0;
         for line in self.layout_text_explicit(
@@ -557,7 +778,10 @@ impl TextRenderer {
                 height: AvailableSpace::Definite(size.height),
             },
             text.text,
+            font_id,
             text.font_size,
+            text.line_height,
+            text.alignment,
         ) {
             let cursor_idx = cursor_idx
                 .filter(|&idx| line_start <= idx && idx < line_start + line.contents.len())
@@ -566,15 +790,17 @@ impl TextRenderer {
             self.draw_line(
                 &line.contents,
                 position + line.position,
+                font_id,
                 text.font_size,
                 &mut instances,
                 cursor_idx,
             );
         }
-        self.commit_drawing(&mut instances, text.font_size, text.color);
+        self.commit_drawing(&instances, font_id, text.font_size, text.color);
     }
 
-    /// Draws text without any wrapping
+    /// Draws text without any wrapping. Text that doesn't fit is truncated if the text asks for
+    /// [TextOverflow::Ellipsis] or [TextOverflow::Clip], otherwise it spills out of `size`
     pub fn draw_on_line(
         &mut self,
         text: Text,
@@ -582,6 +808,7 @@ impl TextRenderer {
         size: taffy::geometry::Size<f32>,
         cursor_idx: Option<usize>,
     ) {
+        let font_id = self.font_id(text.font.as_ref());
         let mut instances = vec![];
         let mut line_start = 0;
         let text_str = text.text.clone();
@@ -592,17 +819,15 @@ impl TextRenderer {
                 height: AvailableSpace::Definite(size.height),
             },
             text_str.clone(),
+            font_id,
             text.font_size,
-            false,
+            text.line_height,
+            match editable_overflow(text.overflow, cursor_idx) {
+                TextOverflow::Wrap => TextOverflow::Visible,
+                overflow => overflow,
+            },
+            text.alignment,
         ) {
-            let aligment_offset = Vector::new(
-                match text.alignment {
-                    TextAlignment::Left => 0.0,
-                    TextAlignment::Center => (size.width - line.size.x) / 2.0,
-                    TextAlignment::Right => size.width - line.size.x,
-                },
-                0.0,
-            );
             let cursor_idx = cursor_idx
                 .filter(|&idx| {
                     line_start <= idx
@@ -623,17 +848,26 @@ impl TextRenderer {
             line_start += line.contents.len();
             self.draw_line(
                 draw_text,
-                position + line.position + aligment_offset,
+                position + line.position,
+                font_id,
                 text.font_size,
                 &mut instances,
                 cursor_idx,
             );
         }
-        self.commit_drawing(&mut instances, text.font_size, text.color);
+        self.commit_drawing(&instances, font_id, text.font_size, text.color);
     }
 
-    fn measure_text_size(&mut self, text: &str, font_size: u32) -> Vector<f32> {
-        let atlas = self.get_or_create_atlas(font_size).unwrap();
+    fn measure_text_size(&mut self, text: &str, font_id: usize, font_size: u32) -> Vector<f32> {
+        let (atlas_size, scale) = self.atlas_font_size(font_size);
+        self.measure_atlas_text(text, font_id, atlas_size)
+            .scaled(scale)
+    }
+
+    /// Measures text in the units of the atlas for `font_size`, see [Self::atlas_font_size]
+    fn measure_atlas_text(&mut self, text: &str, font_id: usize, font_size: u32) -> Vector<f32> {
+        let key = self.atlas_key(font_id, font_size);
+        let atlas = self.get_or_create_atlas(font_id, font_size).unwrap();
         let max_ascent = atlas.max_ascent;
         let max_descent = atlas.max_descent;
         let height = max_ascent + max_descent;
@@ -642,46 +876,77 @@ impl TextRenderer {
             return Vector::new(0.0, height);
         }
 
-        let key = DefaultAtom::from(text);
-        if let Some(atlas) = self.atlases.iter().find(|(fs, _)| *fs == font_size) {
-            if let Some(&size) = atlas.1.size_cache.get(&key) {
+        let text_key = DefaultAtom::from(text);
+        if let Some(atlas) = self.atlases.iter().find(|(k, _)| *k == key) {
+            if let Some(&size) = atlas.1.size_cache.get(&text_key) {
                 return size;
             }
         }
 
+        let size = Vector::new(self.atlas_text_width(text, font_id, font_size), height);
+        let atlas = self.get_or_create_atlas(font_id, font_size).unwrap();
+        atlas.size_cache.insert(text_key, size);
+        size
+    }
+
+    /// Like [Self::measure_text_size] but only the width and without storing the result in the
+    /// size cache. Used for throwaway strings such as truncation candidates.
+    fn measure_text_width_uncached(&mut self, text: &str, font_id: usize, font_size: u32) -> f32 {
+        let (atlas_size, scale) = self.atlas_font_size(font_size);
+        self.atlas_text_width(text, font_id, atlas_size) * scale
+    }
+
+    /// Sums the advances and kerning of `text` in the units of the atlas for `font_size`
+    fn atlas_text_width(&mut self, text: &str, font_id: usize, font_size: u32) -> f32 {
+        let tab_size = self.tab_size(font_id, font_size);
         let mut width: f32 = 0.0;
+        let mut previous: Option<char> = None;
         for c in text.chars() {
-            let ch = match self.load_character(c, font_size) {
+            if c == '\t' {
+                width = next_tab_stop(width, tab_size);
+                previous = None;
+                continue;
+            }
+            let ch = match self.load_character(c, font_id, font_size) {
                 Ok(ch) => ch,
                 Err(_) => continue,
             };
+            if let Some(previous) = previous {
+                width += self.kerning(previous, c, font_id, font_size);
+            }
+            previous = Some(c);
             width += ch.advance;
         }
-
-        let size = Vector::new(width, height);
-        let atlas = self.get_or_create_atlas(font_size).unwrap();
-        atlas.size_cache.insert(key, size);
-        size
+        width
     }
 
     /// Wraps text inside the given `available_space`. Always respects the horizontal spacing but
     /// might overflow over the bottom. This is the automatic layout that ignores explicit newlines
-    /// and trims leading whitespace.
+    /// and trims leading whitespace. Lines are offset horizontally according to `alignment` when
+    /// the available width is definite. Only [TextOverflow::Wrap] breaks the text into several
+    /// lines, the other modes keep it on a single line which is truncated to the available width.
+    /// Consecutive lines are `line_height` times the font size apart.
+    #[allow(clippy::too_many_arguments)]
     pub fn layout_text(
         &mut self,
         available_space: taffy::geometry::Size<taffy::style::AvailableSpace>,
         text: String,
+        font_id: usize,
         font_size: u32,
-        wrap: bool,
+        line_height: f32,
+        overflow: TextOverflow,
+        alignment: TextAlignment,
     ) -> Vec<TextLine> {
         let mut out = vec![];
+        let wrap = overflow == TextOverflow::Wrap;
+        let line_advance = font_size as f32 * line_height;
 
-        let mut y = font_size as f32 * 0.2;
+        let mut y = font_size as f32 * (line_height - 1.0);
         let mut current_line = String::new();
         let mut pending_line = String::new();
         for word in split_with_trailing_whitespace(&text) {
             pending_line.push_str(word);
-            let pending_size = self.measure_text_size(&pending_line, font_size);
+            let pending_size = self.measure_text_size(&pending_line, font_id, font_size);
             // TODO: Think about non-definite cases
             if pending_size.x
                 > (match available_space.width {
@@ -692,13 +957,13 @@ impl TextRenderer {
                 && !current_line.is_empty()
                 && wrap
             {
-                let size = self.measure_text_size(&current_line, font_size);
+                let size = self.measure_text_size(&current_line, font_id, font_size);
                 out.push(TextLine {
                     position: Vector::new(0.0, y),
                     size,
                     contents: current_line.clone(),
                 });
-                y += (font_size as f32) * 1.2;
+                y += line_advance;
                 current_line.clear();
                 pending_line.clear();
                 pending_line.push_str(word);
@@ -708,37 +973,61 @@ impl TextRenderer {
         if !current_line.is_empty() {
             out.push(TextLine {
                 position: Vector::new(0.0, y),
-                size: self.measure_text_size(&current_line, font_size),
+                size: self.measure_text_size(&current_line, font_id, font_size),
                 contents: current_line.clone(),
             });
         } else if out.is_empty() {
             out.push(TextLine {
                 position: Vector::new(0.0, y),
-                size: Vector::new(0.0, font_size as f32 * 1.2),
+                size: Vector::new(0.0, line_advance),
                 contents: String::new(),
             });
         }
 
+        if let (TextOverflow::Ellipsis | TextOverflow::Clip, AvailableSpace::Definite(max_width)) =
+            (overflow, available_space.width)
+        {
+            let suffix = if overflow == TextOverflow::Ellipsis {
+                "…"
+            } else {
+                ""
+            };
+            for line in &mut out {
+                if line.size.x > max_width {
+                    line.contents = truncate_to_width(&line.contents, max_width, suffix, |s| {
+                        self.measure_text_width_uncached(s, font_id, font_size)
+                    });
+                    line.size = self.measure_text_size(&line.contents, font_id, font_size);
+                }
+            }
+        }
+
+        align_lines(&mut out, available_space.width, alignment);
         out
     }
 
     /// Wraps text inside the given `available_space` with explicit newline handling.
-    /// Respects explicit newlines (\n) and preserves leading whitespace/tabs.
+    /// Respects explicit newlines (\n) and preserves leading whitespace/tabs. Consecutive lines are
+    /// `line_height` times the font size apart.
     pub fn layout_text_explicit(
         &mut self,
         available_space: taffy::geometry::Size<taffy::style::AvailableSpace>,
         text: String,
+        font_id: usize,
         font_size: u32,
+        line_height: f32,
+        alignment: TextAlignment,
     ) -> Vec<TextLine> {
         let mut out = vec![];
+        let line_advance = font_size as f32 * line_height;
 
-        let mut y = font_size as f32 * 0.2;
+        let mut y = font_size as f32 * (line_height - 1.0);
         for line in text.split('\n') {
             let mut current_line = String::new();
             let mut pending_line = String::new();
             for word in split_preserve_leading_whitespace(line) {
                 pending_line.push_str(word);
-                let pending_size = self.measure_text_size(&pending_line, font_size);
+                let pending_size = self.measure_text_size(&pending_line, font_id, font_size);
                 // TODO: Think about non-definite cases
                 if pending_size.x
                     > (match available_space.width {
@@ -748,13 +1037,13 @@ impl TextRenderer {
                     })
                     && !current_line.is_empty()
                 {
-                    let size = self.measure_text_size(&current_line, font_size);
+                    let size = self.measure_text_size(&current_line, font_id, font_size);
                     out.push(TextLine {
                         position: Vector::new(0.0, y),
                         size,
                         contents: current_line.clone(),
                     });
-                    y += (font_size as f32) * 1.2;
+                    y += line_advance;
                     current_line.clear();
                     pending_line.clear();
                     pending_line.push_str(word);
@@ -764,14 +1053,15 @@ impl TextRenderer {
             if !current_line.is_empty() {
                 out.push(TextLine {
                     position: Vector::new(0.0, y),
-                    size: self.measure_text_size(&current_line, font_size),
+                    size: self.measure_text_size(&current_line, font_id, font_size),
                     contents: current_line.clone(),
                 });
             }
             // Increment y after each explicit line, even if empty
-            y += (font_size as f32) * 1.2;
+            y += line_advance;
         }
 
+        align_lines(&mut out, available_space.width, alignment);
         out
     }
 }
@@ -781,7 +1071,7 @@ impl Drop for TextRenderer {
         // Free OpenGL atlas textures to avoid memory leaks
         for (_, atlas) in &self.atlases {
             unsafe {
-                gl::DeleteTextures(1, &atlas.texture_id);
+                gl::DeleteTextures(atlas.texture_ids.len() as i32, atlas.texture_ids.as_ptr());
             }
         }
 
@@ -793,6 +1083,46 @@ impl Drop for TextRenderer {
     }
 }
 
+/// Allocates an empty single channel texture used as one page of a [FontAtlas]. Distance fields
+/// are sampled with linear filtering since they are scaled, regular glyphs are drawn pixel for
+/// pixel.
+fn create_atlas_page(size: Vector<i32>, sdf: bool) -> GLuint {
+    let filter = if sdf { gl::LINEAR } else { gl::NEAREST } as i32;
+    let mut texture_id: GLuint = 0;
+
+    unsafe {
+        gl::GenTextures(1, &mut texture_id);
+        gl::BindTexture(gl::TEXTURE_2D, texture_id);
+        gl::TexImage2D(
+            gl::TEXTURE_2D,
+            0,
+            gl::RED as i32,
+            size.x,
+            size.y,
+            0,
+            gl::RED,
+            gl::UNSIGNED_BYTE,
+            std::ptr::null(),
+        );
+
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, filter);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, filter);
+        gl::BindTexture(gl::TEXTURE_2D, 0);
+    }
+
+    texture_id
+}
+
+/// Adds an instance to the list for its atlas page, growing the list of pages if needed
+fn push_to_page(instances: &mut Vec<Vec<CharacterInstance>>, page: usize, inst: CharacterInstance) {
+    if instances.len() <= page {
+        instances.resize_with(page + 1, Vec::new);
+    }
+    instances[page].push(inst);
+}
+
 /// Calculates a single bounding box for a collection of [TextLine]s
 pub fn total_size(lines: &[TextLine]) -> Vector<f32> {
     let mut out = Vector::<f32>::zero();
@@ -805,6 +1135,112 @@ pub fn total_size(lines: &[TextLine]) -> Vector<f32> {
     out
 }
 
+/// Offsets each line horizontally so that it is aligned within `available_width`. Lines are left
+/// untouched unless the width is definite since there is nothing to align against otherwise.
+fn align_lines(lines: &mut [TextLine], available_width: AvailableSpace, alignment: TextAlignment) {
+    let AvailableSpace::Definite(width) = available_width else {
+        return;
+    };
+    for line in lines {
+        line.position.x = match alignment {
+            TextAlignment::Left => 0.0,
+            TextAlignment::Center => (width - line.size.x) / 2.0,
+            TextAlignment::Right => width - line.size.x,
+        };
+    }
+}
+
+/// Converts a glyph coverage bitmap with rows `pitch` bytes apart into a signed distance field
+/// padded by `spread` pixels on every side. Values above 128 are inside the glyph and the value
+/// moves `128 / spread` per pixel of distance from the outline.
+fn signed_distance_field(
+    coverage: &[u8],
+    width: usize,
+    height: usize,
+    pitch: usize,
+    spread: usize,
+) -> Vec<u8> {
+    let inside = |x: isize, y: isize| {
+        x >= 0
+            && y >= 0
+            && (x as usize) < width
+            && (y as usize) < height
+            && coverage[y as usize * pitch + x as usize] >= 128
+    };
+    let reach = spread as isize;
+    let out_width = width + 2 * spread;
+    let out_height = height + 2 * spread;
+
+    let mut out = Vec::with_capacity(out_width * out_height);
+    for out_y in 0..out_height as isize {
+        for out_x in 0..out_width as isize {
+            let (x, y) = (out_x - reach, out_y - reach);
+            let here = inside(x, y);
+            let mut nearest = spread as f32;
+            for dy in -reach..=reach {
+                for dx in -reach..=reach {
+                    if inside(x + dx, y + dy) != here {
+                        nearest = nearest.min(((dx * dx + dy * dy) as f32).sqrt());
+                    }
+                }
+            }
+            let distance = if here { nearest } else { -nearest };
+            let value = 0.5 + distance / (2.0 * spread as f32);
+            out.push((value.clamp(0.0, 1.0) * 255.0).round() as u8);
+        }
+    }
+    out
+}
+
+/// Returns the first tab stop strictly after `x`. Tab stops are placed every `tab_size` pixels.
+fn next_tab_stop(x: f32, tab_size: f32) -> f32 {
+    if tab_size <= 0.0 {
+        return x;
+    }
+    ((x / tab_size).floor() + 1.0) * tab_size
+}
+
+/// Text with a caret in it is never truncated. The caret is placed by its index into the whole
+/// text, which would point past the glyphs that are left after truncating.
+fn editable_overflow(overflow: TextOverflow, cursor_idx: Option<usize>) -> TextOverflow {
+    match overflow {
+        TextOverflow::Ellipsis | TextOverflow::Clip if cursor_idx.is_some() => {
+            TextOverflow::Visible
+        }
+        overflow => overflow,
+    }
+}
+
+/// Removes characters from the end of `text` until what is left, followed by `suffix`, is at most
+/// `max_width` wide according to `measure`. Text that already fits is returned as is. The cut is
+/// found with a binary search over the character count, so `measure` runs O(log n) times.
+fn truncate_to_width(
+    text: &str,
+    max_width: f32,
+    suffix: &str,
+    mut measure: impl FnMut(&str) -> f32,
+) -> String {
+    if measure(text) <= max_width {
+        return text.to_string();
+    }
+    let ends: Vec<usize> = text.char_indices().map(|(i, _)| i).collect();
+    let candidate = |kept: usize| format!("{}{}", text[..ends[kept]].trim_end(), suffix);
+
+    let mut best = None;
+    let (mut lo, mut hi) = (0, ends.len());
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        let truncated = candidate(mid);
+        if measure(&truncated) <= max_width {
+            best = Some(truncated);
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    best.unwrap_or_default()
+}
+
 /// Splits a string slice into on ascii whitespace, but keeps the whitespace at the end of each
 /// split segment since we still want the whitespace included when rendering the text
 fn split_with_trailing_whitespace(s: &str) -> Vec<&str> {
@@ -851,6 +1287,16 @@ mod tests {
     use freetype as ft;
     use image::{GrayImage, ImageBuffer};
     use std::path::Path;
+    use taffy::AvailableSpace;
+
+    use super::{
+        CharacterInstance, TextLine, align_lines, editable_overflow, next_tab_stop, push_to_page,
+        signed_distance_field, truncate_to_width,
+    };
+    use crate::{
+        geometry::Vector,
+        render::{TextAlignment, TextOverflow},
+    };
 
     fn get_test_font_path() -> &'static Path {
         Path::new("../assets/fonts/LiberationMono.ttf")
@@ -1010,4 +1456,110 @@ mod tests {
             println!("Character 'A' written to character_A.png");
         }
     }
+
+    fn line(width: f32) -> TextLine {
+        TextLine {
+            position: Vector::new(0.0, 0.0),
+            size: Vector::new(width, 12.0),
+            contents: String::new(),
+        }
+    }
+
+    #[test]
+    fn align_lines_offsets_by_line_width() {
+        let mut lines = vec![line(20.0), line(60.0)];
+        align_lines(
+            &mut lines,
+            AvailableSpace::Definite(100.0),
+            TextAlignment::Center,
+        );
+        assert_eq!(lines[0].position.x, 40.0);
+        assert_eq!(lines[1].position.x, 20.0);
+
+        align_lines(
+            &mut lines,
+            AvailableSpace::Definite(100.0),
+            TextAlignment::Right,
+        );
+        assert_eq!(lines[0].position.x, 80.0);
+        assert_eq!(lines[1].position.x, 40.0);
+    }
+
+    #[test]
+    fn align_lines_ignores_indefinite_width() {
+        let mut lines = vec![line(20.0)];
+        align_lines(&mut lines, AvailableSpace::MaxContent, TextAlignment::Right);
+        assert_eq!(lines[0].position.x, 0.0);
+    }
+
+    #[test]
+    fn push_to_page_groups_instances_by_page() {
+        let inst = CharacterInstance {
+            position: [0.0, 0.0],
+            size: [1.0, 1.0],
+            atlas_coords: [0.0, 0.0],
+            atlas_size: [0.1, 0.1],
+        };
+        let mut instances = vec![];
+        push_to_page(&mut instances, 2, inst);
+        push_to_page(&mut instances, 0, inst);
+        push_to_page(&mut instances, 2, inst);
+
+        assert_eq!(instances.len(), 3);
+        assert_eq!(instances[0].len(), 1);
+        assert!(instances[1].is_empty());
+        assert_eq!(instances[2].len(), 2);
+    }
+
+    #[test]
+    fn truncate_to_width_appends_suffix() {
+        // Every character is 10 pixels wide
+        let measure = |s: &str| s.chars().count() as f32 * 10.0;
+        assert_eq!(truncate_to_width("short", 50.0, "…", measure), "short");
+        assert_eq!(
+            truncate_to_width("a long label", 50.0, "…", measure),
+            "a lo…"
+        );
+        assert_eq!(truncate_to_width("a long label", 30.0, "…", measure), "a…");
+        assert_eq!(truncate_to_width("a long label", 30.0, "", measure), "a l");
+        assert_eq!(truncate_to_width("a long label", 5.0, "…", measure), "");
+    }
+
+    #[test]
+    fn text_being_edited_is_not_truncated() {
+        assert_eq!(
+            editable_overflow(TextOverflow::Ellipsis, None),
+            TextOverflow::Ellipsis
+        );
+        assert_eq!(
+            editable_overflow(TextOverflow::Ellipsis, Some(3)),
+            TextOverflow::Visible
+        );
+        assert_eq!(
+            editable_overflow(TextOverflow::Clip, Some(0)),
+            TextOverflow::Visible
+        );
+        assert_eq!(
+            editable_overflow(TextOverflow::Wrap, Some(3)),
+            TextOverflow::Wrap
+        );
+    }
+
+    #[test]
+    fn tabs_advance_to_the_next_stop() {
+        assert_eq!(next_tab_stop(0.0, 32.0), 32.0);
+        assert_eq!(next_tab_stop(10.0, 32.0), 32.0);
+        assert_eq!(next_tab_stop(32.0, 32.0), 64.0);
+        assert_eq!(next_tab_stop(10.0, 0.0), 10.0);
+    }
+
+    #[test]
+    fn distance_field_is_padded_and_centered_on_the_outline() {
+        let field = signed_distance_field(&[255], 1, 1, 1, 2);
+        assert_eq!(field.len(), 25);
+        // The glyph pixel is inside, its neighbour just outside and the corner far outside
+        assert!(field[12] > 128);
+        assert!(field[11] < 128 && field[11] > 0);
+        assert_eq!(field[0], 0);
+    }
 }
@@ -0,0 +1,68 @@
+use std::sync::Arc;
+
+use taffy::NodeId;
+
+use crate::geometry::Vector;
+use crate::render::renderer::{AppState, EventListener, Listeners, Renderer};
+use crate::render::widgets::UiBuilder;
+use crate::render::{COLOR_LIGHT, Text};
+
+/// A floating list of actions at a point on the screen. Opened with
+/// [Renderer::open_context_menu] and drawn above all other layers until an item is picked, the
+/// mouse is pressed outside of it or escape is pressed.
+pub struct ContextMenu<T>
+where
+    T: AppState,
+{
+    /// Top left corner of the menu. Moved as needed to keep the menu on screen.
+    pub position: Vector<f32>,
+    pub items: Vec<(String, EventListener<T>)>,
+}
+
+impl<T> Clone for ContextMenu<T>
+where
+    T: AppState,
+{
+    fn clone(&self) -> Self {
+        Self {
+            position: self.position,
+            items: self.items.clone(),
+        }
+    }
+}
+
+impl<T> ContextMenu<T>
+where
+    T: AppState,
+{
+    pub fn new(position: Vector<f32>, items: Vec<(String, EventListener<T>)>) -> Self {
+        Self { position, items }
+    }
+
+    /// Builds the menu. Picking an item closes the menu before running its action, so actions
+    /// are free to open another menu.
+    pub fn build(&self, b: &UiBuilder<T>) -> NodeId {
+        let items: Vec<NodeId> = self
+            .items
+            .iter()
+            .map(|(label, action)| {
+                let action = action.clone();
+                b.ui(
+                    "px-8 py-4 rounded-4 hover:bg-zinc-700",
+                    Listeners {
+                        on_left_mouse_up: Some(Arc::new(move |renderer: &mut Renderer<T>| {
+                            renderer.close_context_menu();
+                            action(renderer);
+                        })),
+                        ..Default::default()
+                    },
+                    [b.text("", Text::new(label.clone(), 12, COLOR_LIGHT))],
+                )
+            })
+            .collect();
+        b.div(
+            "flex-col bg-zinc-800 border-2 border-zinc-600 rounded-4 p-2",
+            &items,
+        )
+    }
+}
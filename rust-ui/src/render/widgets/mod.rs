@@ -12,15 +12,17 @@ use taffy::{Layout, NodeId, Style, TaffyTree};
 
 use crate::geometry::Rect;
 use crate::render::renderer::{
-    flags, Anchor, AppState, DelayedMarker, DelayedRender, EventListener, Listeners, NodeContext,
-    Renderer,
+    flags, Anchor, AppState, DelayedMarker, Listeners, NodeContext, Renderer,
 };
-use crate::render::{Text, COLOR_LIGHT};
+use crate::render::Text;
 use crate::style::parse_style;
 
+pub mod context_menu;
 pub mod scrollable;
 pub mod select;
+pub mod text_area;
 pub mod text_field;
+pub mod virtual_list;
 
 #[macro_export]
 macro_rules! id {
@@ -325,6 +327,28 @@ where
         tree.get_node_context_mut(id).map(f);
     }
 
+    /// Edits the layout style of a node that has already been created, before layout is computed
+    pub fn mutate_style<F>(&self, id: NodeId, f: F)
+    where
+        F: FnOnce(&mut Style),
+    {
+        let mut tree = self.tree.borrow_mut();
+        if let Ok(style) = tree.style(id) {
+            let mut style = style.clone();
+            f(&mut style);
+            if let Err(e) = tree.set_style(id, style) {
+                error!("Couldn't set style of {:?}: {}", id, e);
+            }
+        }
+    }
+
+    /// Shows `text` in a box next to the mouse once it rests on `id`. Returns `id` so that it can
+    /// wrap a node while it is being built.
+    pub fn tooltip(&self, id: NodeId, text: &str) -> NodeId {
+        self.mutate_context(id, |ctx| ctx.tooltip = Some(text.to_string()));
+        id
+    }
+
     /// **WARNING** this erases the delayed markers from [Self].
     pub fn delayed_ids(&self) -> Vec<DelayedMarker> {
         self.delayed_markers.replace(vec![])
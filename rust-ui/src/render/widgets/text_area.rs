@@ -0,0 +1,500 @@
+use std::cell::Cell;
+use std::ops::Range;
+use std::sync::Arc;
+
+use glfw::{Action, Key, Modifiers};
+use taffy::{AvailableSpace, Layout, NodeId, Size};
+
+use crate::clipboard::Clipboard;
+use crate::geometry::{Rect, Vector};
+use crate::input::types_char;
+use crate::render::renderer::{AppState, Listeners, NodeContext, Renderer, flags};
+use crate::render::text::TextRenderer;
+use crate::render::widgets::{DefaultAtom, UiBuilder, UiData};
+use crate::render::{Border, COLOR_LIGHT, COLOR_PRIMARY, Text, TextAlignment};
+use crate::style::parse_style;
+
+const FONT_SIZE: u32 = 12;
+const LINE_HEIGHT: f32 = 1.2;
+const LINE_ADVANCE: f32 = FONT_SIZE as f32 * LINE_HEIGHT;
+
+#[derive(Debug, Clone, Default)]
+pub struct TextAreaData {
+    pub contents: String,
+    /// Byte index of the cursor in [Self::contents]
+    pub cursor_pos: usize,
+    /// The other end of the selection, which spans from here to the cursor
+    pub select_pos: Option<usize>,
+    /// Distance in pixels from the top of the text to the top of the box
+    pub scroll_offset: f32,
+    /// Size of the box without padding as of the last frame. Lines can't be wrapped without it
+    text_size: Cell<Size<f32>>,
+}
+
+impl<T> UiData<T> for TextAreaData
+where
+    T: AppState,
+{
+    fn custom_render(
+        &self,
+        _id: &NodeId,
+        ctx: &NodeContext<T>,
+        layout: &Layout,
+        renderer: &mut Renderer<T>,
+        bbox: Rect<f32>,
+    ) {
+        let size = Size {
+            width: layout.size.width - layout.padding.left - layout.padding.right,
+            height: layout.size.height - layout.padding.top - layout.padding.bottom,
+        };
+        self.text_size.set(size);
+        let origin = Vector::new(
+            bbox.x0.x + layout.padding.left,
+            bbox.x0.y + layout.padding.top - self.scroll_offset,
+        );
+        let focused = ctx.persistent_id.is_some() && renderer.focused == ctx.persistent_id;
+        let lines = wrap_lines(&mut renderer.text_r, &self.contents, size.width);
+        let selection = self.selection();
+
+        for (i, line) in lines.iter().enumerate() {
+            let top = origin.y + i as f32 * LINE_ADVANCE;
+            if top + LINE_ADVANCE < bbox.x0.y || top > bbox.x1.y {
+                continue;
+            }
+            let text = &self.contents[line.clone()];
+
+            if let Some(selection) = &selection
+                && selection.start <= line.end
+                && line.start < selection.end
+            {
+                let start = selection.start.max(line.start) - line.start;
+                let end = selection.end.min(line.end) - line.start;
+                let x0 = line_x(&mut renderer.text_r, text, start);
+                // Selected line breaks are shown as a bit of extra highlight
+                let x1 = line_x(&mut renderer.text_r, text, end)
+                    + if selection.end > line.end { 4.0 } else { 0.0 };
+                renderer.rect_r.draw(
+                    Rect {
+                        x0: Vector::new(origin.x + x0, top),
+                        x1: Vector::new(origin.x + x1, top + LINE_ADVANCE),
+                    },
+                    COLOR_PRIMARY.faded(0.5),
+                    Border::default(),
+                    1.0,
+                );
+            }
+
+            if !text.is_empty() {
+                renderer.text_r.draw_on_line(
+                    Text::new(text, FONT_SIZE, COLOR_LIGHT),
+                    Vector::new(origin.x, top),
+                    Size {
+                        width: size.width,
+                        height: LINE_ADVANCE,
+                    },
+                    None,
+                );
+            }
+
+            if focused && line_index(&lines, self.cursor_pos) == i {
+                let x = line_x(&mut renderer.text_r, text, self.cursor_pos - line.start);
+                renderer.rect_r.draw(
+                    Rect {
+                        x0: Vector::new(origin.x + x, top),
+                        x1: Vector::new(origin.x + x + 1.0, top + LINE_ADVANCE),
+                    },
+                    COLOR_LIGHT,
+                    Border::default(),
+                    1.0,
+                );
+            }
+        }
+    }
+}
+
+impl TextAreaData {
+    /// The selected bytes of [Self::contents], if any
+    pub fn selection(&self) -> Option<Range<usize>> {
+        let select_pos = self.select_pos?;
+        match select_pos.cmp(&self.cursor_pos) {
+            std::cmp::Ordering::Less => Some(select_pos..self.cursor_pos),
+            std::cmp::Ordering::Equal => None,
+            std::cmp::Ordering::Greater => Some(self.cursor_pos..select_pos),
+        }
+    }
+
+    /// Moves the cursor to the byte index `pos`, extending the selection if `select` is set and
+    /// dropping it otherwise
+    pub fn move_to(&mut self, pos: usize, select: bool) {
+        if !select {
+            self.select_pos = None;
+        } else if self.select_pos.is_none() {
+            self.select_pos = Some(self.cursor_pos);
+        }
+        self.cursor_pos = pos.min(self.contents.len());
+    }
+
+    /// Removes the selected text. Returns false if nothing was selected.
+    pub fn delete_selection(&mut self) -> bool {
+        match self.selection() {
+            Some(selection) => {
+                self.contents.replace_range(selection.clone(), "");
+                self.cursor_pos = selection.start;
+                self.select_pos = None;
+                true
+            }
+            None => {
+                self.select_pos = None;
+                false
+            }
+        }
+    }
+
+    /// Inserts `ch` at the cursor, replacing the selection
+    pub fn write(&mut self, ch: char) {
+        self.delete_selection();
+        self.contents.insert(self.cursor_pos, ch);
+        self.cursor_pos += ch.len_utf8();
+    }
+
+    /// Inserts `text` at the cursor, replacing the selection
+    pub fn paste(&mut self, text: &str) {
+        self.delete_selection();
+        let text = text.replace("\r\n", "\n");
+        self.contents.insert_str(self.cursor_pos, &text);
+        self.cursor_pos += text.len();
+    }
+
+    /// Removes the selection or the character before the cursor, joining lines at a line break
+    pub fn delete_char(&mut self) {
+        if !self.delete_selection() && self.cursor_pos > 0 {
+            let start = self.prev_boundary(self.cursor_pos);
+            self.contents.replace_range(start..self.cursor_pos, "");
+            self.cursor_pos = start;
+        }
+    }
+
+    /// Removes the selection or the character after the cursor
+    pub fn delete_char_forward(&mut self) {
+        if !self.delete_selection() && self.cursor_pos < self.contents.len() {
+            let end = self.next_boundary(self.cursor_pos);
+            self.contents.replace_range(self.cursor_pos..end, "");
+        }
+    }
+
+    /// Handles editing, navigation and clipboard keys. Lines are wrapped with `text_r` to move
+    /// between them. Returns false for keys the text area doesn't use, so they can reach the app.
+    pub fn handle_key(
+        &mut self,
+        text_r: &mut TextRenderer,
+        clipboard: &mut dyn Clipboard,
+        key: Key,
+        modifiers: Modifiers,
+    ) -> bool {
+        let select = modifiers.contains(Modifiers::Shift);
+        let lines = wrap_lines(text_r, &self.contents, self.text_size.get().width);
+        let line = lines[line_index(&lines, self.cursor_pos)].clone();
+        match key {
+            Key::Enter | Key::KpEnter => self.write('\n'),
+            Key::Backspace => self.delete_char(),
+            Key::Delete => self.delete_char_forward(),
+            Key::Left => match self.selection() {
+                Some(selection) if !select => self.move_to(selection.start, false),
+                _ => self.move_to(self.prev_boundary(self.cursor_pos), select),
+            },
+            Key::Right => match self.selection() {
+                Some(selection) if !select => self.move_to(selection.end, false),
+                _ => self.move_to(self.next_boundary(self.cursor_pos), select),
+            },
+            Key::Up => self.move_vertical(text_r, &lines, -1, select),
+            Key::Down => self.move_vertical(text_r, &lines, 1, select),
+            Key::Home => self.move_to(line.start, select),
+            Key::End => {
+                let end = if self.is_wrapped(&line) {
+                    self.prev_boundary(line.end)
+                } else {
+                    line.end
+                };
+                self.move_to(end, select);
+            }
+            Key::A if modifiers.contains(Modifiers::Control) => {
+                self.select_pos = Some(0);
+                self.cursor_pos = self.contents.len();
+            }
+            Key::C if modifiers.contains(Modifiers::Control) => {
+                if let Some(selection) = self.selection() {
+                    clipboard.set(&self.contents[selection]);
+                }
+            }
+            Key::X if modifiers.contains(Modifiers::Control) => {
+                if let Some(selection) = self.selection() {
+                    clipboard.set(&self.contents[selection]);
+                    self.delete_selection();
+                }
+            }
+            Key::V if modifiers.contains(Modifiers::Control) => {
+                if let Some(text) = clipboard.get() {
+                    self.paste(&text);
+                }
+            }
+            // The character itself arrives through on_char
+            _ => return types_char(key, modifiers),
+        }
+        self.scroll_to_cursor(text_r);
+        true
+    }
+
+    /// Scrolls just far enough for the line with the cursor to be visible
+    pub fn scroll_to_cursor(&mut self, text_r: &mut TextRenderer) {
+        let size = self.text_size.get();
+        let lines = wrap_lines(text_r, &self.contents, size.width);
+        let top = line_index(&lines, self.cursor_pos) as f32 * LINE_ADVANCE;
+        if top < self.scroll_offset {
+            self.scroll_offset = top;
+        } else if top + LINE_ADVANCE > self.scroll_offset + size.height {
+            self.scroll_offset = top + LINE_ADVANCE - size.height;
+        }
+        self.clamp_scroll(lines.len());
+    }
+
+    fn clamp_scroll(&mut self, line_count: usize) {
+        let max = (line_count as f32 * LINE_ADVANCE - self.text_size.get().height).max(0.0);
+        self.scroll_offset = self.scroll_offset.clamp(0.0, max);
+    }
+
+    /// Moves the cursor `delta` wrapped lines up or down, keeping it as close as possible to its
+    /// current horizontal position
+    fn move_vertical(
+        &mut self,
+        text_r: &mut TextRenderer,
+        lines: &[Range<usize>],
+        delta: isize,
+        select: bool,
+    ) {
+        let current = line_index(lines, self.cursor_pos);
+        let target = match current.checked_add_signed(delta) {
+            Some(target) if target < lines.len() => target,
+            Some(_) => {
+                self.move_to(self.contents.len(), select);
+                return;
+            }
+            None => {
+                self.move_to(0, select);
+                return;
+            }
+        };
+        let from = lines[current].clone();
+        let x = line_x(
+            text_r,
+            &self.contents[from.clone()],
+            self.cursor_pos - from.start,
+        );
+
+        let to = lines[target].clone();
+        let text = &self.contents[to.clone()];
+        // The end of a wrapped line is the same position as the start of the next one
+        let candidates = text
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain((!self.is_wrapped(&to)).then_some(text.len()));
+        let mut best = (0, f32::INFINITY);
+        for idx in candidates {
+            let distance = (line_x(text_r, text, idx) - x).abs();
+            if distance < best.1 {
+                best = (idx, distance);
+            }
+        }
+        self.move_to(to.start + best.0, select);
+    }
+
+    /// True if `line` continues on the next line without a line break
+    fn is_wrapped(&self, line: &Range<usize>) -> bool {
+        line.end < self.contents.len() && !self.contents[line.end..].starts_with('\n')
+    }
+
+    fn prev_boundary(&self, pos: usize) -> usize {
+        self.contents[..pos]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(i, _)| i)
+    }
+
+    fn next_boundary(&self, pos: usize) -> usize {
+        self.contents[pos..]
+            .chars()
+            .next()
+            .map_or(pos, |ch| pos + ch.len_utf8())
+    }
+}
+
+/// Byte ranges of `text` for each line after wrapping it to `width`, excluding line breaks
+fn wrap_lines(text_r: &mut TextRenderer, text: &str, width: f32) -> Vec<Range<usize>> {
+    let font_id = text_r.font_id(None);
+    let mut out = vec![];
+    let mut start = 0;
+    for explicit_line in text.split('\n') {
+        let mut line_start = start;
+        let wrapped = text_r.layout_text_explicit(
+            Size {
+                width: AvailableSpace::Definite(width),
+                height: AvailableSpace::MaxContent,
+            },
+            explicit_line.to_string(),
+            font_id,
+            FONT_SIZE,
+            LINE_HEIGHT,
+            TextAlignment::Left,
+        );
+        if wrapped.is_empty() {
+            out.push(start..start);
+        }
+        for line in wrapped {
+            out.push(line_start..line_start + line.contents().len());
+            line_start += line.contents().len();
+        }
+        start += explicit_line.len() + 1;
+    }
+    out
+}
+
+/// Index of the line the byte index `pos` is on. Positions between two wrapped lines belong to
+/// the latter.
+fn line_index(lines: &[Range<usize>], pos: usize) -> usize {
+    lines
+        .iter()
+        .rposition(|line| line.start <= pos)
+        .unwrap_or(0)
+}
+
+/// Horizontal distance from the start of `line` to the byte index `idx`
+fn line_x(text_r: &mut TextRenderer, line: &str, idx: usize) -> f32 {
+    let font_id = text_r.font_id(None);
+    // The glyph positions are indexed per character
+    let idx = if idx == line.len() {
+        idx
+    } else {
+        line[..idx].chars().count()
+    };
+    text_r
+        .cursor_pos(line, Vector::zero(), font_id, FONT_SIZE, idx)
+        .x
+}
+
+pub trait TextAreaBuilder<T>
+where
+    T: AppState,
+{
+    /// Text areas are multi line text inputs which scroll vertically once the text outgrows them.
+    /// `style` is applied to the box, which is where the size of the text area is set. The text
+    /// is kept in [TextAreaData].
+    fn text_area(&self, id: DefaultAtom, focused_id: &Option<DefaultAtom>, style: &str) -> NodeId;
+}
+
+impl<T> TextAreaBuilder<T> for UiBuilder<T>
+where
+    T: AppState,
+{
+    fn text_area(&self, id: DefaultAtom, focused_id: &Option<DefaultAtom>, style: &str) -> NodeId {
+        if self.accessing_state(&id).is_none() {
+            self.insert_state(id.clone(), TextAreaData::default());
+        }
+
+        let style = if Some(&id) == focused_id.as_ref() {
+            format!(
+                "bg-slate-900 p-2 rounded-4 border-2 border-sky-500 overflow-clip {}",
+                style
+            )
+        } else {
+            format!(
+                "bg-slate-900 hover:bg-slate-800 p-2 rounded-4 overflow-clip {}",
+                style
+            )
+        };
+        let (style, mut context) = parse_style(&style);
+        context.flags |= flags::FOCUSABLE;
+        context.persistent_id = Some(id.clone());
+        let key_id = id.clone();
+        let char_id = id.clone();
+        context.set_listeners(Listeners {
+            on_key: Some(Arc::new(
+                move |renderer: &mut Renderer<T>, key, action, modifiers| {
+                    if action == Action::Release {
+                        return false;
+                    }
+                    let text_r = &mut renderer.text_r;
+                    let clipboard = renderer.clipboard.as_mut();
+                    renderer
+                        .ui_builder
+                        .mutate_state(&key_id, |ui_data: &mut dyn UiData<T>| {
+                            let d: &mut TextAreaData = ui_data.downcast_mut().unwrap();
+                            d.handle_key(text_r, clipboard, key, modifiers)
+                        })
+                        .unwrap_or(false)
+                },
+            )),
+            on_char: Some(Arc::new(move |renderer: &mut Renderer<T>, ch| {
+                if ch.is_control() {
+                    return false;
+                }
+                let text_r = &mut renderer.text_r;
+                renderer
+                    .ui_builder
+                    .mutate_state(&char_id, |ui_data: &mut dyn UiData<T>| {
+                        let d: &mut TextAreaData = ui_data.downcast_mut().unwrap();
+                        d.write(ch);
+                        d.scroll_to_cursor(text_r);
+                    });
+                true
+            })),
+            on_scroll: Some(Arc::new(move |renderer: &mut Renderer<T>| {
+                let delta = renderer.scroll_delta.y.signum() * 3.0 * LINE_ADVANCE;
+                let text_r = &mut renderer.text_r;
+                renderer
+                    .ui_builder
+                    .mutate_state(&id, |ui_data: &mut dyn UiData<T>| {
+                        let d: &mut TextAreaData = ui_data.downcast_mut().unwrap();
+                        let lines = wrap_lines(text_r, &d.contents, d.text_size.get().width);
+                        d.scroll_offset -= delta;
+                        d.clamp_scroll(lines.len());
+                    });
+            })),
+            ..Default::default()
+        });
+        let mut tree = self.tree.borrow_mut();
+        self.new_leaf_with_context(&mut tree, style, context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn editing_across_line_breaks() {
+        let mut data = TextAreaData {
+            contents: "ab\ncd".into(),
+            cursor_pos: 3,
+            ..Default::default()
+        };
+        data.delete_char();
+        assert_eq!(data.contents, "abcd");
+        assert_eq!(data.cursor_pos, 2);
+
+        data.write('\n');
+        data.move_to(1, false);
+        data.move_to(4, true);
+        assert_eq!(data.selection(), Some(1..4));
+        data.write('x');
+        assert_eq!(data.contents, "axd");
+        assert_eq!(data.cursor_pos, 2);
+    }
+
+    #[test]
+    fn positions_between_lines_belong_to_the_next() {
+        let lines = [0..4, 4..6, 7..7];
+        assert_eq!(line_index(&lines, 0), 0);
+        assert_eq!(line_index(&lines, 4), 1);
+        assert_eq!(line_index(&lines, 6), 1);
+        assert_eq!(line_index(&lines, 7), 2);
+    }
+}
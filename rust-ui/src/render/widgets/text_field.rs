@@ -1,10 +1,18 @@
+use std::cmp::Ordering;
+use std::ops::Range;
 use std::sync::Arc;
 
-use crate::render::renderer::{flags, AppState, Listeners, Renderer};
+use crate::clipboard::Clipboard;
+use crate::geometry::{Rect, Vector};
+use crate::input::types_char;
+use crate::render::renderer::{flags, AppState, Listeners, NodeContext, Renderer};
 use crate::render::widgets::{DefaultAtom, UiBuilder, UiData};
-use crate::render::{Text, COLOR_LIGHT};
+use crate::render::{Border, Text, COLOR_LIGHT, COLOR_PRIMARY};
 use crate::style::parse_style;
-use taffy::NodeId;
+use glfw::{Action, Key, Modifiers};
+use taffy::{Layout, NodeId};
+
+const FONT_SIZE: u32 = 12;
 
 pub struct TextFieldData<T>
 where
@@ -12,6 +20,8 @@ where
 {
     pub contents: String,
     pub cursor_pos: usize,
+    /// The other end of the selection, which spans from here to the cursor. Equal to the cursor
+    /// position when nothing is selected.
     pub select_pos: usize,
     pub on_confirm: EventListener<T>,
 }
@@ -65,23 +75,118 @@ where
     T: AppState,
 {
     pub fn move_cursor(&mut self, arg: isize) {
+        self.extend_selection(arg);
+        self.select_pos = self.cursor_pos;
+    }
+
+    /// Moves the cursor while keeping the other end of the selection in place
+    pub fn extend_selection(&mut self, arg: isize) {
         self.cursor_pos = self
             .cursor_pos
             .saturating_add_signed(arg)
             .clamp(0, self.contents.len());
     }
 
+    pub fn select_all(&mut self) {
+        self.select_pos = 0;
+        self.cursor_pos = self.contents.len();
+    }
+
+    pub fn selection(&self) -> Option<Range<usize>> {
+        match self.select_pos.cmp(&self.cursor_pos) {
+            Ordering::Less => Some(self.select_pos..self.cursor_pos),
+            Ordering::Equal => None,
+            Ordering::Greater => Some(self.cursor_pos..self.select_pos),
+        }
+    }
+
+    pub fn selected_text(&self) -> Option<&str> {
+        self.selection().map(|selection| &self.contents[selection])
+    }
+
+    /// Removes the selected text. Returns false if nothing was selected.
+    pub fn delete_selection(&mut self) -> bool {
+        match self.selection() {
+            Some(selection) => {
+                self.contents.replace_range(selection.clone(), "");
+                self.cursor_pos = selection.start;
+                self.select_pos = selection.start;
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn write(&mut self, ch: char) {
+        self.delete_selection();
         self.contents.insert(self.cursor_pos, ch);
         self.move_cursor(1);
     }
 
+    /// Inserts `text` at the cursor, replacing the selection. Line breaks become spaces since
+    /// text fields only have one line.
+    pub fn paste(&mut self, text: &str) {
+        self.delete_selection();
+        let text = text.replace("\r\n", " ").replace(['\r', '\n'], " ");
+        self.contents.insert_str(self.cursor_pos, &text);
+        self.move_cursor(text.len() as isize);
+    }
+
     pub fn delete_char(&mut self) {
-        if !self.contents.is_empty() {
+        if !self.delete_selection() && self.cursor_pos > 0 {
             self.contents.remove(self.cursor_pos - 1);
             self.move_cursor(-1)
         }
     }
+
+    /// Edits the text for the keys a text field owns: arrows, backspace, the clipboard shortcuts
+    /// and keys that type a character. Returns false for other keys so that they can reach the
+    /// app, like Enter to confirm the field.
+    pub fn handle_key(
+        &mut self,
+        clipboard: &mut dyn Clipboard,
+        key: Key,
+        modifiers: Modifiers,
+    ) -> bool {
+        let select = modifiers.contains(Modifiers::Shift);
+        match key {
+            Key::Left if select => self.extend_selection(-1),
+            Key::Right if select => self.extend_selection(1),
+            Key::Left => self.move_cursor(-1),
+            Key::Right => self.move_cursor(1),
+            Key::Backspace => self.delete_char(),
+            Key::C | Key::X | Key::V | Key::A if modifiers.contains(Modifiers::Control) => {
+                self.handle_clipboard_key(clipboard, key)
+            }
+            // The character itself arrives through on_char
+            _ => return types_char(key, modifiers),
+        }
+        true
+    }
+
+    /// Handles Ctrl+C, Ctrl+X, Ctrl+V and Ctrl+A
+    pub fn handle_clipboard_key(&mut self, clipboard: &mut dyn Clipboard, key: Key) {
+        match key {
+            Key::C => {
+                if let Some(text) = self.selected_text() {
+                    clipboard.set(text);
+                }
+            }
+            Key::X => {
+                if let Some(text) = self.selected_text() {
+                    clipboard.set(text);
+                    self.delete_selection();
+                }
+            }
+            Key::V => {
+                if let Some(text) = clipboard.get() {
+                    self.paste(&text);
+                }
+            }
+            Key::A => self.select_all(),
+            _ => {}
+        }
+    }
 }
 impl<T> UiData<T> for TextFieldData<T>
 where
@@ -94,6 +199,48 @@ where
             }
         }
     }
+
+    /// Highlights the selection behind the text, which is drawn by the inner text node
+    fn custom_render(
+        &self,
+        _id: &NodeId,
+        ctx: &NodeContext<T>,
+        layout: &Layout,
+        renderer: &mut Renderer<T>,
+        bbox: Rect<f32>,
+    ) {
+        let Some(selection) = self.selection() else {
+            return;
+        };
+        if ctx.persistent_id.is_none() || renderer.focused != ctx.persistent_id {
+            return;
+        }
+        let font_id = renderer.text_r.font_id(None);
+        let mut x_of = |idx| {
+            renderer
+                .text_r
+                .cursor_pos(&self.contents, Vector::zero(), font_id, FONT_SIZE, idx)
+                .x
+        };
+        // Follows the scrolling of text that doesn't fit, see [flags::TEXT_SCROLL]
+        let cursor_x = x_of(self.cursor_pos);
+        let scroll = if cursor_x > layout.size.width {
+            layout.size.width - cursor_x - 10.0
+        } else {
+            0.0
+        };
+        let x = bbox.x0.x + layout.padding.left + scroll;
+        let (x0, x1) = (x_of(selection.start), x_of(selection.end));
+        renderer.rect_r.draw(
+            Rect {
+                x0: Vector::new(x + x0, bbox.x0.y + layout.padding.top),
+                x1: Vector::new(x + x1, bbox.x1.y - layout.padding.bottom),
+            },
+            COLOR_PRIMARY.faded(0.5),
+            Border::default(),
+            1.0,
+        );
+    }
 }
 
 pub type EventListener<T> = Option<Arc<dyn Fn(&mut T, &TextFieldData<T>)>>;
@@ -130,7 +277,7 @@ where
         state.on_confirm = on_confirm;
 
         let (style, mut context) = parse_style("");
-        context.text = Text::new(state.contents.clone(), 12, COLOR_LIGHT);
+        context.text = Text::new(state.contents.clone(), FONT_SIZE, COLOR_LIGHT);
         context.flags |= flags::TEXT | flags::TEXT_SCROLL | flags::TEXT_SINGLE_LINE;
         context.cursor_idx = if Some(&id) == focused_id.as_ref() {
             Some(state.cursor_pos)
@@ -147,15 +294,93 @@ where
         } else {
             "bg-slate-900 hover:bg-slate-800 w-200 p-2 rounded-4"
         };
-        self.ui(
-            style,
-            Listeners {
-                on_left_mouse_down: Some(Arc::new(move |state: &mut Renderer<T>| {
-                    state.set_focus(Some(id.clone()));
-                })),
-                ..Default::default()
-            },
-            &[inner_text],
-        )
+        let (style, mut context) = parse_style(style);
+        context.flags |= flags::FOCUSABLE;
+        context.persistent_id = Some(id.clone());
+        let key_id = id.clone();
+        context.set_listeners(Listeners {
+            on_key: Some(Arc::new(
+                move |renderer: &mut Renderer<T>, key, action, modifiers| {
+                    if action == Action::Release {
+                        return false;
+                    }
+                    let clipboard = renderer.clipboard.as_mut();
+                    renderer
+                        .ui_builder
+                        .mutate_state(&key_id, |ui_data: &mut dyn UiData<T>| {
+                            let d: &mut TextFieldData<T> = ui_data.downcast_mut().unwrap();
+                            d.handle_key(clipboard, key, modifiers)
+                        })
+                        .unwrap_or(false)
+                },
+            )),
+            on_char: Some(Arc::new(move |renderer: &mut Renderer<T>, ch| {
+                if ch.is_control() {
+                    return false;
+                }
+                renderer
+                    .ui_builder
+                    .mutate_state(&id, |ui_data: &mut dyn UiData<T>| {
+                        let d: &mut TextFieldData<T> = ui_data.downcast_mut().unwrap();
+                        d.write(ch);
+                    });
+                true
+            })),
+            ..Default::default()
+        });
+        let mut tree = self.tree.borrow_mut();
+        let outer = self.new_leaf_with_context(&mut tree, style, context);
+        tree.add_child(outer, inner_text).unwrap();
+        outer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clipboard::LocalClipboard;
+    use crate::render::renderer::tests::DummyState;
+
+    #[test]
+    fn cut_and_paste_selection() {
+        let mut clipboard = LocalClipboard::default();
+        let mut data = TextFieldData::<DummyState> {
+            contents: "hello world".into(),
+            cursor_pos: 11,
+            select_pos: 11,
+            ..Default::default()
+        };
+        data.extend_selection(-6);
+        assert_eq!(data.selected_text(), Some(" world"));
+        data.handle_clipboard_key(&mut clipboard, Key::X);
+        assert_eq!(data.contents, "hello");
+
+        data.move_cursor(-5);
+        data.handle_clipboard_key(&mut clipboard, Key::V);
+        assert_eq!(data.contents, " worldhello");
+        assert_eq!(data.cursor_pos, 6);
+        assert_eq!(data.selection(), None);
+    }
+
+    #[test]
+    fn owns_typing_but_not_confirm() {
+        let mut clipboard = LocalClipboard::default();
+        let mut data = TextFieldData::<DummyState> {
+            contents: "abc".into(),
+            cursor_pos: 3,
+            select_pos: 3,
+            ..Default::default()
+        };
+        assert!(data.handle_key(&mut clipboard, Key::Left, Modifiers::empty()));
+        assert!(data.handle_key(&mut clipboard, Key::Backspace, Modifiers::empty()));
+        assert_eq!(data.contents, "ac");
+        assert!(data.handle_key(&mut clipboard, Key::Left, Modifiers::Shift));
+        assert_eq!(data.selected_text(), Some("a"));
+
+        // Typed characters are inserted by on_char, but the key press is still claimed
+        assert!(data.handle_key(&mut clipboard, Key::A, Modifiers::empty()));
+        assert_eq!(data.contents, "ac");
+        assert!(!data.handle_key(&mut clipboard, Key::Enter, Modifiers::empty()));
+        assert!(!data.handle_key(&mut clipboard, Key::S, Modifiers::Control));
     }
 }
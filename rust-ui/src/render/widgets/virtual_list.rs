@@ -0,0 +1,174 @@
+use std::cell::Cell;
+use std::sync::Arc;
+
+use crate::geometry::Rect;
+use crate::render::renderer::{AppState, Listeners, NodeContext, Renderer};
+use crate::render::widgets::{DefaultAtom, UiBuilder, UiData};
+use crate::style::parse_style;
+use taffy::prelude::length;
+use taffy::{Layout, NodeId};
+
+/// Number of rows built before the list has been laid out once and its height is known
+const UNMEASURED_ROWS: usize = 50;
+
+#[derive(Debug, Clone)]
+pub struct VirtualListData {
+    /// Distance in pixels from the top of the first row to the top of the viewport
+    pub scroll_offset: f32,
+    /// How many rows to scroll per wheel tick
+    pub scroll_rows: f32,
+    /// Height of the list as of the last frame. Measured while rendering since the layout isn't
+    /// known while the rows are being built
+    viewport_height: Cell<f32>,
+}
+
+impl Default for VirtualListData {
+    fn default() -> Self {
+        Self {
+            scroll_offset: 0.0,
+            scroll_rows: 3.0,
+            viewport_height: Cell::new(0.0),
+        }
+    }
+}
+
+impl<T> UiData<T> for VirtualListData
+where
+    T: AppState,
+{
+    fn custom_render(
+        &self,
+        _id: &NodeId,
+        _ctx: &NodeContext<T>,
+        layout: &Layout,
+        _renderer: &mut Renderer<T>,
+        _bbox: Rect<f32>,
+    ) {
+        self.viewport_height.set(layout.size.height);
+    }
+}
+
+impl VirtualListData {
+    /// The range of rows intersecting the viewport. Empty when `row_height` isn't positive
+    pub fn visible_rows(&self, item_count: usize, row_height: f32) -> std::ops::Range<usize> {
+        if row_height <= 0.0 {
+            return 0..0;
+        }
+        let viewport = self.viewport_height.get();
+        let first = ((self.scroll_offset / row_height).floor() as usize).min(item_count);
+        let visible = if viewport > 0.0 {
+            (viewport / row_height).ceil() as usize + 1
+        } else {
+            UNMEASURED_ROWS
+        };
+        first..(first + visible).min(item_count)
+    }
+
+    fn max_offset(&self, item_count: usize, row_height: f32) -> f32 {
+        (item_count as f32 * row_height - self.viewport_height.get()).max(0.0)
+    }
+}
+
+pub trait VirtualListBuilder {
+    /// A vertical list of `item_count` rows of `row_height` pixels where only the rows currently
+    /// scrolled into view are built by `row`. Rows are forced to `row_height` pixels.
+    fn virtual_list(
+        &self,
+        id: DefaultAtom,
+        style: &str,
+        item_count: usize,
+        row_height: f32,
+        row: impl Fn(usize) -> NodeId,
+    ) -> NodeId;
+}
+
+impl<T> VirtualListBuilder for UiBuilder<T>
+where
+    T: AppState,
+{
+    fn virtual_list(
+        &self,
+        id: DefaultAtom,
+        style: &str,
+        item_count: usize,
+        row_height: f32,
+        row: impl Fn(usize) -> NodeId,
+    ) -> NodeId {
+        let state = match self.accessing_state(&id) {
+            Some(s) => s,
+            None => self.insert_state(id.clone(), VirtualListData::default()),
+        };
+        let mut guard = state.data.lock().unwrap();
+        let data: &mut VirtualListData = guard.downcast_mut().unwrap();
+        // The item count may have shrunk since the last frame
+        data.scroll_offset = data
+            .scroll_offset
+            .clamp(0.0, data.max_offset(item_count, row_height));
+        let scroll_offset = data.scroll_offset;
+        let scroll_step = data.scroll_rows * row_height;
+        let visible = data.visible_rows(item_count, row_height);
+        drop(guard);
+
+        let rows: Vec<NodeId> = visible
+            .clone()
+            .map(|i| {
+                let node = row(i);
+                self.mutate_style(node, |style| {
+                    style.size.height = length(row_height);
+                    style.flex_shrink = 0.0;
+                });
+                node
+            })
+            .collect();
+
+        let content = {
+            let (stl, mut ctx) = parse_style::<T>("flex-col w-full");
+            ctx.offset.y = visible.start as f32 * row_height - scroll_offset;
+            let mut tree = self.tree.borrow_mut();
+            let content = self.new_leaf_with_context(&mut tree, stl, ctx);
+            for r in rows {
+                tree.add_child(content, r).unwrap();
+            }
+            content
+        };
+
+        let (stl, mut ctx) = parse_style(&format!("flex-col overflow-clip min-h-0 {}", style));
+        ctx.persistent_id = Some(id.clone());
+        ctx.set_listeners(Listeners {
+            on_scroll: Some(Arc::new(move |renderer: &mut Renderer<T>| {
+                let delta = renderer.scroll_delta.y.signum() * scroll_step;
+                renderer
+                    .ui_builder
+                    .mutate_state(&id, |ui_data: &mut dyn UiData<T>| {
+                        let d: &mut VirtualListData = ui_data.downcast_mut().unwrap();
+                        let max = d.max_offset(item_count, row_height);
+                        d.scroll_offset = (d.scroll_offset - delta).clamp(0.0, max);
+                    });
+            })),
+            ..Default::default()
+        });
+        let mut tree = self.tree.borrow_mut();
+        let outer = self.new_leaf_with_context(&mut tree, stl, ctx);
+        tree.add_child(outer, content).unwrap();
+        outer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visible_rows_follow_scroll_offset() {
+        let data = VirtualListData {
+            scroll_offset: 250.0,
+            ..Default::default()
+        };
+        data.viewport_height.set(100.0);
+        assert_eq!(data.visible_rows(10_000, 20.0), 12..18);
+        assert_eq!(data.visible_rows(15, 20.0), 12..15);
+        assert_eq!(data.max_offset(10, 20.0), 100.0);
+        assert_eq!(data.visible_rows(10_000, 0.0), 0..0);
+        assert_eq!(data.visible_rows(10_000, -5.0), 0..0);
+    }
+}
@@ -86,6 +86,7 @@ pub enum ShaderName {
     Line,
     Text,
     Rect,
+    RectInstanced,
     Mesh,
     Sprite,
     Pick,
@@ -99,6 +100,7 @@ impl ShaderName {
             ShaderName::Line => Self::to_paths("line"),
             ShaderName::Text => Self::to_paths("text"),
             ShaderName::Rect => Self::to_paths("rounded_rect"),
+            ShaderName::RectInstanced => Self::to_paths("rounded_rect_instanced"),
             ShaderName::Mesh => Self::to_paths("mesh"),
             ShaderName::Sprite => Self::to_paths("sprite"),
             ShaderName::Pick => Self::to_paths("picking"),
@@ -118,6 +120,7 @@ impl ShaderName {
             ShaderName::Line,
             ShaderName::Text,
             ShaderName::Rect,
+            ShaderName::RectInstanced,
             ShaderName::Mesh,
             ShaderName::Sprite,
             ShaderName::Pick,
@@ -138,11 +141,17 @@ pub enum ShaderType {
 #[derive(Debug, Clone, Copy)]
 pub struct Shader {
     id: u32,
+    /// Where the sources came from if loaded through [Shader::new_from_name], used by
+    /// [Shader::reload]
+    name: Option<ShaderName>,
 }
 
 impl Shader {
     pub fn empty() -> Self {
-        Self { id: u32::MAX }
+        Self {
+            id: u32::MAX,
+            name: None,
+        }
     }
     pub fn use_shader(&self) {
         unsafe {
@@ -184,7 +193,10 @@ impl Shader {
             }
             gl::LinkProgram(program);
 
-            let shader = Shader { id: program };
+            let shader = Shader {
+                id: program,
+                name: None,
+            };
 
             success = success && check_compile_errors(shader.id, ShaderType::Program);
 
@@ -195,6 +207,7 @@ impl Shader {
             }
 
             if !success {
+                gl::DeleteProgram(program);
                 return Err(anyhow!("Couldn't compile or link shader"));
             }
 
@@ -222,7 +235,43 @@ impl Shader {
     /// Wrapper for [Self::compile_shader]
     pub fn new_from_name(name: &ShaderName) -> Result<Self> {
         let (v, f) = name.paths();
-        Self::from_paths(&v, &f, None)
+        let mut shader = Self::from_paths(&v, &f, None)?;
+        shader.name = Some(*name);
+        Ok(shader)
+    }
+
+    /// Where the sources came from, see [Shader::reload]
+    pub fn name(&self) -> Option<ShaderName> {
+        self.name
+    }
+
+    /// Re-reads the source files and relinks them into the same program, so every copy of this
+    /// shader held by the renderers picks up the change. The new sources are compiled and linked
+    /// into a separate program first, so if they contain errors the error is returned and the
+    /// previous program keeps working. Uniforms are reset by the relink and have to be set again.
+    pub fn reload(&self) -> Result<()> {
+        let Some(name) = self.name else {
+            return Err(anyhow!("Only shaders loaded by name can be reloaded"));
+        };
+        let fresh = Self::new_from_name(&name)?;
+        unsafe {
+            let mut count = 0;
+            let mut attached = [0u32; 3];
+            gl::GetAttachedShaders(self.id, 3, &mut count, attached.as_mut_ptr());
+            for s in &attached[..count as usize] {
+                gl::DetachShader(self.id, *s);
+            }
+            gl::GetAttachedShaders(fresh.id, 3, &mut count, attached.as_mut_ptr());
+            for s in &attached[..count as usize] {
+                gl::AttachShader(self.id, *s);
+            }
+            gl::LinkProgram(self.id);
+            gl::DeleteProgram(fresh.id);
+        }
+        if !check_compile_errors(self.id, ShaderType::Program) {
+            return Err(anyhow!("Couldn't relink shader {:?}", name));
+        }
+        Ok(())
     }
 
     fn find(&self, name: &str) -> gl::types::GLint {
@@ -311,4 +360,14 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn reload_keeps_program_id() {
+        let _window = init_window();
+        let shader = Shader::new_from_name(&ShaderName::Rect).unwrap();
+        let id = shader.id;
+        shader.reload().unwrap();
+        assert_eq!(shader.id, id);
+        assert!(Shader::empty().reload().is_err());
+    }
 }
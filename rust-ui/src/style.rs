@@ -1,14 +1,19 @@
 use std::{
     cell::LazyCell,
     sync::{LazyLock, RwLock},
+    time::Duration,
 };
 
 use taffy::{Dimension, FlexDirection, Style};
 use tracing::error;
 
-use crate::render::{
-    BorderRadius, Color,
-    renderer::{AppState, NodeContext, flags},
+use crate::{
+    easing::Easing,
+    render::{
+        Background, BorderRadius, Color,
+        animation::TransitionProperty,
+        renderer::{AppState, NodeContext, flags},
+    },
 };
 
 const POSSIBLE_PARAMETERS: LazyCell<Vec<&str>> = LazyCell::new(|| {
@@ -16,6 +21,14 @@ const POSSIBLE_PARAMETERS: LazyCell<Vec<&str>> = LazyCell::new(|| {
         "bg",
         "hover:bg",
         "rounded",
+        "rounded-tl",
+        "rounded-tr",
+        "rounded-bl",
+        "rounded-br",
+        "rounded-t",
+        "rounded-b",
+        "rounded-l",
+        "rounded-r",
         "border",
         "border",
         "text",
@@ -84,6 +97,29 @@ const POSSIBLE_PARAMETERS: LazyCell<Vec<&str>> = LazyCell::new(|| {
         "overflow-clip",
         "scroll-bar",
         "scroll-content",
+        "shadow",
+        "shadow-x",
+        "shadow-y",
+        "outline",
+        "sprite-tint",
+        "bg-gradient-to-r",
+        "bg-gradient-to-l",
+        "bg-gradient-to-t",
+        "bg-gradient-to-b",
+        "bg-gradient-to-tr",
+        "bg-gradient-to-tl",
+        "bg-gradient-to-br",
+        "bg-gradient-to-bl",
+        "from",
+        "to",
+        "transition",
+        "transition-colors",
+        "transition-opacity",
+        "duration",
+        "ease-linear",
+        "ease-in",
+        "ease-out",
+        "ease-in-out",
     ];
     out.sort_by(|a, b| b.len().cmp(&a.len()));
     out
@@ -432,6 +468,22 @@ impl TryFrom<&str> for StyleArgument {
     }
 }
 
+/// Maps a `bg-gradient-to-*` class to the angle of a [Background::LinearGradient]
+fn gradient_angle(class: &str) -> f32 {
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+    match class.trim_start_matches("bg-gradient-to-") {
+        "r" => 0.0,
+        "br" => FRAC_PI_4,
+        "b" => FRAC_PI_2,
+        "bl" => 3.0 * FRAC_PI_4,
+        "l" => PI,
+        "tl" => -3.0 * FRAC_PI_4,
+        "t" => -FRAC_PI_2,
+        "tr" => -FRAC_PI_4,
+        _ => 0.0,
+    }
+}
+
 pub fn parse_style<T>(style_str: &str) -> (Style, NodeContext<T>)
 where
     T: AppState,
@@ -457,7 +509,7 @@ where
                 if let Ok(argument) = StyleArgument::try_from(argument) {
                     match (*possible, argument) {
                         ("bg", StyleArgument::Color(color)) => {
-                            ctx.bg_color = color;
+                            ctx.background = Background::Solid(color);
                         }
                         ("hover:bg", StyleArgument::Color(color)) => {
                             ctx.flags |= flags::HOVER_BG;
@@ -466,6 +518,34 @@ where
                         ("rounded", StyleArgument::Length(length)) => {
                             ctx.border.radius = BorderRadius::all(length);
                         }
+                        ("rounded-tl", StyleArgument::Length(length)) => {
+                            ctx.border.radius.top_left = length;
+                        }
+                        ("rounded-tr", StyleArgument::Length(length)) => {
+                            ctx.border.radius.top_right = length;
+                        }
+                        ("rounded-bl", StyleArgument::Length(length)) => {
+                            ctx.border.radius.bottom_left = length;
+                        }
+                        ("rounded-br", StyleArgument::Length(length)) => {
+                            ctx.border.radius.bottom_right = length;
+                        }
+                        ("rounded-t", StyleArgument::Length(length)) => {
+                            ctx.border.radius.top_left = length;
+                            ctx.border.radius.top_right = length;
+                        }
+                        ("rounded-b", StyleArgument::Length(length)) => {
+                            ctx.border.radius.bottom_left = length;
+                            ctx.border.radius.bottom_right = length;
+                        }
+                        ("rounded-l", StyleArgument::Length(length)) => {
+                            ctx.border.radius.top_left = length;
+                            ctx.border.radius.bottom_left = length;
+                        }
+                        ("rounded-r", StyleArgument::Length(length)) => {
+                            ctx.border.radius.top_right = length;
+                            ctx.border.radius.bottom_right = length;
+                        }
                         ("border", StyleArgument::Length(length)) => {
                             ctx.border.thickness = length;
                         }
@@ -668,7 +748,31 @@ where
                             style.justify_self = Some(taffy::JustifySelf::Stretch);
                         }
                         ("opacity", StyleArgument::Length(length)) => {
-                            ctx.bg_color.a = length / 100.0;
+                            ctx.opacity = length / 100.0;
+                        }
+                        ("transition", StyleArgument::None) => {
+                            ctx.transition.property = Some(TransitionProperty::All);
+                        }
+                        ("transition-colors", StyleArgument::None) => {
+                            ctx.transition.property = Some(TransitionProperty::Colors);
+                        }
+                        ("transition-opacity", StyleArgument::None) => {
+                            ctx.transition.property = Some(TransitionProperty::Opacity);
+                        }
+                        ("duration", StyleArgument::Length(length)) => {
+                            ctx.transition.duration = Duration::from_millis(length as u64);
+                        }
+                        ("ease-linear", StyleArgument::None) => {
+                            ctx.transition.easing = Easing::Linear;
+                        }
+                        ("ease-in", StyleArgument::None) => {
+                            ctx.transition.easing = Easing::EaseIn;
+                        }
+                        ("ease-out", StyleArgument::None) => {
+                            ctx.transition.easing = Easing::EaseOut;
+                        }
+                        ("ease-in-out", StyleArgument::None) => {
+                            ctx.transition.easing = Easing::EaseInOut;
                         }
                         ("w", StyleArgument::Length(length)) => {
                             style.size.width = Dimension::length(length);
@@ -743,6 +847,38 @@ where
                             }
                             ctx.flags |= flags::SCROLL_CONTENT;
                         }
+                        ("shadow", StyleArgument::Length(length)) => {
+                            ctx.shadow.get_or_insert_default().blur = length;
+                        }
+                        ("shadow", StyleArgument::Color(color)) => {
+                            ctx.shadow.get_or_insert_default().color = color;
+                        }
+                        ("shadow-x", StyleArgument::Length(length)) => {
+                            ctx.shadow.get_or_insert_default().offset.x = length;
+                        }
+                        ("shadow-y", StyleArgument::Length(length)) => {
+                            ctx.shadow.get_or_insert_default().offset.y = length;
+                        }
+                        ("outline", StyleArgument::Length(length)) => {
+                            ctx.outline.get_or_insert_default().thickness = length;
+                        }
+                        ("outline", StyleArgument::Color(color)) => {
+                            ctx.outline.get_or_insert_default().color = color;
+                        }
+                        (direction, StyleArgument::None)
+                            if direction.starts_with("bg-gradient-to-") =>
+                        {
+                            *ctx.background.as_gradient_mut().2 = gradient_angle(direction);
+                        }
+                        ("sprite-tint", StyleArgument::Color(color)) => {
+                            ctx.sprite_tint = color;
+                        }
+                        ("from", StyleArgument::Color(color)) => {
+                            *ctx.background.as_gradient_mut().0 = color;
+                        }
+                        ("to", StyleArgument::Color(color)) => {
+                            *ctx.background.as_gradient_mut().1 = color;
+                        }
                         _ => {
                             error!("Unknown style argument-parameter combination {:?}", param);
                         }
@@ -806,6 +942,23 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn per_corner_rounding() {
+        let (_, ctx) = parse_style::<DummyState>("rounded-t-8 rounded-br-4");
+        let radius = ctx.border.radius;
+        assert_eq!(radius.top_left, 8.0);
+        assert_eq!(radius.top_right, 8.0);
+        assert_eq!(radius.bottom_left, 0.0);
+        assert_eq!(radius.bottom_right, 4.0);
+
+        let (_, ctx) = parse_style::<DummyState>("rounded-8 rounded-l-2");
+        let radius = ctx.border.radius;
+        assert_eq!(radius.top_left, 2.0);
+        assert_eq!(radius.top_right, 8.0);
+        assert_eq!(radius.bottom_left, 2.0);
+        assert_eq!(radius.bottom_right, 8.0);
+    }
+
     #[test]
     pub fn hover_bg_and_bg_parsing() {
         let style_str = "w-full bg-red-800 hover:bg-red-900 h-16 rounded-4";
@@ -820,4 +973,75 @@ mod tests {
             "The hover color should be red-900"
         );
     }
+
+    #[test]
+    pub fn shadow_parsing() {
+        let (_, ctx) = parse_style::<DummyState>("bg-white shadow-12 shadow-y-4 shadow-black");
+        let shadow = ctx.shadow.expect("Should have a shadow");
+        assert_eq!(shadow.blur, 12.0);
+        assert_eq!(shadow.offset, crate::geometry::Vector::new(0.0, 4.0));
+        assert_eq!(shadow.color, hex("#000000"));
+
+        let (_, ctx) = parse_style::<DummyState>("bg-white");
+        assert!(ctx.shadow.is_none(), "Shadows should be opt-in");
+    }
+
+    #[test]
+    pub fn outline_parsing() {
+        let (style, ctx) = parse_style::<DummyState>("p-4 outline-2 outline-blue-500");
+        let outline = ctx.outline.expect("Should have an outline");
+        assert_eq!(outline.thickness, 2.0);
+        assert_eq!(outline.color, hex("#3b82f6"));
+        assert_eq!(
+            style.border,
+            taffy::Rect::zero(),
+            "Outlines shouldn't take up any space"
+        );
+    }
+
+    #[test]
+    pub fn sprite_tint_parsing() {
+        let (_, ctx) = parse_style::<DummyState>("w-16 h-16");
+        assert_eq!(ctx.sprite_tint, Color::new(1.0, 1.0, 1.0, 1.0));
+
+        let (_, ctx) = parse_style::<DummyState>("w-16 h-16 sprite-tint-red-500");
+        assert_eq!(ctx.sprite_tint, hex("#ef4444"));
+    }
+
+    #[test]
+    pub fn gradient_parsing() {
+        let (_, ctx) = parse_style::<DummyState>("bg-gradient-to-b from-white to-black");
+        assert_eq!(
+            ctx.background,
+            Background::LinearGradient {
+                from: hex("#ffffff"),
+                to: hex("#000000"),
+                angle: std::f32::consts::FRAC_PI_2,
+            }
+        );
+    }
+
+    #[test]
+    pub fn opacity_parsing() {
+        let (_, ctx) = parse_style::<DummyState>("bg-white opacity-50");
+        assert_eq!(ctx.opacity, 0.5);
+        assert_eq!(
+            ctx.background,
+            Background::Solid(hex("#ffffff")),
+            "Opacity is applied while rendering, not baked into the background"
+        );
+    }
+
+    #[test]
+    pub fn transition_parsing() {
+        let (_, ctx) = parse_style::<DummyState>(
+            "bg-white hover:bg-black transition-colors duration-300 ease-out",
+        );
+        assert_eq!(ctx.transition.property, Some(TransitionProperty::Colors));
+        assert_eq!(ctx.transition.duration, Duration::from_millis(300));
+        assert_eq!(ctx.transition.easing, Easing::EaseOut);
+
+        let (_, ctx) = parse_style::<DummyState>("bg-white duration-300");
+        assert_eq!(ctx.transition.property, None);
+    }
 }
@@ -1,10 +1,14 @@
 #version 300 es
 layout (location = 0) in vec3 aPos;
+layout (location = 1) in float aEdge;
 
 uniform mat4 model;
 uniform mat4 view;
 uniform mat4 projection;
 
+out float edge;
+
 void main() {
     gl_Position = projection * view * model * vec4(aPos, 1.0);
+    edge = aEdge;
 }
@@ -8,6 +8,10 @@ in vec2 fragCoord;
 uniform float edgeSoftness;
 uniform vec2 size;
 uniform vec4 bgColor;
+// The background fades from bgColor to bgColorTo in the direction of gradientAngle. Solid
+// backgrounds use the same color for both
+uniform vec4 bgColorTo;
+uniform float gradientAngle;
 uniform vec4 borderColor;
 uniform float borderThickness;
 // top-left top-right bottom-left bottom-right
@@ -32,14 +36,19 @@ float box(vec2 position, vec2 halfSize, vec4 cornerRadius) {
 void main() {
     vec2 center = size / 2.0;
 
+    vec2 position = (fragCoord * (size+edgeSoftness*2.0)/size - 0.5) * size - edgeSoftness;
     float distance = box(
-        (fragCoord * (size+edgeSoftness*2.0)/size - 0.5) * size - edgeSoftness, 
+        position,
         size / 2.0, 
         borderRadius
     );
+    vec2 direction = vec2(cos(gradientAngle), sin(gradientAngle));
+    float extent = dot(abs(direction), size / 2.0);
+    float t = clamp(dot(position, direction) / (2.0 * extent) + 0.5, 0.0, 1.0);
+    vec4 background = mix(bgColor, bgColorTo, t);
     float smoothedAlpha = 1.0 - smoothstep(0.0, edgeSoftness, distance);
     float borderAlpha = 1.0 - smoothstep(borderThickness - 1.0, borderThickness, abs(distance));
-    vec4 xcolor = mix(bgColor, borderColor, borderAlpha);
+    vec4 xcolor = mix(background, borderColor, borderAlpha);
 
     color = vec4(xcolor.rgb, min(smoothedAlpha, xcolor.a));
 }
\ No newline at end of file
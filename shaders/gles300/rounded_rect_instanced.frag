@@ -0,0 +1,47 @@
+#version 300 es
+precision mediump float;
+precision mediump int;
+
+out vec4 color;
+in vec2 fragCoord;
+
+in vec2 size;
+in vec4 bgColor;
+in vec4 bgColorTo;
+in vec4 borderColor;
+// top-left top-right bottom-left bottom-right
+in vec4 borderRadius;
+in float borderThickness;
+in float edgeSoftness;
+in float gradientAngle;
+
+float box(vec2 position, vec2 halfSize, vec4 cornerRadius) {
+    float corner = cornerRadius.x;
+    if (position.x > 0.0 && position.y > 0.0) {
+        corner = cornerRadius.w;
+    }
+    if (position.x > 0.0 && position.y < 0.0) {
+        corner = cornerRadius.y;
+    }
+    if (position.x < 0.0 && position.y > 0.0) {
+        corner = cornerRadius.z;
+    }
+    position = abs(position) - halfSize + corner;
+    return length(max(position, 0.0)) + min(max(position.x, position.y), 0.0) - corner;
+}
+
+// Same shading as rounded_rect.frag with the rectangle data coming from the instance instead of
+// uniforms
+void main() {
+    vec2 position = (fragCoord * (size+edgeSoftness*2.0)/size - 0.5) * size - edgeSoftness;
+    float distance = box(position, size / 2.0, borderRadius);
+    vec2 direction = vec2(cos(gradientAngle), sin(gradientAngle));
+    float extent = dot(abs(direction), size / 2.0);
+    float t = clamp(dot(position, direction) / (2.0 * extent) + 0.5, 0.0, 1.0);
+    vec4 background = mix(bgColor, bgColorTo, t);
+    float smoothedAlpha = 1.0 - smoothstep(0.0, edgeSoftness, distance);
+    float borderAlpha = 1.0 - smoothstep(borderThickness - 1.0, borderThickness, abs(distance));
+    vec4 xcolor = mix(background, borderColor, borderAlpha);
+
+    color = vec4(xcolor.rgb, min(smoothedAlpha, xcolor.a));
+}
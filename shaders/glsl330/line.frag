@@ -1,8 +1,17 @@
 #version 330 core
 out vec4 FragColor;
 
+in float edge;
+
 uniform vec4 color;
+// Half the line thickness in pixels. Antialiasing is disabled when this is zero
+uniform float halfWidth;
 
 void main() {
-    FragColor = color;
+    float alpha = 1.0;
+    if (halfWidth > 0.0) {
+        // Fade out over the pixel closest to the edge of the line
+        alpha = clamp(halfWidth - abs(edge) + 0.5, 0.0, 1.0);
+    }
+    FragColor = vec4(color.rgb, color.a * alpha);
 }
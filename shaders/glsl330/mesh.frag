@@ -1,6 +1,16 @@
 #version 330 core
 out vec4 FragColor;
+in vec3 normal;
+
+uniform vec4 objectColor;
+// Normalized direction the light travels in
+uniform vec3 lightDirection;
+uniform vec4 lightColor;
+uniform vec4 ambientColor;
 
 void main() {
-    FragColor = vec4(1.0, 0.0, 0.0, 1.0);
+    // Surfaces are lit from both sides since open surfaces don't have a consistent outside
+    float diffuse = abs(dot(normalize(normal), -lightDirection));
+    vec3 light = ambientColor.rgb + diffuse * lightColor.rgb;
+    FragColor = vec4(min(light, 1.0) * objectColor.rgb, objectColor.a);
 }
@@ -0,0 +1,39 @@
+#version 330 core
+layout (location = 0) in vec4 vertex; // <vec2 position, vec2 texCoords>
+// Per-instance rectangle data, see RectInstance in rect.rs
+layout (location = 1) in vec2 instancePosition;
+layout (location = 2) in vec2 instanceSize;
+layout (location = 3) in vec4 instanceBgColor;
+layout (location = 4) in vec4 instanceBgColorTo;
+layout (location = 5) in vec4 instanceBorderColor;
+layout (location = 6) in vec4 instanceBorderRadius;
+layout (location = 7) in vec3 instanceParams; // borderThickness, edgeSoftness, gradientAngle
+
+out vec2 fragCoord;
+out vec2 size;
+out vec4 bgColor;
+out vec4 bgColorTo;
+out vec4 borderColor;
+out vec4 borderRadius;
+out float borderThickness;
+out float edgeSoftness;
+out float gradientAngle;
+
+uniform mat4 projection;
+
+void main() {
+    fragCoord = vertex.zw;
+    size = instanceSize;
+    bgColor = instanceBgColor;
+    bgColorTo = instanceBgColorTo;
+    borderColor = instanceBorderColor;
+    borderRadius = instanceBorderRadius;
+    borderThickness = instanceParams.x;
+    edgeSoftness = instanceParams.y;
+    gradientAngle = instanceParams.z;
+
+    // Quad needs to be a little bit bigger to fit the soft edge
+    vec2 quadSize = instanceSize + edgeSoftness * 2.0;
+    vec2 worldPos = instancePosition - edgeSoftness + vertex.xy * quadSize;
+    gl_Position = projection * vec4(worldPos, 0.0, 1.0);
+}
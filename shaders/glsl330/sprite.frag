@@ -3,7 +3,9 @@ in vec2 TexCoords;
 out vec4 color;
 
 uniform sampler2D text;
+// Multiplied into every texel, white leaves the sprite unchanged
+uniform vec4 tint;
 
 void main() {
-    color = texture(text, TexCoords);
+    color = texture(text, TexCoords) * tint;
 }
@@ -4,15 +4,24 @@ layout (location = 1) in vec2 instance_position; // per-instance position
 layout (location = 2) in vec2 instance_size; // per-instance size
 layout (location = 3) in vec2 instance_atlas_coords; // per-instance atlas UV coords
 layout (location = 4) in vec2 instance_atlas_size; // per-instance atlas UV size
+layout (location = 5) in float instance_rotation; // per-instance rotation in radians about the center
+layout (location = 6) in vec2 instance_flip; // per-instance -1 to mirror an axis, 1 otherwise
 
 out vec2 TexCoords;
 
 uniform mat4 projection;
 
 void main() {
-    // Transform unit quad to character position and size
-    vec2 world_pos = instance_position + vertex.xy * instance_size;
+    // Rotate the unit quad about its center, scaled to the sprite's size. Keep in sync with
+    // SpriteTransform::apply
+    vec2 centered = (vertex.xy - 0.5) * instance_size;
+    float c = cos(instance_rotation);
+    float s = sin(instance_rotation);
+    vec2 rotated = vec2(c * centered.x - s * centered.y, s * centered.x + c * centered.y);
+    vec2 world_pos = instance_position + 0.5 * instance_size + rotated;
     gl_Position = projection * vec4(world_pos, 0.0, 1.0);
+    // Flipping mirrors the UVs about the middle of the sprite
+    vec2 uv = 0.5 + (vertex.zw - 0.5) * instance_flip;
     // Map unit quad UV to character's atlas UV region
-    TexCoords = instance_atlas_coords + vertex.zw * instance_atlas_size;
+    TexCoords = instance_atlas_coords + uv * instance_atlas_size;
 }
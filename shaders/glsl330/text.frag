@@ -4,8 +4,17 @@ out vec4 color;
 
 uniform sampler2D text;
 uniform vec4 textColor;
+// Set to 1 when the atlas holds signed distance fields instead of coverage
+uniform int sdf;
 
 void main() {
-    vec4 sampled = vec4(1.0, 1.0, 1.0, texture(text, TexCoords).r);
+    float value = texture(text, TexCoords).r;
+    float alpha = value;
+    if (sdf == 1) {
+        // Smooth over roughly one screen pixel regardless of how much the glyph is scaled
+        float smoothing = fwidth(value);
+        alpha = smoothstep(0.5 - smoothing, 0.5 + smoothing, value);
+    }
+    vec4 sampled = vec4(1.0, 1.0, 1.0, alpha);
     color = textColor * sampled;
 }
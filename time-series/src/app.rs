@@ -9,7 +9,7 @@ use rust_ui::{
     render::{
         COLOR_DANGER, COLOR_LIGHT, Text,
         renderer::{AppState, Listeners, RenderLayout, visual_log},
-        widgets::{DefaultAtom, UiBuilder, UiData, text_field::TextFieldData},
+        widgets::{DefaultAtom, UiBuilder, UiData},
     },
 };
 use strum::EnumString;
@@ -296,35 +296,6 @@ impl AppState for App {
                         .dispatch(&mut self.config.bindings, key_input)
                     {
                         self.handle_message(msg, ui);
-                    } else {
-                        if self.mode_stack.is_outermost(&AppMode::Typing) {
-                            if let Some(focused) = &self.focus {
-                                match key_input.key() {
-                                    keybinds::Key::Right => {
-                                        ui.mutate_state(focused, |ui_data| {
-                                            let d: &mut TextFieldData<Self> =
-                                                ui_data.downcast_mut().unwrap();
-                                            d.move_cursor(1);
-                                        });
-                                    }
-                                    keybinds::Key::Left => {
-                                        ui.mutate_state(focused, |ui_data| {
-                                            let d: &mut TextFieldData<Self> =
-                                                ui_data.downcast_mut().unwrap();
-                                            d.move_cursor(-1);
-                                        });
-                                    }
-                                    keybinds::Key::Backspace => {
-                                        ui.mutate_state(focused, |ui_data| {
-                                            let d: &mut TextFieldData<Self> =
-                                                ui_data.downcast_mut().unwrap();
-                                            d.delete_char();
-                                        });
-                                    }
-                                    _ => {}
-                                }
-                            }
-                        }
                     }
                 }
                 None => {
@@ -337,21 +308,6 @@ impl AppState for App {
         }
     }
 
-    fn handle_char(&mut self, unicode: u32, ui: &UiBuilder<Self>) {
-        if let Some(ch) = char::from_u32(unicode) {
-            if self.mode_stack.is_outermost(&AppMode::Typing) {
-                if let Some(focused) = &self.focus {
-                    if !ch.is_control() {
-                        ui.mutate_state(focused, |ui_data| {
-                            let d: &mut TextFieldData<Self> = ui_data.downcast_mut().unwrap();
-                            d.write(ch);
-                        });
-                    }
-                }
-            }
-        }
-    }
-
     fn handle_mouse_button(
         &mut self,
         _button: glfw::MouseButton,
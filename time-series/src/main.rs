@@ -4,8 +4,10 @@ use anyhow::Result;
 use clap::{Parser, arg};
 use glfw::Context;
 use rust_ui::{
+    WindowOptions,
+    clipboard::GlfwClipboard,
     geometry::Vector,
-    init_open_gl,
+    init_open_gl, logical_cursor_position,
     render::{
         COLOR_DANGER,
         graph::GraphRenderer,
@@ -28,6 +30,8 @@ mod pipeline;
 
 const TARGET_FPS: u64 = 60;
 const FRAME_TIME: Duration = Duration::from_nanos(1_000_000_000 / TARGET_FPS);
+/// Longest time to sleep waiting for input while nothing on screen changes
+const IDLE_TIMEOUT: Duration = Duration::from_millis(500);
 
 #[derive(Debug, EnumString, Clone, Copy)]
 pub enum Scenario {
@@ -49,15 +53,16 @@ fn main() -> Result<()> {
 
     let args = Args::parse();
 
-    let (mut glfw, mut window, events) = init_open_gl(1600, 900, true, true);
+    let (mut glfw, mut window, events) = init_open_gl(1600, 900, WindowOptions::default());
 
     let rect_shader = Shader::new_from_name(&ShaderName::Rect)?;
     let text_shader = Shader::new_from_name(&ShaderName::Text)?;
     let line_shader = Shader::new_from_name(&ShaderName::Line)?;
     let sprite_shader = Shader::new_from_name(&ShaderName::Sprite)?;
     let graph_shader = Shader::new_from_name(&ShaderName::Graph)?;
+    let rect_instanced_shader = Shader::new_from_name(&ShaderName::RectInstanced)?;
 
-    let rect_r = RectRenderer::new(rect_shader);
+    let rect_r = RectRenderer::new(rect_shader).with_batch_shader(rect_instanced_shader);
     let text_r = TextRenderer::new(
         text_shader,
         &PathBuf::from_str("assets/fonts/LiberationMono.ttf")?,
@@ -83,17 +88,23 @@ fn main() -> Result<()> {
         None => (App::new(), vec![]),
     };
     let mut state = Renderer::new(rect_r, text_r, line_r, sprite_r, graph_r, app_state);
+    state.window_size(window.get_framebuffer_size());
+    state.scale_factor = window.get_content_scale().0;
+    state.clipboard = Box::new(GlfwClipboard::new(&glfw));
     state.update();
     for msg in msgs {
         state.app_state.handle_message(msg, &state.ui_builder);
     }
 
     // Set up projection matrix for 2D rendering
-    let projection = glm::ortho(0.0, state.width as f32, state.height as f32, 0.0, -1.0, 1.0);
+    let projection = state.projection();
 
     rect_shader.use_shader();
     rect_shader.set_uniform("projection", &projection);
 
+    rect_instanced_shader.use_shader();
+    rect_instanced_shader.set_uniform("projection", &projection);
+
     text_shader.use_shader();
     text_shader.set_uniform("projection", &projection);
 
@@ -104,7 +115,12 @@ fn main() -> Result<()> {
     graph_shader.set_uniform("projection", &projection);
 
     while !window.should_close() {
-        glfw.poll_events();
+        if state.is_dirty() {
+            glfw.poll_events();
+        } else {
+            // Nothing changes on screen without input, so sleep until some arrives
+            glfw.wait_events_timeout(IDLE_TIMEOUT.as_secs_f64());
+        }
         state.pre_update();
         for (_, event) in glfw::flush_messages(&events) {
             match event {
@@ -112,7 +128,16 @@ fn main() -> Result<()> {
                     state.handle_mouse_button(button, action, modifiers);
                 }
                 glfw::WindowEvent::CursorPos(x, y) => {
-                    state.handle_mouse_position(Vector::new(x as f32, y as f32));
+                    state.handle_mouse_position(logical_cursor_position(
+                        &window,
+                        x,
+                        y,
+                        state.scale_factor,
+                    ));
+                }
+                glfw::WindowEvent::ContentScale(scale, _) => {
+                    state.scale_factor = scale;
+                    state.mark_dirty();
                 }
                 glfw::WindowEvent::FramebufferSize(width, height) => {
                     state.window_size((width, height));
@@ -129,6 +154,9 @@ fn main() -> Result<()> {
                             }
                             _ => {}
                         },
+                        glfw::Key::F5 if action == glfw::Action::Release => {
+                            state.reload_shaders();
+                        }
                         _ => {}
                     }
                 }
@@ -141,12 +169,17 @@ fn main() -> Result<()> {
                 _ => {}
             }
         }
-        state.update();
-        let projection = glm::ortho(0.0, state.width as f32, state.height as f32, 0.0, -1.0, 1.0);
+        if !state.is_dirty() {
+            continue;
+        }
+        let projection = state.projection();
 
         rect_shader.use_shader();
         rect_shader.set_uniform("projection", &projection);
 
+        rect_instanced_shader.use_shader();
+        rect_instanced_shader.set_uniform("projection", &projection);
+
         text_shader.use_shader();
         text_shader.set_uniform("projection", &projection);
 
@@ -159,7 +192,7 @@ fn main() -> Result<()> {
             gl::ClearColor(0.2, 0.2, 0.2, 1.0);
             gl::Clear(gl::COLOR_BUFFER_BIT);
         }
-        state.render();
+        state.render_if_dirty();
 
         window.swap_buffers();
     }
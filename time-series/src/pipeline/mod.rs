@@ -9,6 +9,7 @@ use std::{
     str::FromStr,
 };
 use strum::{Display, EnumString};
+use tracing::warn;
 
 pub mod processing;
 pub mod ui;
@@ -36,52 +37,104 @@ impl Record {
 pub struct DataFrame {
     pub column_names: Vec<String>,
     pub columns: Vec<Vec<f64>>,
+    /// Problems encountered while parsing, one per skipped row or unparseable field
+    pub warnings: Vec<String>,
 }
 
 impl DataFrame {
     pub fn from_path(path: &Path) -> Result<Self> {
+        Self::from_path_with(path, b',', true)
+    }
+
+    /// Like [DataFrame::from_path] but for delimited text files that don't use commas or lack a
+    /// header row. Columns are named `col0..colN` when `has_headers` is false.
+    pub fn from_path_with(path: &Path, delimiter: u8, has_headers: bool) -> Result<Self> {
         let file = File::open(path).map_err(|_| anyhow!("Error opening file"))?;
-        if let Some(ext) = path.extension() {
-            Self::from_reader(file)
+        if path.extension().is_some() {
+            Self::from_reader_with(file, delimiter, has_headers)
         } else {
             Self::from_binary_format(file)
         }
     }
 
-    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self> {
+        Self::from_reader_with(reader, b',', true)
+    }
+
+    pub fn from_reader_with<R: Read>(
+        mut reader: R,
+        delimiter: u8,
+        has_headers: bool,
+    ) -> Result<Self> {
         let mut contents = String::new();
         reader.read_to_string(&mut contents)?;
 
         let mut rdr = csv::ReaderBuilder::new()
             .comment(Some(b'#'))
+            .delimiter(delimiter)
+            .has_headers(has_headers)
             .from_reader(contents.as_bytes());
 
         let mut out = DataFrame::default();
-        for h in rdr.headers()? {
-            out.column_names.push(h.to_string());
+        for (i, h) in rdr.headers()?.iter().enumerate() {
+            if has_headers {
+                out.column_names.push(h.to_string());
+            } else {
+                out.column_names.push(format!("col{i}"));
+            }
             out.columns.push(vec![]);
         }
-        for record in rdr.records().flatten() {
+        // Fields that fail to parse become NaN so every column keeps the same length. Columns
+        // where nothing parses (labels, timestamps) are dropped and not warned about.
+        let mut failures = vec![];
+        for (row, record) in rdr.records().enumerate() {
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    out.warnings.push(format!("Skipped row {}: {e}", row + 1));
+                    continue;
+                }
+            };
             for (i, r) in record.iter().enumerate() {
                 match r.parse() {
                     Ok(f) => {
                         out.columns[i].push(f);
                     }
-                    Err(_) => {}
+                    Err(_) => {
+                        out.columns[i].push(f64::NAN);
+                        failures.push((row, i, r.to_string()));
+                    }
                 }
             }
         }
 
-        let mut empty_columns = vec![];
-        for (i, col) in out.columns.iter().enumerate() {
-            if col.is_empty() {
-                empty_columns.push(i);
+        let empty_columns: Vec<usize> = out
+            .columns
+            .iter()
+            .enumerate()
+            .filter(|(_, col)| col.iter().all(|x| x.is_nan()))
+            .map(|(i, _)| i)
+            .collect();
+        for (row, i, value) in failures {
+            if !empty_columns.contains(&i) {
+                out.warnings.push(format!(
+                    "Row {}, column \"{}\": could not parse \"{value}\", using NaN",
+                    row + 1,
+                    out.column_names[i]
+                ));
             }
         }
         for idx in empty_columns.iter().rev() {
             out.columns.remove(*idx);
             out.column_names.remove(*idx);
         }
+        if !out.warnings.is_empty() {
+            warn!(
+                "{} problems while reading data frame, first: {}",
+                out.warnings.len(),
+                out.warnings[0]
+            );
+        }
 
         Ok(out)
     }
@@ -95,6 +148,7 @@ impl DataFrame {
         let mut out = Self {
             column_names: vec!["timestamp".into(), "voltage".into()],
             columns: vec![vec![], vec![]],
+            warnings: vec![],
         };
 
         let mut sample: Sample = unsafe { std::mem::zeroed() };
@@ -165,6 +219,16 @@ pub enum PipelineIntermediate {
     Complex(Vec<Complex<f64>>),
 }
 
+impl PipelineIntermediate {
+    pub fn kind(&self) -> SignalKind {
+        match self {
+            PipelineIntermediate::DataFrame(_) => SignalKind::DataFrame,
+            PipelineIntermediate::Signal(_) => SignalKind::Signal,
+            PipelineIntermediate::Complex(_) => SignalKind::Complex,
+        }
+    }
+}
+
 #[derive(
     Debug, Clone, Copy, Serialize, Deserialize, EnumString, Default, Display, PartialEq, Eq,
 )]
@@ -174,6 +238,18 @@ pub enum AxisSelection {
     Y,
 }
 
+/// Tapers applied to a signal before a Fourier transform to reduce spectral leakage
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, EnumString, Default, Display, PartialEq, Eq,
+)]
+pub enum WindowKind {
+    #[default]
+    Hann,
+    Hamming,
+    Blackman,
+    Rectangular,
+}
+
 #[derive(Debug, Clone, Copy, EnumString, Display, Deserialize, Serialize)]
 pub enum StepConfig {
     Average,
@@ -213,6 +289,25 @@ pub enum StepConfig {
         axis: AxisSelection,
         base: f64,
     },
+    Derivative,
+    Integrate,
+    ApplyWindow {
+        kind: WindowKind,
+    },
+    FindPeaks {
+        min_prominence: f64,
+        min_distance: usize,
+    },
+    MedianFilter {
+        window: usize,
+    },
+    Resample {
+        n: usize,
+    },
+    Spectrogram {
+        window: usize,
+        hop: usize,
+    },
 }
 
 impl StepConfig {
@@ -250,6 +345,21 @@ impl StepConfig {
                 axis: AxisSelection::X,
                 base: 10.0,
             },
+            StepConfig::Derivative,
+            StepConfig::Integrate,
+            StepConfig::ApplyWindow {
+                kind: WindowKind::Hann,
+            },
+            StepConfig::FindPeaks {
+                min_prominence: 0.0,
+                min_distance: 1,
+            },
+            StepConfig::MedianFilter { window: 5 },
+            StepConfig::Resample { n: 1024 },
+            StepConfig::Spectrogram {
+                window: 256,
+                hop: 128,
+            },
         ]
     }
 
@@ -281,6 +391,16 @@ impl StepConfig {
             } => SignalKind::DataFrame,
             StepConfig::ScaleAxis { axis: _, factor: _ } => SignalKind::Signal,
             StepConfig::LogAxis { axis: _, base: _ } => SignalKind::Signal,
+            StepConfig::Derivative => SignalKind::Signal,
+            StepConfig::Integrate => SignalKind::Signal,
+            StepConfig::ApplyWindow { kind: _ } => SignalKind::Signal,
+            StepConfig::FindPeaks {
+                min_prominence: _,
+                min_distance: _,
+            } => SignalKind::Signal,
+            StepConfig::MedianFilter { window: _ } => SignalKind::Signal,
+            StepConfig::Resample { n: _ } => SignalKind::Signal,
+            StepConfig::Spectrogram { window: _, hop: _ } => SignalKind::Signal,
         }
     }
 
@@ -312,6 +432,16 @@ impl StepConfig {
             } => SignalKind::Signal,
             StepConfig::ScaleAxis { axis: _, factor: _ } => SignalKind::Signal,
             StepConfig::LogAxis { axis: _, base: _ } => SignalKind::Signal,
+            StepConfig::Derivative => SignalKind::Signal,
+            StepConfig::Integrate => SignalKind::Signal,
+            StepConfig::ApplyWindow { kind: _ } => SignalKind::Signal,
+            StepConfig::FindPeaks {
+                min_prominence: _,
+                min_distance: _,
+            } => SignalKind::Signal,
+            StepConfig::MedianFilter { window: _ } => SignalKind::Signal,
+            StepConfig::Resample { n: _ } => SignalKind::Signal,
+            StepConfig::Spectrogram { window: _, hop: _ } => SignalKind::DataFrame,
         }
     }
 }
@@ -357,6 +487,43 @@ impl PartialEq for StepConfig {
                 StepConfig::ScaleAxis { axis: _, factor: _ },
                 StepConfig::ScaleAxis { axis: _, factor: _ },
             ) => true,
+            (
+                StepConfig::CurrentCalculator {
+                    capacitance: _,
+                    x1: _,
+                    x2: _,
+                },
+                StepConfig::CurrentCalculator {
+                    capacitance: _,
+                    x1: _,
+                    x2: _,
+                },
+            ) => true,
+            (
+                StepConfig::LogAxis { axis: _, base: _ },
+                StepConfig::LogAxis { axis: _, base: _ },
+            ) => true,
+            (StepConfig::Derivative, StepConfig::Derivative) => true,
+            (StepConfig::Integrate, StepConfig::Integrate) => true,
+            (StepConfig::ApplyWindow { kind: _ }, StepConfig::ApplyWindow { kind: _ }) => true,
+            (
+                StepConfig::FindPeaks {
+                    min_prominence: _,
+                    min_distance: _,
+                },
+                StepConfig::FindPeaks {
+                    min_prominence: _,
+                    min_distance: _,
+                },
+            ) => true,
+            (StepConfig::MedianFilter { window: _ }, StepConfig::MedianFilter { window: _ }) => {
+                true
+            }
+            (StepConfig::Resample { n: _ }, StepConfig::Resample { n: _ }) => true,
+            (
+                StepConfig::Spectrogram { window: _, hop: _ },
+                StepConfig::Spectrogram { window: _, hop: _ },
+            ) => true,
             _ => false,
         }
     }
@@ -364,12 +531,23 @@ impl PartialEq for StepConfig {
 
 #[cfg(test)]
 mod tests {
-    use crate::pipeline::processing::run_pipeline;
+    use std::f64::consts::PI;
+
+    use crate::pipeline::processing::{run_pipeline, stft};
 
     use super::*;
 
     const SAWTOOTH: &str = include_str!("../../assets/test_csvs/sawtooth.csv");
 
+    fn expect_signal(output: PipelineIntermediate) -> Signal {
+        match output {
+            PipelineIntermediate::Signal(records) => records,
+            PipelineIntermediate::DataFrame(_) | PipelineIntermediate::Complex(_) => {
+                panic!("Output should be a signal");
+            }
+        }
+    }
+
     #[test]
     fn extract_coordinates_from_sawtooth() {
         let pipeline = vec![StepConfig::PickColumns {
@@ -377,13 +555,247 @@ mod tests {
             column_2: 1,
         }];
         let df = DataFrame::from_str(SAWTOOTH).unwrap();
-        match run_pipeline(&pipeline, PipelineIntermediate::DataFrame(df)).unwrap() {
-            PipelineIntermediate::Signal(records) => {
-                assert_eq!(records.len(), 10);
+        let records =
+            expect_signal(run_pipeline(&pipeline, PipelineIntermediate::DataFrame(df)).unwrap());
+        assert_eq!(records.len(), 10);
+    }
+
+    #[test]
+    fn derivative_of_parabola() {
+        let parabola = (0..10)
+            .map(|i| Record::new(i as f64 * 0.5, (i as f64 * 0.5).powi(2)))
+            .collect();
+        let records = expect_signal(
+            run_pipeline(
+                &[StepConfig::Derivative],
+                PipelineIntermediate::Signal(parabola),
+            )
+            .unwrap(),
+        );
+        assert_eq!(records.len(), 10);
+        // Central differences are exact for a parabola away from the ends
+        for r in &records[1..9] {
+            assert!((r.y - 2.0 * r.x).abs() < 1e-12);
+        }
+
+        let derivative = |signal: Signal| {
+            expect_signal(
+                run_pipeline(
+                    &[StepConfig::Derivative],
+                    PipelineIntermediate::Signal(signal),
+                )
+                .unwrap(),
+            )
+            .iter()
+            .map(|r| r.y)
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(derivative(vec![Record::new(1.0, 3.0)]), vec![0.0]);
+        assert_eq!(
+            derivative(vec![Record::new(1.0, 3.0), Record::new(1.0, 4.0)]),
+            vec![0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn integral_of_line() {
+        // Uneven spacing shouldn't matter since the trapezoids are exact for a straight line
+        let line = [0.0, 0.5, 2.0, 3.0]
+            .iter()
+            .map(|x| Record::new(*x, 2.0 * x))
+            .collect();
+        let records = expect_signal(
+            run_pipeline(&[StepConfig::Integrate], PipelineIntermediate::Signal(line)).unwrap(),
+        );
+        for r in &records {
+            assert!((r.y - r.x.powi(2)).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn hann_window_tapers_to_zero() {
+        let ones = (0..5).map(|x| Record::new(x as f64, 1.0)).collect();
+        let records = expect_signal(
+            run_pipeline(
+                &[StepConfig::ApplyWindow {
+                    kind: WindowKind::Hann,
+                }],
+                PipelineIntermediate::Signal(ones),
+            )
+            .unwrap(),
+        );
+        let expected = [0.0, 0.5, 1.0, 0.5, 0.0];
+        for (r, e) in records.iter().zip(expected) {
+            assert!((r.y - e).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn step_config_eq_ignores_parameters() {
+        let steps = StepConfig::all();
+        for (i, a) in steps.iter().enumerate() {
+            for (j, b) in steps.iter().enumerate() {
+                assert_eq!(a == b, i == j, "{a} vs {b}");
+            }
+        }
+        assert_eq!(
+            StepConfig::LogAxis {
+                axis: AxisSelection::X,
+                base: 10.0,
+            },
+            StepConfig::LogAxis {
+                axis: AxisSelection::Y,
+                base: 2.0,
+            }
+        );
+    }
+
+    #[test]
+    fn mismatched_step_is_reported() {
+        let df = DataFrame::from_str(SAWTOOTH).unwrap();
+        let err = run_pipeline(
+            &[StepConfig::FourierTransform],
+            PipelineIntermediate::DataFrame(df),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Step 1 (FourierTransform) expects Signal input but received DataFrame"
+        );
+    }
+
+    #[test]
+    fn stft_frames() {
+        let signal: Signal = (0..64)
+            .map(|i| Record::new(i as f64, (2.0 * PI * 4.0 * i as f64 / 16.0).sin()))
+            .collect();
+        let frames = stft(&signal, 16, 8, WindowKind::Rectangular);
+        assert_eq!(frames.len(), 7);
+        for frame in &frames {
+            assert_eq!(frame.len(), 16);
+            let peak = (0..8).max_by(|a, b| frame[*a].norm().total_cmp(&frame[*b].norm()));
+            assert_eq!(peak, Some(4));
+        }
+
+        let short = stft(&signal[..5].to_vec(), 16, 8, WindowKind::Hann);
+        assert_eq!(short.len(), 1);
+        assert_eq!(short[0].len(), 16);
+        assert!(stft(&Vec::new(), 16, 8, WindowKind::Hann).is_empty());
+    }
+
+    #[test]
+    fn spectrogram_step_outputs_frequency_and_frame_columns() {
+        let signal: Signal = (0..64)
+            .map(|i| Record::new(i as f64 / 16.0, (2.0 * PI * 4.0 * i as f64 / 16.0).sin()))
+            .collect();
+        match run_pipeline(
+            &[StepConfig::Spectrogram { window: 16, hop: 8 }],
+            PipelineIntermediate::Signal(signal),
+        )
+        .unwrap()
+        {
+            PipelineIntermediate::DataFrame(df) => {
+                assert_eq!(df.column_names.len(), 8);
+                assert_eq!(df.column_names[0], "frequency");
+                assert_eq!(df.column_names[1], "0");
+                assert_eq!(df.column_names[2], "0.5");
+                assert_eq!(df.columns[0].len(), 9);
+                assert_eq!(df.columns[0][4], 4.0);
+                for column in &df.columns[1..] {
+                    let peak = (0..9).max_by(|a, b| column[*a].total_cmp(&column[*b]));
+                    assert_eq!(peak, Some(4));
+                }
             }
-            PipelineIntermediate::DataFrame(_) | PipelineIntermediate::Complex(_) => {
-                panic!("Output should be a signal");
+            PipelineIntermediate::Signal(_) | PipelineIntermediate::Complex(_) => {
+                panic!("Output should be a data frame");
             }
         }
     }
+
+    #[test]
+    fn find_peaks_filters_small_and_close_peaks() {
+        let ys = [0.0, 5.0, 0.0, 1.0, 0.8, 1.0, 0.0, 4.0, 3.0, 4.5, 0.0];
+        let signal: Signal = ys
+            .iter()
+            .enumerate()
+            .map(|(i, y)| Record::new(i as f64, *y))
+            .collect();
+        let peak_xs = |min_prominence, min_distance| {
+            expect_signal(
+                run_pipeline(
+                    &[StepConfig::FindPeaks {
+                        min_prominence,
+                        min_distance,
+                    }],
+                    PipelineIntermediate::Signal(signal.clone()),
+                )
+                .unwrap(),
+            )
+            .iter()
+            .map(|r| r.x)
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(peak_xs(2.0, 1), vec![1.0, 9.0]);
+        assert_eq!(peak_xs(0.5, 3), vec![1.0, 5.0, 9.0]);
+    }
+
+    #[test]
+    fn headerless_tab_separated() {
+        let df =
+            DataFrame::from_reader_with(Cursor::new("0\t1.5\n1\t2.5\n"), b'\t', false).unwrap();
+        assert_eq!(df.column_names, vec!["col0", "col1"]);
+        assert_eq!(df.columns, vec![vec![0.0, 1.0], vec![1.5, 2.5]]);
+    }
+
+    #[test]
+    fn unparseable_fields_become_nan() {
+        let df = DataFrame::from_str("label,t,v\na,0,1.0\nb,1,oops\nc,2,3.0\n").unwrap();
+        assert_eq!(df.column_names, vec!["t", "v"]);
+        assert_eq!(df.columns[0], vec![0.0, 1.0, 2.0]);
+        assert_eq!(df.columns[1].len(), 3);
+        assert!(df.columns[1][1].is_nan());
+        assert_eq!(df.warnings.len(), 1);
+        assert!(df.warnings[0].contains("oops"));
+    }
+
+    #[test]
+    fn median_filter_removes_spike() {
+        let ys = [1.0, 1.0, 50.0, 1.0, 2.0];
+        let signal = ys
+            .iter()
+            .enumerate()
+            .map(|(i, y)| Record::new(i as f64, *y))
+            .collect();
+        let records = expect_signal(
+            run_pipeline(
+                &[StepConfig::MedianFilter { window: 3 }],
+                PipelineIntermediate::Signal(signal),
+            )
+            .unwrap(),
+        );
+        let ys: Vec<f64> = records.iter().map(|r| r.y).collect();
+        assert_eq!(ys, vec![1.0, 1.0, 1.0, 2.0, 1.5]);
+    }
+
+    #[test]
+    fn resample_unsorted_with_duplicates() {
+        let signal = vec![
+            Record::new(2.0, 4.0),
+            Record::new(0.0, 0.0),
+            Record::new(1.0, 1.0),
+            Record::new(1.0, 3.0),
+        ];
+        let records = expect_signal(
+            run_pipeline(
+                &[StepConfig::Resample { n: 5 }],
+                PipelineIntermediate::Signal(signal),
+            )
+            .unwrap(),
+        );
+        let points: Vec<(f64, f64)> = records.iter().map(|r| (r.x, r.y)).collect();
+        assert_eq!(
+            points,
+            vec![(0.0, 0.0), (0.5, 1.0), (1.0, 2.0), (1.5, 3.0), (2.0, 4.0)]
+        );
+    }
 }
@@ -1,8 +1,10 @@
+use std::f64::consts::PI;
+
 use anyhow::{Result, anyhow};
 use peroxide::fuga::Statistics as _;
 use rustfft::{FftDirection, FftPlanner, num_complex::Complex};
 
-use super::{PipelineIntermediate, Record, Signal, StepConfig};
+use super::{DataFrame, PipelineIntermediate, Record, Signal, StepConfig, WindowKind};
 
 /// Uses welfords online algorithm for numerical stability
 pub fn average(data: Signal) -> f64 {
@@ -117,6 +119,67 @@ pub fn fourier_transform(data: Signal) -> Vec<Complex<f64>> {
     buffer
 }
 
+/// Short-time Fourier transform, one spectrum per frame of `window` samples taken every `hop`
+/// samples. A signal shorter than one window is zero-padded into a single frame.
+pub fn stft(
+    signal: &Signal,
+    window: usize,
+    hop: usize,
+    window_kind: WindowKind,
+) -> Vec<Vec<Complex<f64>>> {
+    if window == 0 || signal.is_empty() {
+        return vec![];
+    }
+    let hop = hop.max(1);
+    let coefficients = window_coefficients(window_kind, window);
+    let mut planner = FftPlanner::new();
+    let fft = planner.plan_fft(window, FftDirection::Forward);
+
+    let mut frames = vec![];
+    let mut start = 0;
+    loop {
+        let mut buffer = vec![Complex { re: 0.0, im: 0.0 }; window];
+        for (i, (sample, c)) in signal[start..].iter().zip(coefficients.iter()).enumerate() {
+            buffer[i].re = sample.y * c;
+        }
+        fft.process(&mut buffer);
+        frames.push(buffer);
+
+        start += hop;
+        if start + window > signal.len() {
+            break;
+        }
+    }
+    frames
+}
+
+/// Magnitudes of a Hann-windowed [stft] laid out for a heatmap. The first column holds the
+/// frequency of each bin up to Nyquist, followed by one column per frame named after the x value
+/// the frame starts at.
+pub fn spectrogram(signal: Signal, window: usize, hop: usize) -> DataFrame {
+    let frames = stft(&signal, window, hop, WindowKind::Hann);
+    if frames.is_empty() {
+        return DataFrame::default();
+    }
+    let dx = match signal.len() {
+        0 | 1 => 1.0,
+        n => (signal[n - 1].x - signal[0].x) / (n - 1) as f64,
+    };
+    let bins = window / 2 + 1;
+
+    let mut out = DataFrame::default();
+    out.column_names.push("frequency".to_string());
+    out.columns
+        .push((0..bins).map(|k| k as f64 / (window as f64 * dx)).collect());
+    for (i, frame) in frames.iter().enumerate() {
+        out.column_names
+            .push(format!("{}", signal[i * hop.max(1)].x));
+        out.columns
+            .push(frame[..bins].iter().map(|c| c.norm()).collect());
+    }
+    out
+}
+
 /// **OBS**: Assumes the input data is an un-normalized FFT
 pub fn inverse_fourier_transform(data: Vec<Complex<f64>>) -> Signal {
     let mut planner = FftPlanner::new();
@@ -185,6 +248,172 @@ pub fn bandpass_filter(mut data: Vec<Complex<f64>>, low: f64, high: f64) -> Vec<
     data
 }
 
+/// Estimates dy/dx with central differences. The first and last samples use one-sided
+/// differences since they only have one neighbour. Samples without a neighbour at a different x,
+/// such as a lone sample or repeated x values, get a slope of zero.
+pub fn derivative(data: Signal) -> Signal {
+    let last = data.len().saturating_sub(1);
+    (0..data.len())
+        .map(|i| {
+            let before = data[i.saturating_sub(1)];
+            let after = data[(i + 1).min(last)];
+            let dx = after.x - before.x;
+            Record {
+                x: data[i].x,
+                y: if dx == 0.0 {
+                    0.0
+                } else {
+                    (after.y - before.y) / dx
+                },
+            }
+        })
+        .collect()
+}
+
+/// The cumulative trapezoidal integral of y over x, starting at zero for the first sample
+pub fn integrate(data: Signal) -> Signal {
+    let mut total = 0.0;
+    let mut out = Vec::with_capacity(data.len());
+    for (i, r) in data.iter().enumerate() {
+        if i > 0 {
+            let prev = data[i - 1];
+            total += (r.x - prev.x) * (r.y + prev.y) / 2.0;
+        }
+        out.push(Record { x: r.x, y: total });
+    }
+    out
+}
+
+/// The coefficients of a symmetric window spanning `len` samples
+pub fn window_coefficients(kind: WindowKind, len: usize) -> Vec<f64> {
+    if len < 2 {
+        return vec![1.0; len];
+    }
+    (0..len)
+        .map(|n| {
+            let phase = 2.0 * PI * n as f64 / (len - 1) as f64;
+            match kind {
+                WindowKind::Hann => 0.5 - 0.5 * phase.cos(),
+                WindowKind::Hamming => 0.54 - 0.46 * phase.cos(),
+                WindowKind::Blackman => 0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos(),
+                WindowKind::Rectangular => 1.0,
+            }
+        })
+        .collect()
+}
+
+pub fn apply_window(kind: WindowKind, mut data: Signal) -> Signal {
+    let coefficients = window_coefficients(kind, data.len());
+    for (sample, c) in data.iter_mut().zip(coefficients) {
+        sample.y *= c;
+    }
+    data
+}
+
+/// Replaces each sample with the median of the `window_size` samples centered on it. The window
+/// shrinks near the ends of the signal rather than padding.
+pub fn median_filter(window_size: usize, data: Signal) -> Signal {
+    let before = window_size / 2;
+    let after = window_size.saturating_sub(1) / 2;
+    let mut window: Vec<f64> = Vec::with_capacity(window_size);
+    (0..data.len())
+        .map(|i| {
+            window.clear();
+            let start = i.saturating_sub(before);
+            let end = (i + after + 1).min(data.len());
+            window.extend(data[start..end].iter().map(|r| r.y));
+            window.sort_by(f64::total_cmp);
+            let mid = window.len() / 2;
+            let y = if window.len() % 2 == 1 {
+                window[mid]
+            } else {
+                (window[mid - 1] + window[mid]) / 2.0
+            };
+            Record { x: data[i].x, y }
+        })
+        .collect()
+}
+
+/// Linearly interpolates the signal onto `n` evenly spaced x values spanning the original range.
+/// Samples don't need to be sorted and samples sharing an x value are averaged.
+pub fn resample(n: usize, mut data: Signal) -> Signal {
+    data.sort_by(|a, b| a.x.total_cmp(&b.x));
+    let mut merged: Vec<(f64, f64, usize)> = vec![];
+    for r in data {
+        match merged.last_mut() {
+            Some((x, y, count)) if *x == r.x => {
+                *y += r.y;
+                *count += 1;
+            }
+            _ => merged.push((r.x, r.y, 1)),
+        }
+    }
+    let points: Vec<Record> = merged
+        .into_iter()
+        .map(|(x, y, count)| Record::new(x, y / count as f64))
+        .collect();
+    if n == 0 || points.is_empty() {
+        return vec![];
+    }
+    if n == 1 || points.len() == 1 {
+        return vec![points[0]];
+    }
+
+    let x_min = points[0].x;
+    let x_max = points[points.len() - 1].x;
+    let mut segment = 0;
+    (0..n)
+        .map(|i| {
+            let x = x_min + (x_max - x_min) * i as f64 / (n - 1) as f64;
+            while segment + 2 < points.len() && points[segment + 1].x < x {
+                segment += 1;
+            }
+            let (a, b) = (points[segment], points[segment + 1]);
+            let t = ((x - a.x) / (b.x - a.x)).clamp(0.0, 1.0);
+            Record::new(x, a.y + t * (b.y - a.y))
+        })
+        .collect()
+}
+
+/// Local maxima standing at least `min_prominence` above the higher of the two valleys
+/// separating them from taller terrain. When peaks are closer than `min_distance` samples
+/// only the tallest is kept.
+pub fn find_peaks(data: Signal, min_prominence: f64, min_distance: usize) -> Signal {
+    let mut candidates: Vec<usize> = (1..data.len().saturating_sub(1))
+        .filter(|&i| data[i].y > data[i - 1].y && data[i].y >= data[i + 1].y)
+        .filter(|&i| prominence(&data, i) >= min_prominence)
+        .collect();
+    candidates.sort_by(|a, b| data[*b].y.total_cmp(&data[*a].y));
+
+    let mut peaks: Vec<usize> = vec![];
+    for i in candidates {
+        if peaks.iter().all(|p| p.abs_diff(i) >= min_distance) {
+            peaks.push(i);
+        }
+    }
+    peaks.sort();
+    peaks.into_iter().map(|i| data[i]).collect()
+}
+
+fn prominence(data: &Signal, peak: usize) -> f64 {
+    let height = data[peak].y;
+    let mut left_min = height;
+    for r in data[..peak].iter().rev() {
+        if r.y > height {
+            break;
+        }
+        left_min = left_min.min(r.y);
+    }
+    let mut right_min = height;
+    for r in &data[peak + 1..] {
+        if r.y > height {
+            break;
+        }
+        right_min = right_min.min(r.y);
+    }
+    height - left_min.max(right_min)
+}
+
 fn scale_axis(axis: super::AxisSelection, factor: f64, mut vec: Signal) -> Signal {
     for sample in &mut vec {
         match axis {
@@ -210,14 +439,22 @@ pub fn run_pipeline(
     pipeline: &[StepConfig],
     input: PipelineIntermediate,
 ) -> Result<PipelineIntermediate> {
-    let mut out = input.clone();
-    for step in pipeline {
+    let mut out = input;
+    for (i, step) in pipeline.iter().enumerate() {
+        if step.input_kind() != out.kind() {
+            return Err(anyhow!(
+                "Step {} ({step}) expects {:?} input but received {:?}",
+                i + 1,
+                step.input_kind(),
+                out.kind()
+            ));
+        }
         out = match out {
             PipelineIntermediate::DataFrame(df) => match step {
                 StepConfig::PickColumns { column_1, column_2 } => {
                     PipelineIntermediate::Signal(df.pick(*column_1, *column_2))
                 }
-                _ => return Err(anyhow!("The pipeline expected a dataframe at this step.")),
+                _ => return Err(anyhow!("Step {} ({step}) can't be run yet", i + 1)),
             },
             PipelineIntermediate::Signal(vec) => match step {
                 StepConfig::Average => PipelineIntermediate::Signal(vec![Record {
@@ -242,7 +479,23 @@ pub fn run_pipeline(
                 StepConfig::LogAxis { axis, base } => {
                     PipelineIntermediate::Signal(log_axis(*axis, *base, vec))
                 }
-                _ => return Err(anyhow!("The pipeline didn't expect a signal at this step.")),
+                StepConfig::Derivative => PipelineIntermediate::Signal(derivative(vec)),
+                StepConfig::Integrate => PipelineIntermediate::Signal(integrate(vec)),
+                StepConfig::ApplyWindow { kind } => {
+                    PipelineIntermediate::Signal(apply_window(*kind, vec))
+                }
+                StepConfig::FindPeaks {
+                    min_prominence,
+                    min_distance,
+                } => PipelineIntermediate::Signal(find_peaks(vec, *min_prominence, *min_distance)),
+                StepConfig::MedianFilter { window } => {
+                    PipelineIntermediate::Signal(median_filter(*window, vec))
+                }
+                StepConfig::Resample { n } => PipelineIntermediate::Signal(resample(*n, vec)),
+                StepConfig::Spectrogram { window, hop } => {
+                    PipelineIntermediate::DataFrame(spectrogram(vec, *window, *hop))
+                }
+                _ => return Err(anyhow!("Step {} ({step}) can't be run yet", i + 1)),
             },
             PipelineIntermediate::Complex(vec) => match step {
                 StepConfig::SmoothReals { window } => {
@@ -261,11 +514,7 @@ pub fn run_pipeline(
                 StepConfig::BandpassFilter { middle, half_width } => PipelineIntermediate::Complex(
                     bandpass_filter(vec, middle - half_width, middle + half_width),
                 ),
-                _ => {
-                    return Err(anyhow!(
-                        "The pipeline didn't expect a complex series at this step."
-                    ));
-                }
+                _ => return Err(anyhow!("Step {} ({step}) can't be run yet", i + 1)),
             },
         }
     }
@@ -27,7 +27,7 @@ use tracing::{error, info};
 use crate::{
     app::{App, AppMessage},
     pipeline::{
-        AxisSelection, DataFrame, PipelineIntermediate, Record, SignalKind, StepConfig,
+        AxisSelection, DataFrame, PipelineIntermediate, Record, SignalKind, StepConfig, WindowKind,
         processing::{average, run_pipeline},
     },
 };
@@ -218,6 +218,77 @@ impl PipelineManagerUi {
                     })),
                 )],
             ),
+            StepConfig::MedianFilter { window: _ } => b.div(
+                "flex-col gap-4",
+                &[
+                    b.text("", Text::new("Window size", 12, COLOR_LIGHT)),
+                    b.text_field(
+                        id!("cfg-{step_id}-median"),
+                        focused_id,
+                        Some(Arc::new(move |app, data| {
+                            if let Ok(new_window) = data.contents.parse() {
+                                app.pipeline_manager.set_cfg_step(
+                                    StepConfig::MedianFilter { window: new_window },
+                                    step_idx,
+                                );
+                            }
+                        })),
+                    ),
+                ],
+            ),
+            StepConfig::Resample { n: _ } => b.div(
+                "flex-col gap-4",
+                &[
+                    b.text("", Text::new("Number of points", 12, COLOR_LIGHT)),
+                    b.text_field(
+                        id!("cfg-{step_id}-resample"),
+                        focused_id,
+                        Some(Arc::new(move |app, data| {
+                            if let Ok(new_n) = data.contents.parse() {
+                                app.pipeline_manager
+                                    .set_cfg_step(StepConfig::Resample { n: new_n }, step_idx);
+                            }
+                        })),
+                    ),
+                ],
+            ),
+            StepConfig::Spectrogram { window, hop } => b.div(
+                "flex-col gap-4",
+                &[
+                    b.text("", Text::new("Window size", 12, COLOR_LIGHT)),
+                    b.text_field(
+                        id!("cfg-{step_id}-spectrogram-window"),
+                        focused_id,
+                        Some(Arc::new(move |app, data| {
+                            if let Ok(new_window) = data.contents.parse() {
+                                app.pipeline_manager.set_cfg_step(
+                                    StepConfig::Spectrogram {
+                                        window: new_window,
+                                        hop,
+                                    },
+                                    step_idx,
+                                );
+                            }
+                        })),
+                    ),
+                    b.text("", Text::new("Hop", 12, COLOR_LIGHT)),
+                    b.text_field(
+                        id!("cfg-{step_id}-spectrogram-hop"),
+                        focused_id,
+                        Some(Arc::new(move |app, data| {
+                            if let Ok(new_hop) = data.contents.parse() {
+                                app.pipeline_manager.set_cfg_step(
+                                    StepConfig::Spectrogram {
+                                        window,
+                                        hop: new_hop,
+                                    },
+                                    step_idx,
+                                );
+                            }
+                        })),
+                    ),
+                ],
+            ),
             StepConfig::SmoothReals { window } => b.div(
                 "flex-col gap-4",
                 &[
@@ -243,6 +314,8 @@ impl PipelineManagerUi {
             StepConfig::SkipFirstEntry => b.div("", &[]),
             StepConfig::SkipFirstComplexEntry => b.div("", &[]),
             StepConfig::Normalize => b.div("", &[]),
+            StepConfig::Derivative => b.div("", &[]),
+            StepConfig::Integrate => b.div("", &[]),
             StepConfig::BandpassFilter { middle, half_width } => b.div(
                 "flex-col gap-4",
                 &[
@@ -408,6 +481,68 @@ impl PipelineManagerUi {
                     ),
                 ],
             ),
+            StepConfig::ApplyWindow { kind } => b.div(
+                "flex-col gap-4",
+                &[
+                    b.text("", Text::new("Window", 12, COLOR_LIGHT)),
+                    b.select(
+                        id!("cfg-{step_id}-window-kind"),
+                        Some(kind),
+                        &[
+                            WindowKind::Hann,
+                            WindowKind::Hamming,
+                            WindowKind::Blackman,
+                            WindowKind::Rectangular,
+                        ],
+                        Some(Arc::new(move |app, _, selected| {
+                            app.pipeline_manager.set_cfg_step(
+                                StepConfig::ApplyWindow { kind: *selected },
+                                step_idx,
+                            );
+                        })),
+                    ),
+                ],
+            ),
+            StepConfig::FindPeaks {
+                min_prominence,
+                min_distance,
+            } => b.div(
+                "flex-col gap-4",
+                &[
+                    b.text("", Text::new("Minimum prominence", 12, COLOR_LIGHT)),
+                    b.text_field(
+                        id!("cfg-{step_id}-peaks-prominence"),
+                        focused_id,
+                        Some(Arc::new(move |app, data| {
+                            if let Ok(new_prominence) = data.contents.parse() {
+                                app.pipeline_manager.set_cfg_step(
+                                    StepConfig::FindPeaks {
+                                        min_prominence: new_prominence,
+                                        min_distance,
+                                    },
+                                    step_idx,
+                                );
+                            }
+                        })),
+                    ),
+                    b.text("", Text::new("Minimum distance", 12, COLOR_LIGHT)),
+                    b.text_field(
+                        id!("cfg-{step_id}-peaks-distance"),
+                        focused_id,
+                        Some(Arc::new(move |app, data| {
+                            if let Ok(new_distance) = data.contents.parse() {
+                                app.pipeline_manager.set_cfg_step(
+                                    StepConfig::FindPeaks {
+                                        min_prominence,
+                                        min_distance: new_distance,
+                                    },
+                                    step_idx,
+                                );
+                            }
+                        })),
+                    ),
+                ],
+            ),
         };
 
         let mut inner = vec![b.div(